@@ -0,0 +1,587 @@
+//! Pure computations over stored price series.
+//!
+//! Anything awkward to express in SQL lives here and operates on plain
+//! in-memory series pulled from the repository.
+
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::models::DailyBar;
+
+// ── Price series ──────────────────────────────────────────────────────────────
+
+/// A date-ordered close series for one symbol.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PriceSeries {
+    pub dates: Vec<NaiveDate>,
+    pub closes: Vec<f64>,
+}
+
+impl PriceSeries {
+    pub fn new(points: Vec<(NaiveDate, f64)>) -> Self {
+        let (dates, closes) = points.into_iter().unzip();
+        Self { dates, closes }
+    }
+
+    /// Largest peak-to-trough decline as `(peak_date, trough_date, magnitude)`,
+    /// where magnitude is a fraction of the peak (0.25 = 25% drawdown).
+    /// A series that never falls reports 0 at its first date; empty → `None`.
+    pub fn max_drawdown(&self) -> Option<(NaiveDate, NaiveDate, f64)> {
+        let first = *self.dates.first()?;
+        let mut best = (first, first, 0.0);
+        let mut peak_idx = 0;
+
+        for (i, &close) in self.closes.iter().enumerate() {
+            if close > self.closes[peak_idx] {
+                peak_idx = i;
+                continue;
+            }
+            let peak = self.closes[peak_idx];
+            let drawdown = (peak - close) / peak;
+            if drawdown > best.2 {
+                best = (self.dates[peak_idx], self.dates[i], drawdown);
+            }
+        }
+
+        Some(best)
+    }
+}
+
+// ── Alignment & rebasing ──────────────────────────────────────────────────────
+
+/// Several symbols' closes on the dates they all share.
+/// `closes[i][j]` is symbol `i` on `dates[j]`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AlignedSeries {
+    pub dates: Vec<NaiveDate>,
+    pub closes: Vec<Vec<f64>>,
+}
+
+/// Inner-join series on date (each input must be date-ordered, as the
+/// repository returns them).
+pub fn align(series: &[PriceSeries]) -> AlignedSeries {
+    let Some((first, rest)) = series.split_first() else {
+        return AlignedSeries::default();
+    };
+    let lookups: Vec<HashMap<NaiveDate, f64>> = rest
+        .iter()
+        .map(|s| s.dates.iter().copied().zip(s.closes.iter().copied()).collect())
+        .collect();
+
+    let mut out = AlignedSeries {
+        dates: Vec::new(),
+        closes: vec![Vec::new(); series.len()],
+    };
+    for (&date, &close) in first.dates.iter().zip(&first.closes) {
+        let others: Option<Vec<f64>> = lookups.iter().map(|m| m.get(&date).copied()).collect();
+        let Some(others) = others else { continue };
+        out.dates.push(date);
+        out.closes[0].push(close);
+        for (i, c) in others.into_iter().enumerate() {
+            out.closes[i + 1].push(c);
+        }
+    }
+    out
+}
+
+impl AlignedSeries {
+    /// Scale every series to 100 at the first shared date on or after `base`
+    /// (or the earliest shared date), dropping earlier dates. `None` when no
+    /// shared date qualifies.
+    pub fn rebased(&self, base: Option<NaiveDate>) -> Option<AlignedSeries> {
+        let start = match base {
+            Some(b) => self.dates.iter().position(|d| *d >= b)?,
+            None if self.dates.is_empty() => return None,
+            None => 0,
+        };
+        Some(AlignedSeries {
+            dates: self.dates[start..].to_vec(),
+            closes: self
+                .closes
+                .iter()
+                .map(|c| c[start..].iter().map(|v| v / c[start] * 100.0).collect())
+                .collect(),
+        })
+    }
+}
+
+// ── Split detection ───────────────────────────────────────────────────────────
+
+/// Price ratios left by common splits and bonus issues (2:1, 3:1, 10:1, 3:2,
+/// and the 1-for-4 bonus frequent on NGX).
+const SPLIT_RATIOS: &[f64] = &[1.25, 1.5, 2.0, 3.0, 4.0, 5.0, 10.0, 20.0];
+
+/// If the move from `prev_close` to `close` is within `tolerance_pct` of a
+/// common split ratio, that ratio as `prev / close` (above 1 for a split,
+/// below 1 for a reverse split).
+pub fn split_ratio_match(prev_close: f64, close: f64, tolerance_pct: f64) -> Option<f64> {
+    if prev_close <= 0.0 || close <= 0.0 {
+        return None;
+    }
+    let ratio = prev_close / close;
+    let magnitude = ratio.max(1.0 / ratio);
+    let matched = SPLIT_RATIOS
+        .iter()
+        .find(|&&r| (magnitude / r - 1.0).abs() * 100.0 <= tolerance_pct)?;
+    Some(if ratio >= 1.0 { *matched } else { 1.0 / matched })
+}
+
+// ── Moving averages & signals ─────────────────────────────────────────────────
+
+/// Simple moving average over the trailing `window` values. Entries before
+/// the first full window are `None`.
+pub fn sma(values: &[f64], window: usize) -> Vec<Option<f64>> {
+    let mut out = vec![None; values.len()];
+    if window == 0 || values.len() < window {
+        return out;
+    }
+    let mut sum: f64 = values[..window].iter().sum();
+    out[window - 1] = Some(sum / window as f64);
+    for i in window..values.len() {
+        sum += values[i] - values[i - window];
+        out[i] = Some(sum / window as f64);
+    }
+    out
+}
+
+/// Exponential moving average with smoothing `2 / (period + 1)`, seeded with
+/// the simple average of the first `period` values. Entries before that seed
+/// (the warm-up) are `None`.
+pub fn ema(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut out = vec![None; values.len()];
+    if period == 0 || values.len() < period {
+        return out;
+    }
+    let alpha = 2.0 / (period as f64 + 1.0);
+    let mut prev = values[..period].iter().sum::<f64>() / period as f64;
+    out[period - 1] = Some(prev);
+    for i in period..values.len() {
+        prev = alpha * values[i] + (1.0 - alpha) * prev;
+        out[i] = Some(prev);
+    }
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum IndicatorKind {
+    /// Simple moving average
+    Sma,
+    /// Exponential moving average (SMA-seeded)
+    Ema,
+}
+
+impl IndicatorKind {
+    pub fn compute(self, values: &[f64], window: usize) -> Vec<Option<f64>> {
+        match self {
+            IndicatorKind::Sma => sma(values, window),
+            IndicatorKind::Ema => ema(values, window),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignalKind {
+    Buy,
+    Sell,
+}
+
+/// A crossover at `date_idx` (index into the input closes).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signal {
+    pub date_idx: usize,
+    pub kind: SignalKind,
+}
+
+/// Buy when the fast EMA crosses above the slow one, sell when it crosses
+/// below. A signal fires only on the bar where the sign of `fast - slow`
+/// flips; the first bar with both EMAs valid just sets the starting side,
+/// and bars where they are equal keep the previous side.
+pub fn ema_crossover(closes: &[f64], fast: usize, slow: usize) -> Vec<Signal> {
+    let (fast_ema, slow_ema) = (ema(closes, fast), ema(closes, slow));
+    let mut signals = Vec::new();
+    let mut side: Option<bool> = None; // true = fast above slow
+
+    for (i, (f, s)) in fast_ema.iter().zip(&slow_ema).enumerate() {
+        let (Some(f), Some(s)) = (f, s) else { continue };
+        if f == s {
+            continue;
+        }
+        let above = f > s;
+        if let Some(was_above) = side
+            && was_above != above
+        {
+            let kind = if above { SignalKind::Buy } else { SignalKind::Sell };
+            signals.push(Signal { date_idx: i, kind });
+        }
+        side = Some(above);
+    }
+    signals
+}
+
+// ── Returns ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReturnKind {
+    /// close / prev_close - 1
+    Simple,
+    /// ln(close / prev_close)
+    Log,
+}
+
+/// Close-to-close returns of date-ordered `bars`, dated on the later bar.
+/// The first bar has no prior close and yields nothing. Closes are validated
+/// positive on load, so a zero prior close is a bug: it trips a debug
+/// assertion and is skipped in release builds rather than dividing by zero.
+pub fn daily_returns(bars: &[DailyBar], kind: ReturnKind) -> Vec<(NaiveDate, f64)> {
+    bars.windows(2)
+        .filter_map(|w| {
+            let (prev, cur) = (w[0].close, w[1].close);
+            debug_assert!(prev > 0.0, "non-positive close for {} on {}", w[0].symbol, w[0].date);
+            if prev <= 0.0 {
+                return None;
+            }
+            let r = match kind {
+                ReturnKind::Simple => cur / prev - 1.0,
+                ReturnKind::Log => (cur / prev).ln(),
+            };
+            Some((w[1].date, r))
+        })
+        .collect()
+}
+
+/// Bars whose close moved more than `max_daily_move_pct` percent from the
+/// prior close (either way), with that move in percent. Meant for spotting bad
+/// scrapes such as a misplaced decimal; the first bar has no prior and is
+/// never flagged.
+pub fn flag_outliers(bars: &[DailyBar], max_daily_move_pct: f64) -> Vec<(NaiveDate, f64)> {
+    daily_returns(bars, ReturnKind::Simple)
+        .into_iter()
+        .map(|(date, r)| (date, r * 100.0))
+        .filter(|(_, pct)| pct.abs() > max_daily_move_pct)
+        .collect()
+}
+
+// ── Market aggregates ─────────────────────────────────────────────────────────
+
+/// A crude all-share proxy: each day's level moves by the mean simple return
+/// of the symbols that traded that day (each measured from its own previous
+/// bar), starting at 100 on the earliest date. Symbols join as they list and
+/// drop out as they stop trading; a date where only first bars appear keeps
+/// the prior level.
+pub fn equal_weight_index(
+    bars_by_symbol: &HashMap<String, Vec<DailyBar>>,
+) -> Vec<(NaiveDate, f64)> {
+    // date → (sum of returns, symbols contributing)
+    let mut days: BTreeMap<NaiveDate, (f64, usize)> = BTreeMap::new();
+    for bars in bars_by_symbol.values() {
+        if let Some(first) = bars.first() {
+            days.entry(first.date).or_default();
+        }
+        for (date, r) in daily_returns(bars, ReturnKind::Simple) {
+            let day = days.entry(date).or_default();
+            day.0 += r;
+            day.1 += 1;
+        }
+    }
+
+    let mut level = 100.0;
+    days.into_iter()
+        .map(|(date, (sum, n))| {
+            if n > 0 {
+                level *= 1.0 + sum / n as f64;
+            }
+            (date, level)
+        })
+        .collect()
+}
+
+// ── Volume ────────────────────────────────────────────────────────────────────
+
+/// Rolling VWAP of date-ordered `bars`: sum(price × volume) / sum(volume) over
+/// the trailing `window` bars, one entry per bar, priced at
+/// [`DailyBar::typical_price`] (the close, for most free history). Early bars use
+/// whatever history there is; `None` when no bar in the window traded.
+pub fn vwap(bars: &[DailyBar], window: usize) -> Vec<(NaiveDate, Option<f64>)> {
+    let window = window.max(1);
+    bars.iter()
+        .enumerate()
+        .map(|(i, bar)| {
+            let (turnover, volume) = bars[(i + 1).saturating_sub(window)..=i]
+                .iter()
+                .filter_map(|b| b.volume.filter(|&v| v > 0).map(|v| (b.typical_price(), v as f64)))
+                .fold((0.0, 0.0), |(t, n), (price, v)| (t + price * v, n + v));
+            (bar.date, (volume > 0.0).then(|| turnover / volume))
+        })
+        .collect()
+}
+
+// ── FX ────────────────────────────────────────────────────────────────────────
+
+/// Annualised change implied by moving from `from_rate` to `to_rate` over
+/// `days` calendar days: `(to / from)^(365 / days) - 1`, as a fraction.
+/// `None` for a non-positive rate or span.
+pub fn annualised_change(from_rate: f64, to_rate: f64, days: i64) -> Option<f64> {
+    if from_rate <= 0.0 || to_rate <= 0.0 || days <= 0 {
+        return None;
+    }
+    Some((to_rate / from_rate).powf(365.0 / days as f64) - 1.0)
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(closes: &[f64]) -> PriceSeries {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        PriceSeries::new(
+            closes
+                .iter()
+                .enumerate()
+                .map(|(i, &c)| (start + chrono::Days::new(i as u64), c))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_max_drawdown_picks_largest_decline() {
+        let s = series(&[100.0, 120.0, 90.0, 110.0, 130.0, 104.0]);
+        let (peak, trough, dd) = s.max_drawdown().unwrap();
+        assert_eq!(peak, s.dates[1]);
+        assert_eq!(trough, s.dates[2]);
+        assert!((dd - 0.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_max_drawdown_strictly_increasing_is_zero() {
+        let s = series(&[10.0, 11.0, 12.5, 13.0]);
+        assert_eq!(s.max_drawdown(), Some((s.dates[0], s.dates[0], 0.0)));
+        assert_eq!(PriceSeries::default().max_drawdown(), None);
+    }
+
+    #[test]
+    fn test_ema_warm_up_and_constant_series() {
+        let e = ema(&[5.0; 6], 3);
+        assert_eq!(&e[..2], &[None, None]);
+        assert!(e[2..].iter().all(|v| *v == Some(5.0)));
+        assert!(ema(&[1.0, 2.0], 3).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_ema_crossover_emits_on_sign_flip_only() {
+        // Down, up, down again: one buy on the way up, one sell on the way down.
+        let closes = [
+            10.0, 9.0, 8.0, 7.0, 6.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 10.0, 9.0, 8.0, 7.0,
+            6.0, 5.0,
+        ];
+        let signals = ema_crossover(&closes, 2, 4);
+        let kinds: Vec<SignalKind> = signals.iter().map(|s| s.kind).collect();
+        assert_eq!(kinds, vec![SignalKind::Buy, SignalKind::Sell]);
+        assert!((6..=8).contains(&signals[0].date_idx));
+        assert!((12..=14).contains(&signals[1].date_idx));
+
+        // Monotonic series never crosses.
+        let rising: Vec<f64> = (0..30).map(|i| 10.0 + i as f64).collect();
+        assert!(ema_crossover(&rising, 3, 8).is_empty());
+    }
+
+    #[test]
+    fn test_align_and_rebase_on_common_dates() {
+        let a = series(&[10.0, 11.0, 12.0, 13.0]);
+        let mut b = series(&[50.0, 55.0, 60.0, 40.0]);
+        b.dates.remove(0);
+        b.closes.remove(0); // b starts one day later
+
+        let aligned = align(&[a.clone(), b]);
+        assert_eq!(aligned.dates, a.dates[1..].to_vec());
+
+        let rebased = aligned.rebased(None).unwrap();
+        assert_eq!(rebased.closes[0], vec![100.0, 12.0 / 11.0 * 100.0, 13.0 / 11.0 * 100.0]);
+        assert_eq!(rebased.closes[1], vec![100.0, 60.0 / 55.0 * 100.0, 40.0 / 55.0 * 100.0]);
+
+        let from_third = aligned.rebased(Some(a.dates[2])).unwrap();
+        assert_eq!(from_third.dates.len(), 2);
+        assert_eq!(from_third.closes[1][0], 100.0);
+        assert!(aligned.rebased(Some(a.dates[3] + chrono::Days::new(1))).is_none());
+    }
+
+    #[test]
+    fn test_flag_outliers_uses_prior_close() {
+        let bars: Vec<DailyBar> = series(&[10.0, 100.0, 10.5, 11.0])
+            .dates
+            .iter()
+            .zip([10.0, 100.0, 10.5, 11.0])
+            .map(|(&date, close)| DailyBar {
+                symbol: "GTCO".to_string(),
+                exchange: "NGX".to_string(),
+                date,
+                open: None,
+                high: None,
+                low: None,
+                close,
+                change_pct: None,
+                volume: None,
+                scraped_at: chrono::Utc::now().naive_utc(),
+            })
+            .collect();
+
+        let flagged = flag_outliers(&bars, 25.0);
+        assert_eq!(flagged.len(), 2);
+        assert_eq!(flagged[0].0, bars[1].date);
+        assert!((flagged[0].1 - 900.0).abs() < 1e-9);
+        assert!((flagged[1].1 + 89.5).abs() < 1e-9);
+        assert!(flag_outliers(&bars[..1], 25.0).is_empty());
+    }
+
+    #[test]
+    fn test_sma_warms_up_then_slides() {
+        let out = sma(&[1.0, 2.0, 3.0, 4.0, 5.0], 3);
+        assert_eq!(out, [None, None, Some(2.0), Some(3.0), Some(4.0)]);
+        assert_eq!(sma(&[1.0, 2.0], 3), [None, None]);
+        assert_eq!(sma(&[1.0], 0), [None]);
+    }
+
+    #[test]
+    fn test_typical_price_and_range_fall_back_without_ohlc() {
+        let mut bar = DailyBar {
+            symbol: "GTCO".to_string(),
+            exchange: "NGX".to_string(),
+            date: NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+            open: None,
+            high: Some(12.0),
+            low: Some(9.0),
+            close: 10.5,
+            change_pct: None,
+            volume: None,
+            scraped_at: chrono::Utc::now().naive_utc(),
+        };
+        assert!((bar.typical_price() - 10.5).abs() < 1e-12);
+        assert_eq!(bar.range(), Some(3.0));
+
+        bar.low = None;
+        assert_eq!(bar.typical_price(), 10.5);
+        assert_eq!(bar.range(), None);
+    }
+
+    #[test]
+    fn test_vwap_weights_by_volume_and_skips_untraded_windows() {
+        let bars: Vec<DailyBar> = series(&[10.0, 20.0, 30.0, 40.0])
+            .dates
+            .iter()
+            .zip([(10.0, Some(100)), (20.0, Some(300)), (30.0, None), (40.0, Some(0))])
+            .map(|(&date, (close, volume))| DailyBar {
+                symbol: "GTCO".to_string(),
+                exchange: "NGX".to_string(),
+                date,
+                open: None,
+                high: None,
+                low: None,
+                close,
+                change_pct: None,
+                volume,
+                scraped_at: chrono::Utc::now().naive_utc(),
+            })
+            .collect();
+
+        let v = vwap(&bars, 2);
+        assert_eq!(v.len(), 4);
+        assert_eq!(v[0], (bars[0].date, Some(10.0)));
+        assert!((v[1].1.unwrap() - 17.5).abs() < 1e-12);
+        assert_eq!(v[2].1, Some(20.0));
+        assert_eq!(v[3].1, None);
+
+        // A window longer than the history just covers all of it.
+        assert!((vwap(&bars, 50)[3].1.unwrap() - 17.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_daily_returns_skip_first_bar() {
+        let bars: Vec<DailyBar> = series(&[100.0, 110.0, 99.0])
+            .dates
+            .iter()
+            .zip([100.0, 110.0, 99.0])
+            .map(|(&date, close)| DailyBar {
+                symbol: "GTCO".to_string(),
+                exchange: "NGX".to_string(),
+                date,
+                open: None,
+                high: None,
+                low: None,
+                close,
+                change_pct: None,
+                volume: None,
+                scraped_at: chrono::Utc::now().naive_utc(),
+            })
+            .collect();
+
+        let simple = daily_returns(&bars, ReturnKind::Simple);
+        assert_eq!(simple.len(), 2);
+        assert_eq!(simple[0].0, bars[1].date);
+        assert!((simple[0].1 - 0.1).abs() < 1e-12);
+        assert!((simple[1].1 + 0.1).abs() < 1e-12);
+
+        let log = daily_returns(&bars, ReturnKind::Log);
+        assert!((log[0].1 - 1.1f64.ln()).abs() < 1e-12);
+        assert!(daily_returns(&bars[..1], ReturnKind::Log).is_empty());
+    }
+
+    #[test]
+    fn test_equal_weight_index_averages_daily_returns() {
+        let dates = series(&[0.0; 3]).dates;
+        let bars = |symbol: &str, closes: &[(usize, f64)]| -> Vec<DailyBar> {
+            closes
+                .iter()
+                .map(|&(day, close)| DailyBar {
+                    symbol: symbol.to_string(),
+                    exchange: "NGX".to_string(),
+                    date: dates[day],
+                    open: None,
+                    high: None,
+                    low: None,
+                    close,
+                    change_pct: None,
+                    volume: None,
+                    scraped_at: chrono::Utc::now().naive_utc(),
+                })
+                .collect()
+        };
+        let by_symbol = HashMap::from([
+            ("GTCO".to_string(), bars("GTCO", &[(0, 10.0), (1, 11.0), (2, 11.0)])),
+            // Lists a day late: its first bar adds no return on day 1
+            ("MTNN".to_string(), bars("MTNN", &[(1, 200.0), (2, 180.0)])),
+        ]);
+
+        let index = equal_weight_index(&by_symbol);
+        assert_eq!(index.len(), 3);
+        assert_eq!(index[0], (dates[0], 100.0));
+        assert!((index[1].1 - 110.0).abs() < 1e-9);
+        // Day 2: GTCO flat, MTNN -10% → mean -5%
+        assert!((index[2].1 - 104.5).abs() < 1e-9);
+        assert!(equal_weight_index(&HashMap::new()).is_empty());
+    }
+
+    #[test]
+    fn test_split_ratio_match() {
+        assert_eq!(split_ratio_match(100.0, 50.5, 2.0), Some(2.0));
+        assert_eq!(split_ratio_match(10.0, 100.0, 2.0), Some(0.1));
+        assert_eq!(split_ratio_match(12.5, 10.0, 1.0), Some(1.25));
+        // A 10% limit-down day is not a split.
+        assert_eq!(split_ratio_match(100.0, 90.0, 3.0), None);
+    }
+
+    #[test]
+    fn test_annualised_change_guards_degenerate_inputs() {
+        let full_year = annualised_change(1000.0, 1500.0, 365).unwrap();
+        assert!((full_year - 0.5).abs() < 1e-12);
+        // 10% over half a year compounds to 21% a year.
+        let half = annualised_change(1000.0, 1100.0, 365 / 2).unwrap();
+        assert!((half - 0.21).abs() < 0.005);
+        assert_eq!(annualised_change(1000.0, 1100.0, 0), None);
+        assert_eq!(annualised_change(0.0, 1100.0, 30), None);
+        assert_eq!(annualised_change(1000.0, -1.0, 30), None);
+    }
+}
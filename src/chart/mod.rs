@@ -0,0 +1,148 @@
+//! PNG price charts for quick visual checks, without exporting to Python.
+
+use crate::models::DailyBar;
+use anyhow::{anyhow, Result};
+use chrono::{Duration, NaiveDate};
+use clap::ValueEnum;
+use plotters::prelude::*;
+use std::path::Path;
+use tracing::warn;
+
+const SIZE: (u32, u32) = (1280, 720);
+
+/// Most bars worth plotting: about one per horizontal pixel. Longer ranges
+/// are downsampled by the repository (see `Repository::bars_in_range`).
+pub const MAX_POINTS: usize = SIZE.0 as usize;
+
+/// Upper bound on x-axis labels; plotters picks evenly spaced dates, so long
+/// histories stay readable.
+const MAX_X_LABELS: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ChartKind {
+    /// Close price line
+    Line,
+    /// OHLC candlesticks (falls back to a line when OHLC is missing)
+    Candle,
+}
+
+/// True when every bar has open/high/low, i.e. candles can be drawn.
+fn has_ohlc(bars: &[DailyBar]) -> bool {
+    bars.iter()
+        .all(|b| b.open.is_some() && b.high.is_some() && b.low.is_some())
+}
+
+/// Price axis bounds with a little headroom, covering highs/lows when present.
+fn y_bounds(bars: &[DailyBar]) -> (f64, f64) {
+    let lo = bars
+        .iter()
+        .map(|b| b.low.unwrap_or(b.close).min(b.close))
+        .fold(f64::INFINITY, f64::min);
+    let hi = bars
+        .iter()
+        .map(|b| b.high.unwrap_or(b.close).max(b.close))
+        .fold(f64::NEG_INFINITY, f64::max);
+    let pad = ((hi - lo) * 0.05).max(hi.abs() * 0.01).max(0.01);
+    (lo - pad, hi + pad)
+}
+
+/// Render date-ordered `bars` to a PNG at `out`.
+pub fn render(symbol: &str, bars: &[DailyBar], kind: ChartKind, out: &Path) -> Result<()> {
+    let (Some(first), Some(last)) = (bars.first(), bars.last()) else {
+        return Ok(());
+    };
+
+    let kind = if kind == ChartKind::Candle && !has_ohlc(bars) {
+        warn!("{}: some bars lack open/high/low, drawing a line chart instead", symbol);
+        ChartKind::Line
+    } else {
+        kind
+    };
+
+    let root = BitMapBackend::new(out, SIZE).into_drawing_area();
+    root.fill(&WHITE).map_err(|e| anyhow!("chart: {}", e))?;
+
+    // Pad a day either side so a single bar still gives a non-empty axis.
+    let x_range: std::ops::Range<NaiveDate> =
+        (first.date - Duration::days(1))..(last.date + Duration::days(1));
+    let (y_lo, y_hi) = y_bounds(bars);
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(format!("{}  {} → {}", symbol, first.date, last.date), ("sans-serif", 24))
+        .margin(15)
+        .x_label_area_size(40)
+        .y_label_area_size(70)
+        .build_cartesian_2d(x_range, y_lo..y_hi)
+        .map_err(|e| anyhow!("chart: {}", e))?;
+
+    chart
+        .configure_mesh()
+        .x_labels(MAX_X_LABELS)
+        .x_label_formatter(&|d: &NaiveDate| d.format("%Y-%m-%d").to_string())
+        .y_label_formatter(&|v: &f64| format!("{:.2}", v))
+        .draw()
+        .map_err(|e| anyhow!("chart: {}", e))?;
+
+    match kind {
+        ChartKind::Line => {
+            chart
+                .draw_series(LineSeries::new(bars.iter().map(|b| (b.date, b.close)), &BLUE))
+                .map_err(|e| anyhow!("chart: {}", e))?;
+        }
+        ChartKind::Candle => {
+            // Candle width shrinks with history length; never below 1px.
+            let width = (SIZE.0 / bars.len() as u32).clamp(1, 12);
+            chart
+                .draw_series(bars.iter().map(|b| {
+                    CandleStick::new(
+                        b.date,
+                        b.open.unwrap_or(b.close),
+                        b.high.unwrap_or(b.close),
+                        b.low.unwrap_or(b.close),
+                        b.close,
+                        GREEN.filled(),
+                        RED.filled(),
+                        width,
+                    )
+                }))
+                .map_err(|e| anyhow!("chart: {}", e))?;
+        }
+    }
+
+    root.present().map_err(|e| anyhow!("chart: {}", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(close: f64, ohlc: Option<(f64, f64, f64)>) -> DailyBar {
+        DailyBar {
+            symbol: "DANGCEM".to_string(),
+            exchange: "NGX".to_string(),
+            date: NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+            open: ohlc.map(|o| o.0),
+            high: ohlc.map(|o| o.1),
+            low: ohlc.map(|o| o.2),
+            close,
+            change_pct: None,
+            volume: None,
+            scraped_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+
+    #[test]
+    fn test_candles_need_full_ohlc() {
+        let full = [bar(10.0, Some((9.0, 11.0, 8.0)))];
+        let partial = [bar(10.0, Some((9.0, 11.0, 8.0))), bar(10.5, None)];
+        assert!(has_ohlc(&full));
+        assert!(!has_ohlc(&partial));
+    }
+
+    #[test]
+    fn test_y_bounds_cover_highs_and_lows() {
+        let (lo, hi) = y_bounds(&[bar(10.0, Some((9.0, 12.0, 7.0))), bar(11.0, None)]);
+        assert!(lo < 7.0 && hi > 12.0);
+    }
+}
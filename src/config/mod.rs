@@ -1,13 +1,19 @@
-use anyhow::Result;
+use crate::pipeline::Hook;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use chrono::{Datelike, NaiveDate};
+use std::path::{Path, PathBuf};
 
-/// Top-level application configuration
+/// Top-level application configuration. Sections a file leaves out take
+/// their defaults, so a `--config` file only needs the keys it changes.
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
 pub struct AppConfig {
     pub scraper: ScraperConfig,
     pub storage: StorageConfig,
     pub pipeline: PipelineConfig,
+    pub calendar: CalendarConfig,
+    pub loader: LoaderConfig,
 }
 
 /// Scraper configuration
@@ -28,8 +34,40 @@ pub struct ScraperConfig {
     #[serde(default = "default_max_retries")]
     pub max_retries: u32,
 
+    /// Ceiling on a single retry sleep, jitter included
+    #[serde(default = "default_max_backoff_secs")]
+    pub max_backoff_secs: u64,
+
     #[serde(default = "default_user_agent")]
     pub user_agent: String,
+
+    /// Exchange code stamped on bars from this source (and CSV loads by default)
+    #[serde(default = "default_exchange")]
+    pub exchange: String,
+
+    /// Most recent bars kept from a ticker-page fetch (newest first), so upsert
+    /// volume stays predictable even if the page grows
+    #[serde(default = "default_recent_bars")]
+    pub recent_bars: usize,
+
+    /// Safety stop for listing pagination; the listing normally ends first,
+    /// when a page has no "next" link
+    #[serde(default = "default_max_listing_pages")]
+    pub max_listing_pages: u32,
+
+    /// Abort a fetch whose body grows past this many bytes
+    #[serde(default = "default_max_response_bytes")]
+    pub max_response_bytes: u64,
+
+    /// Seed for request jitter, making scrape timing reproducible; unset means
+    /// fresh randomness per run
+    #[serde(default)]
+    pub seed: Option<u64>,
+
+    /// Where to keep ETag/Last-Modified validators and bodies for conditional
+    /// GETs; unset disables the cache
+    #[serde(default)]
+    pub cache_dir: Option<PathBuf>,
 }
 
 /// Storage configuration
@@ -40,6 +78,20 @@ pub struct StorageConfig {
 
     #[serde(default = "default_true")]
     pub run_migrations: bool,
+
+    /// Record a summary row in `audit_log` for every mutating batch
+    #[serde(default = "default_true")]
+    pub audit: bool,
+
+    /// Extra attempts (with growing backoff) when another process holds the
+    /// database lock, e.g. `stats` while a cron `update` is writing
+    #[serde(default = "default_open_retries")]
+    pub open_retries: u32,
+
+    /// Extra attempts (with growing backoff) when a write loses a transaction
+    /// conflict to another writer
+    #[serde(default = "default_write_retries")]
+    pub write_retries: u32,
 }
 
 /// Pipeline configuration
@@ -53,6 +105,100 @@ pub struct PipelineConfig {
 
     #[serde(default = "default_true")]
     pub skip_up_to_date: bool,
+
+    /// Deadline for one symbol's whole fetch (all retries); a stuck page is
+    /// abandoned and counted as an error so its permit frees up
+    #[serde(default = "default_per_symbol_timeout_secs")]
+    pub per_symbol_timeout_secs: u64,
+
+    /// `daemon` runs a backfill instead of the daily update when the last
+    /// successful run is older than this (or there is none)
+    #[serde(default = "default_catch_up_after_hours")]
+    pub catch_up_after_hours: u64,
+
+    /// `daemon` serves Prometheus metrics on this port (needs the `metrics`
+    /// cargo feature); unset means no endpoint
+    #[serde(default)]
+    pub metrics_port: Option<u16>,
+
+    /// Post-ingest actions run in order after a successful load/update
+    #[serde(default)]
+    pub hooks: Vec<Hook>,
+
+    /// Only fetch bars for these symbols (e.g. the NGX-30); unset means all
+    /// listed symbols
+    #[serde(default)]
+    pub include_symbols: Option<Vec<String>>,
+
+    /// Never fetch bars for these symbols, even if included
+    #[serde(default)]
+    pub exclude_symbols: Vec<String>,
+}
+
+/// CSV dialect for the file loaders (investing.com defaults).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LoaderConfig {
+    /// Field separator, e.g. ';' or '\t' for European/tab-separated exports
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+
+    #[serde(default = "default_quote")]
+    pub quote: char,
+
+    /// Lines starting with this character are skipped
+    #[serde(default)]
+    pub comment: Option<char>,
+
+    /// Whether the first line is a header; when false it is loaded as data
+    #[serde(default = "default_true")]
+    pub has_headers: bool,
+}
+
+impl Default for LoaderConfig {
+    fn default() -> Self {
+        Self {
+            delimiter: default_delimiter(),
+            quote: default_quote(),
+            comment: None,
+            has_headers: true,
+        }
+    }
+}
+
+/// Exchange calendar: days on which no trading is expected besides weekends.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CalendarConfig {
+    /// Holidays recurring on the same date every year, as "MM-DD"
+    #[serde(default = "default_annual_holidays")]
+    pub annual_holidays: Vec<String>,
+
+    /// One-off or moving holidays (Easter, Eid, …) as ISO dates
+    #[serde(default)]
+    pub holidays: Vec<NaiveDate>,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            annual_holidays: default_annual_holidays(),
+            holidays: Vec::new(),
+        }
+    }
+}
+
+impl CalendarConfig {
+    /// All configured holidays falling in `year`.
+    pub fn holidays_in_year(&self, year: i32) -> Vec<NaiveDate> {
+        let annual = self.annual_holidays.iter().filter_map(|md| {
+            NaiveDate::parse_from_str(&format!("{}-{}", year, md.trim()), "%Y-%m-%d").ok()
+        });
+        let mut days: Vec<NaiveDate> = annual
+            .chain(self.holidays.iter().copied().filter(|d| d.year() == year))
+            .collect();
+        days.sort();
+        days.dedup();
+        days
+    }
 }
 
 // ── Defaults ─────────────────────────────────────────────────────────────────
@@ -72,9 +218,36 @@ fn default_jitter_ms() -> u64 {
 fn default_max_retries() -> u32 {
     3
 }
+fn default_max_backoff_secs() -> u64 {
+    60
+}
 fn default_user_agent() -> String {
     "ngx-trading-engine/0.1 (research project; full pipleine quantitative research)".to_string()
 }
+pub fn default_exchange() -> String {
+    "NGX".to_string()
+}
+fn default_recent_bars() -> usize {
+    30
+}
+fn default_max_listing_pages() -> u32 {
+    20
+}
+fn default_max_response_bytes() -> u64 {
+    16 * 1024 * 1024
+}
+fn default_open_retries() -> u32 {
+    5
+}
+fn default_write_retries() -> u32 {
+    3
+}
+fn default_delimiter() -> char {
+    ','
+}
+fn default_quote() -> char {
+    '"'
+}
 fn default_db_path() -> PathBuf {
     PathBuf::from("data/ngx.duckdb")
 }
@@ -84,31 +257,131 @@ fn default_true() -> bool {
 fn default_concurrency() -> usize {
     3
 }
+fn default_per_symbol_timeout_secs() -> u64 {
+    120
+}
+fn default_catch_up_after_hours() -> u64 {
+    72
+}
+/// Nigerian fixed-date public holidays: New Year, Workers' Day, Democracy Day,
+/// Independence Day, Christmas, Boxing Day.
+fn default_annual_holidays() -> Vec<String> {
+    ["01-01", "05-01", "06-12", "10-01", "12-25", "12-26"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// Upper bound on `scraper.max_retries` — beyond this the backoff sleeps run into hours.
+const MAX_RETRIES_CAP: u32 = 10;
 
 // ── Loader ───────────────────────────────────────────────────────────────────
 
 impl AppConfig {
-    /// Load configuration from file + environment overrides
-    pub fn load() -> Result<Self> {
+    /// Load configuration from file + environment overrides. Later layers win:
+    /// `config/default.toml`, then `config_file` (`--config`) if given or else
+    /// `config/local.toml`, then `NGX__*` environment variables. CLI flags
+    /// such as `--db` are applied on top by the caller.
+    pub fn load(config_file: Option<&Path>) -> Result<Self> {
         dotenv::dotenv().ok();
 
+        let overrides = match config_file {
+            Some(path) => {
+                anyhow::ensure!(path.is_file(), "Config file {:?} not found", path);
+                config::File::from(path).format(config::FileFormat::Toml)
+            }
+            None => config::File::with_name("config/local")
+                .required(false)
+                .format(config::FileFormat::Toml),
+        };
         let cfg = config::Config::builder()
             .add_source(
                 config::File::with_name("config/default")
                     .required(false)
                     .format(config::FileFormat::Toml),
             )
-            .add_source(
-                config::File::with_name("config/local")
-                    .required(false)
-                    .format(config::FileFormat::Toml),
-            )
+            .add_source(overrides)
             .add_source(config::Environment::with_prefix("NGX").separator("__"))
             .build()?;
 
-        let app_cfg: AppConfig = cfg.try_deserialize().unwrap_or_else(|_| AppConfig::default());
+        // A file named on the command line should fail loudly, not fall back.
+        let app_cfg: AppConfig = match (cfg.try_deserialize(), config_file) {
+            (Ok(app_cfg), _) => app_cfg,
+            (Err(e), Some(path)) => {
+                return Err(e).with_context(|| format!("Invalid config file {:?}", path));
+            }
+            (Err(_), None) => AppConfig::default(),
+        };
         Ok(app_cfg)
     }
+
+    /// Apply a `--db` CLI override. Written back into the config (not just passed
+    /// to `Repository::open`) so the pipeline sees the same path.
+    pub fn with_db_override(mut self, db_path: Option<PathBuf>) -> Self {
+        if let Some(path) = db_path {
+            self.storage.db_path = path;
+        }
+        self
+    }
+
+    /// Reject nonsensical settings up front instead of failing obscurely at runtime
+    /// (e.g. `concurrency = 0` would deadlock the fetch semaphore).
+    pub fn validate(&self) -> Result<()> {
+        if self.pipeline.concurrency < 1 {
+            anyhow::bail!("pipeline.concurrency must be >= 1 (got {})", self.pipeline.concurrency);
+        }
+        if self.pipeline.per_symbol_timeout_secs < 1 {
+            anyhow::bail!(
+                "pipeline.per_symbol_timeout_secs must be >= 1 (got {})",
+                self.pipeline.per_symbol_timeout_secs
+            );
+        }
+        if self.scraper.timeout_secs < 1 {
+            anyhow::bail!("scraper.timeout_secs must be >= 1 (got {})", self.scraper.timeout_secs);
+        }
+        if self.scraper.base_url.trim().is_empty() {
+            anyhow::bail!("scraper.base_url must not be empty");
+        }
+        url::Url::parse(&self.scraper.base_url)
+            .with_context(|| format!("scraper.base_url is not a valid URL: {:?}", self.scraper.base_url))?;
+        if self.scraper.max_backoff_secs < 1 {
+            anyhow::bail!(
+                "scraper.max_backoff_secs must be >= 1 (got {})",
+                self.scraper.max_backoff_secs
+            );
+        }
+        if self.scraper.recent_bars < 1 {
+            anyhow::bail!("scraper.recent_bars must be >= 1 (got {})", self.scraper.recent_bars);
+        }
+        if self.scraper.max_listing_pages < 1 {
+            anyhow::bail!(
+                "scraper.max_listing_pages must be >= 1 (got {})",
+                self.scraper.max_listing_pages
+            );
+        }
+        if self.scraper.max_response_bytes < 1 {
+            anyhow::bail!("scraper.max_response_bytes must be >= 1");
+        }
+        for (field, c) in [
+            ("loader.delimiter", Some(self.loader.delimiter)),
+            ("loader.quote", Some(self.loader.quote)),
+            ("loader.comment", self.loader.comment),
+        ] {
+            if let Some(c) = c
+                && !c.is_ascii()
+            {
+                anyhow::bail!("{} must be a single ASCII character (got {:?})", field, c);
+            }
+        }
+        if self.scraper.max_retries > MAX_RETRIES_CAP {
+            anyhow::bail!(
+                "scraper.max_retries must be <= {} (got {})",
+                MAX_RETRIES_CAP,
+                self.scraper.max_retries
+            );
+        }
+        Ok(())
+    }
 }
 
 impl Default for AppConfig {
@@ -120,17 +393,134 @@ impl Default for AppConfig {
                 request_delay_ms: default_request_delay_ms(),
                 jitter_ms: default_jitter_ms(),
                 max_retries: default_max_retries(),
+                max_backoff_secs: default_max_backoff_secs(),
                 user_agent: default_user_agent(),
+                exchange: default_exchange(),
+                recent_bars: default_recent_bars(),
+                max_listing_pages: default_max_listing_pages(),
+                max_response_bytes: default_max_response_bytes(),
+                seed: None,
+                cache_dir: None,
             },
             storage: StorageConfig {
                 db_path: default_db_path(),
                 run_migrations: true,
+                audit: true,
+                open_retries: default_open_retries(),
+                write_retries: default_write_retries(),
             },
             pipeline: PipelineConfig {
                 backfill: false,
                 concurrency: default_concurrency(),
                 skip_up_to_date: true,
+                per_symbol_timeout_secs: default_per_symbol_timeout_secs(),
+                catch_up_after_hours: default_catch_up_after_hours(),
+                metrics_port: None,
+                hooks: Vec::new(),
+                include_symbols: None,
+                exclude_symbols: Vec::new(),
             },
+            calendar: CalendarConfig::default(),
+            loader: LoaderConfig::default(),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert!(AppConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_db_override_replaces_configured_path() {
+        let cfg = AppConfig::default().with_db_override(Some(PathBuf::from("/tmp/other.duckdb")));
+        assert_eq!(cfg.storage.db_path, PathBuf::from("/tmp/other.duckdb"));
+
+        let cfg = AppConfig::default().with_db_override(None);
+        assert_eq!(cfg.storage.db_path, default_db_path());
+    }
+
+    #[test]
+    fn test_config_flag_file_overrides_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("flagged.toml");
+        std::fs::write(
+            &path,
+            "[storage]\ndb_path = \"/tmp/flagged.duckdb\"\n\n[pipeline]\nconcurrency = 7\n",
+        )
+        .unwrap();
+        let cfg = AppConfig::load(Some(&path)).unwrap();
+
+        assert_eq!(cfg.storage.db_path, PathBuf::from("/tmp/flagged.duckdb"));
+        assert_eq!(cfg.pipeline.concurrency, 7);
+        assert!(AppConfig::load(Some(Path::new("/nonexistent/ngx.toml"))).is_err());
+    }
+
+    #[test]
+    fn test_holidays_in_year_combines_annual_and_one_off() {
+        let cal = CalendarConfig {
+            holidays: vec![
+                NaiveDate::from_ymd_opt(2024, 4, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2023, 4, 10).unwrap(),
+            ],
+            ..Default::default()
+        };
+        let days = cal.holidays_in_year(2024);
+        assert_eq!(days.len(), 7);
+        assert!(days.contains(&NaiveDate::from_ymd_opt(2024, 6, 12).unwrap()));
+        assert!(days.contains(&NaiveDate::from_ymd_opt(2024, 4, 1).unwrap()));
+    }
+
+    #[test]
+    fn test_validate_rejects_zero_concurrency() {
+        let mut cfg = AppConfig::default();
+        cfg.pipeline.concurrency = 0;
+        let err = cfg.validate().unwrap_err().to_string();
+        assert!(err.contains("pipeline.concurrency"), "{}", err);
+    }
+
+    #[test]
+    fn test_validate_rejects_zero_timeout() {
+        let mut cfg = AppConfig::default();
+        cfg.scraper.timeout_secs = 0;
+        let err = cfg.validate().unwrap_err().to_string();
+        assert!(err.contains("scraper.timeout_secs"), "{}", err);
+    }
+
+    #[test]
+    fn test_validate_rejects_empty_base_url() {
+        let mut cfg = AppConfig::default();
+        cfg.scraper.base_url = "  ".to_string();
+        let err = cfg.validate().unwrap_err().to_string();
+        assert!(err.contains("scraper.base_url"), "{}", err);
+    }
+
+    #[test]
+    fn test_validate_rejects_malformed_base_url() {
+        let mut cfg = AppConfig::default();
+        cfg.scraper.base_url = "afx.kwayisi.org/ngx".to_string();
+        let err = cfg.validate().unwrap_err().to_string();
+        assert!(err.contains("scraper.base_url"), "{}", err);
+    }
+
+    #[test]
+    fn test_validate_rejects_excessive_retries() {
+        let mut cfg = AppConfig::default();
+        cfg.scraper.max_retries = MAX_RETRIES_CAP + 1;
+        let err = cfg.validate().unwrap_err().to_string();
+        assert!(err.contains("scraper.max_retries"), "{}", err);
+    }
+
+    #[test]
+    fn test_validate_rejects_non_ascii_delimiter() {
+        let mut cfg = AppConfig::default();
+        cfg.loader.delimiter = '；';
+        assert!(cfg.validate().is_err());
+        cfg.loader.delimiter = ';';
+        assert!(cfg.validate().is_ok());
+    }
+}
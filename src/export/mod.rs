@@ -0,0 +1,282 @@
+//! File exports of stored data for downstream tools (pandas, data lakes).
+
+use crate::models::DailyBar;
+use crate::storage::Repository;
+use anyhow::{Context, Result};
+use chrono::Datelike;
+use clap::ValueEnum;
+use rust_xlsxwriter::{Format, Workbook, Worksheet};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// File type written by `export`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    /// Excel workbook, one sheet per symbol
+    Xlsx,
+    /// Columnar file of a whole table (see `--table`)
+    Parquet,
+}
+
+impl ExportFormat {
+    /// Infer from `out`'s extension: `.xlsx` and `.parquet` map to their
+    /// formats, anything else is CSV.
+    pub fn from_path(out: &Path) -> Self {
+        match out.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("xlsx") => ExportFormat::Xlsx,
+            Some(ext) if ext.eq_ignore_ascii_case("parquet") => ExportFormat::Parquet,
+            _ => ExportFormat::Csv,
+        }
+    }
+}
+
+/// How exported rows are split across files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PartitionBy {
+    /// `<out>/<SYMBOL>/year=<YYYY>/part.csv` (Hive-style layout)
+    Year,
+}
+
+/// Relative file path for a bar's partition.
+fn partition_path(partition: PartitionBy, bar: &DailyBar) -> PathBuf {
+    match partition {
+        PartitionBy::Year => PathBuf::from(&bar.symbol)
+            .join(format!("year={}", bar.date.year()))
+            .join("part.csv"),
+    }
+}
+
+fn create_writer(path: &Path) -> Result<csv::Writer<File>> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Could not create dir {:?}", parent))?;
+    }
+    debug!("Writing {:?}", path);
+    csv::Writer::from_path(path).with_context(|| format!("Could not create {:?}", path))
+}
+
+/// Export daily bars (one symbol, or all) as CSV, streaming from the DB.
+///
+/// Without a partition `out` is a single file. With one, `out` is a directory
+/// and each partition gets its own file; since bars arrive ordered by
+/// (symbol, date) each partition is contiguous, so only one file is open at a
+/// time and partitions without rows are never created.
+pub fn export_bars_csv(
+    repo: &Repository,
+    symbol: Option<&str>,
+    out: &Path,
+    partition: Option<PartitionBy>,
+) -> Result<usize> {
+    let Some(partition) = partition else {
+        let mut writer = create_writer(out)?;
+        let n = repo.for_each_bar(symbol, |bar| Ok(writer.serialize(&bar)?))?;
+        writer.flush()?;
+        return Ok(n);
+    };
+
+    let mut current: Option<(PathBuf, csv::Writer<File>)> = None;
+    let n = repo.for_each_bar(symbol, |bar| {
+        let rel = partition_path(partition, &bar);
+        if current.as_ref().map(|(p, _)| p != &rel).unwrap_or(true) {
+            if let Some((_, mut w)) = current.take() {
+                w.flush()?;
+            }
+            current = Some((rel.clone(), create_writer(&out.join(&rel))?));
+        }
+        if let Some((_, w)) = current.as_mut() {
+            w.serialize(&bar)?;
+        }
+        Ok(())
+    })?;
+    if let Some((_, mut w)) = current {
+        w.flush()?;
+    }
+    Ok(n)
+}
+
+// ── Excel ─────────────────────────────────────────────────────────────────────
+
+/// Same columns, in the same order, as the CSV export.
+const XLSX_HEADERS: [&str; 10] = [
+    "symbol", "exchange", "date", "open", "high", "low", "close", "change_pct", "volume",
+    "scraped_at",
+];
+
+struct XlsxFormats {
+    header: Format,
+    date: Format,
+    datetime: Format,
+    price: Format,
+    volume: Format,
+}
+
+impl XlsxFormats {
+    fn new() -> Self {
+        Self {
+            header: Format::new().set_bold(),
+            date: Format::new().set_num_format("yyyy-mm-dd"),
+            datetime: Format::new().set_num_format("yyyy-mm-dd hh:mm:ss"),
+            price: Format::new().set_num_format("0.00"),
+            volume: Format::new().set_num_format("#,##0"),
+        }
+    }
+}
+
+fn new_sheet(symbol: &str, fmt: &XlsxFormats) -> Result<Worksheet> {
+    let mut sheet = Worksheet::new();
+    sheet.set_name(symbol)?;
+    for (col, header) in XLSX_HEADERS.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *header, &fmt.header)?;
+    }
+    sheet.set_column_width(2, 12)?;
+    sheet.set_column_width(9, 20)?;
+    sheet.set_freeze_panes(1, 0)?;
+    Ok(sheet)
+}
+
+/// Write one bar as row `row`. Dates are real Excel dates; missing values are
+/// left as blank cells rather than zeros.
+fn write_bar(sheet: &mut Worksheet, row: u32, bar: &DailyBar, fmt: &XlsxFormats) -> Result<()> {
+    sheet.write_string(row, 0, &bar.symbol)?;
+    sheet.write_string(row, 1, &bar.exchange)?;
+    sheet.write_datetime_with_format(row, 2, bar.date, &fmt.date)?;
+    let prices = [bar.open, bar.high, bar.low, Some(bar.close), bar.change_pct];
+    for (i, value) in prices.into_iter().enumerate() {
+        if let Some(v) = value {
+            sheet.write_number_with_format(row, 3 + i as u16, v, &fmt.price)?;
+        }
+    }
+    if let Some(volume) = bar.volume {
+        sheet.write_number_with_format(row, 8, volume as f64, &fmt.volume)?;
+    }
+    sheet.write_datetime_with_format(row, 9, bar.scraped_at, &fmt.datetime)?;
+    Ok(())
+}
+
+/// Export daily bars (one symbol, or all) to an Excel workbook with one sheet
+/// per symbol. Rows stream from the DB in (symbol, date) order, so each sheet
+/// is finished before the next begins.
+pub fn export_bars_xlsx(repo: &Repository, symbol: Option<&str>, out: &Path) -> Result<usize> {
+    let fmt = XlsxFormats::new();
+    let mut workbook = Workbook::new();
+    let mut current: Option<(String, Worksheet, u32)> = None;
+
+    let n = repo.for_each_bar(symbol, |bar| {
+        if current.as_ref().map(|(s, _, _)| s != &bar.symbol).unwrap_or(true) {
+            if let Some((_, sheet, _)) = current.take() {
+                workbook.push_worksheet(sheet);
+            }
+            current = Some((bar.symbol.clone(), new_sheet(&bar.symbol, &fmt)?, 1));
+        }
+        if let Some((_, sheet, row)) = current.as_mut() {
+            write_bar(sheet, *row, &bar, &fmt)?;
+            *row += 1;
+        }
+        Ok(())
+    })?;
+    if let Some((_, sheet, _)) = current {
+        workbook.push_worksheet(sheet);
+    }
+
+    if let Some(parent) = out.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Could not create dir {:?}", parent))?;
+    }
+    debug!("Writing {:?}", out);
+    workbook
+        .save(out)
+        .with_context(|| format!("Could not write {:?}", out))?;
+    Ok(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calamine::{open_workbook, Data, Reader, Xlsx};
+    use chrono::{NaiveDate, Utc};
+
+    fn bar(symbol: &str, day: u32, ohlc: bool) -> DailyBar {
+        DailyBar {
+            symbol: symbol.to_string(),
+            exchange: "NGX".to_string(),
+            date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            open: ohlc.then_some(39.5),
+            high: ohlc.then_some(41.0),
+            low: ohlc.then_some(39.0),
+            close: 40.123,
+            change_pct: None,
+            volume: Some(1_000),
+            scraped_at: Utc::now().naive_utc(),
+        }
+    }
+
+    #[test]
+    fn test_csv_export_partitions_by_symbol_and_year() {
+        let repo = Repository::open_in_memory().unwrap();
+        repo.run_migrations().unwrap();
+        let last_year = DailyBar {
+            date: NaiveDate::from_ymd_opt(2023, 12, 29).unwrap(),
+            ..bar("GTCO", 2, true)
+        };
+        repo.upsert_daily_bars(&[
+            bar("DANGCEM", 2, true),
+            bar("DANGCEM", 3, true),
+            last_year,
+            bar("GTCO", 2, true),
+            bar("GTCO", 3, false),
+        ])
+        .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("parts");
+        assert_eq!(export_bars_csv(&repo, None, &out, Some(PartitionBy::Year)).unwrap(), 5);
+
+        let mut parts = Vec::new();
+        for symbol in std::fs::read_dir(&out).unwrap() {
+            for year in std::fs::read_dir(symbol.unwrap().path()).unwrap() {
+                let file = year.unwrap().path().join("part.csv");
+                let rows = csv::Reader::from_path(&file).unwrap().records().count();
+                let rel = file.strip_prefix(&out).unwrap().to_string_lossy().replace('\\', "/");
+                parts.push((rel, rows));
+            }
+        }
+        parts.sort();
+        let expected = [
+            ("DANGCEM/year=2024/part.csv", 2),
+            ("GTCO/year=2023/part.csv", 1),
+            ("GTCO/year=2024/part.csv", 2),
+        ];
+        assert_eq!(parts, expected.map(|(p, n)| (p.to_string(), n)));
+    }
+
+    #[test]
+    fn test_xlsx_export_writes_sheet_per_symbol_with_real_dates() {
+        let repo = Repository::open_in_memory().unwrap();
+        repo.run_migrations().unwrap();
+        repo.upsert_daily_bars(&[
+            bar("DANGCEM", 2, true),
+            bar("GTCO", 2, false),
+            bar("GTCO", 3, true),
+        ])
+        .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bars.xlsx");
+        assert_eq!(export_bars_xlsx(&repo, None, &path).unwrap(), 3);
+
+        let mut book: Xlsx<_> = open_workbook(&path).unwrap();
+        assert_eq!(book.sheet_names(), ["DANGCEM", "GTCO"]);
+        let gtco = book.worksheet_range("GTCO").unwrap();
+        assert_eq!(gtco.get_value((0, 2)), Some(&Data::String("date".to_string())));
+        // 2024-01-02 is Excel serial 45293.
+        match gtco.get_value((1, 2)) {
+            Some(Data::DateTime(d)) => assert_eq!(d.as_f64(), 45293.0),
+            other => panic!("date cell is not a date: {:?}", other),
+        }
+        assert_eq!(gtco.get_value((1, 3)), Some(&Data::Empty));
+        assert_eq!(gtco.get_value((1, 6)), Some(&Data::Float(40.123)));
+        assert_eq!(gtco.get_value((2, 3)), Some(&Data::Float(39.5)));
+    }
+}
@@ -1,17 +1,111 @@
 //! CSV loaders for investing.com data.
 
-use crate::models::{DailyBar, FxRate, RawCsvRow, RawFxCsvRow, RawTickerRow, Ticker};
-use crate::scraper::cleaner::{csv_row_to_bar, fx_csv_row_to_rate, ticker_row_to_ticker};
+use crate::config::LoaderConfig;
+use crate::models::{
+    CorporateAction, DailyBar, FxRate, RawActionRow, RawCsvRow, RawFxCsvRow, RawTickerRow, Ticker,
+};
+use crate::scraper::cleaner::{
+    infer_date_order, normalise_pair, parse_date_with_hint, parse_price, ticker_row_to_ticker,
+    DateOrder,
+};
 use anyhow::{Context, Result};
 use chrono::Utc;
+use flate2::read::GzDecoder;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
 use std::path::{Path, PathBuf};
+use thiserror::Error;
 use tracing::{debug, info, warn};
 
+/// Loader failures callers treat differently from I/O or parse errors.
+#[derive(Debug, Error)]
+pub enum LoaderError {
+    /// 0 bytes or a header line only, usually a failed download.
+    #[error("{0:?} has no data rows")]
+    Empty(PathBuf),
+    /// The `--fast` SQL path would read the file differently from
+    /// `load_equity_csv`; load it through the Rust loader instead.
+    #[error("{path:?} can't take the fast path: {reason}")]
+    NotFastLoadable { path: PathBuf, reason: String },
+}
+
+/// CSV reader for `path` using the configured dialect, decompressing `.gz`
+/// files on the fly. Validation guarantees the characters are ASCII, so the
+/// byte casts are lossless.
+fn csv_reader(path: &Path, opts: &LoaderConfig) -> Result<csv::Reader<Box<dyn Read>>> {
+    let file = File::open(path).with_context(|| format!("Could not open {:?}", path))?;
+    let input: Box<dyn Read> = if is_gzip(path) {
+        Box::new(GzDecoder::new(BufReader::new(file)))
+    } else {
+        Box::new(file)
+    };
+    Ok(csv::ReaderBuilder::new()
+        .has_headers(opts.has_headers)
+        .flexible(true)
+        .delimiter(opts.delimiter as u8)
+        .quote(opts.quote as u8)
+        .comment(opts.comment.map(|c| c as u8))
+        .from_reader(input))
+}
+
+fn is_gzip(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "gz")
+}
+
+/// File name without its `.csv` / `.csv.gz` extension:
+/// "DANGCEM_historical.csv.gz" → "DANGCEM_historical"
+pub fn csv_stem(path: &Path) -> Option<&str> {
+    let name = path.file_name()?.to_str()?;
+    let name = name.strip_suffix(".gz").unwrap_or(name);
+    Some(name.strip_suffix(".csv").unwrap_or(name))
+}
+
+// ── File classification ───────────────────────────────────────────────────────
+
+/// What a CSV holds, judged from its contents rather than its name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvKind {
+    /// Date, Price, Open, High, Low, Vol., Change %
+    Equity,
+    /// Date, Price, Open, High, Low, Change % (no volume)
+    Fx,
+    /// symbol, name, sector, … (see `load_tickers_csv`)
+    Tickers,
+}
+
+/// Sniff `path`'s header row: a `symbol` column without a `date` column means
+/// ticker metadata, a volume column an equity file, anything else FX (a
+/// multi-pair FX file has both `symbol`/`pair` and `date`). Header-less files
+/// have only the column count to go on: seven or more is equity.
+pub fn classify_csv(path: &Path, opts: &LoaderConfig) -> Result<CsvKind> {
+    let mut reader = csv_reader(path, opts)?;
+    let header = reader
+        .headers()
+        .with_context(|| format!("Could not read header of {:?}", path))?;
+    if header.is_empty() {
+        return Err(LoaderError::Empty(path.to_path_buf()).into());
+    }
+
+    if !opts.has_headers {
+        return Ok(if header.len() >= 7 { CsvKind::Equity } else { CsvKind::Fx });
+    }
+    let names: Vec<String> = header.iter().map(|h| h.trim().to_lowercase()).collect();
+    let has = |name: &str| names.iter().any(|h| h == name);
+    Ok(if has("symbol") && !has("date") {
+        CsvKind::Tickers
+    } else if names.iter().any(|h| h.starts_with("vol")) {
+        CsvKind::Equity
+    } else {
+        CsvKind::Fx
+    })
+}
+
 // ── Symbol/pair extraction ───────────────────────────────────────────────────
 
-/// Extract ticker symbol from filename: "DANGCEM_historical.csv" → "DANGCEM"
+/// Extract ticker symbol from filename: "DANGCEM_historical.csv[.gz]" → "DANGCEM"
 pub fn extract_symbol_from_filename(path: &Path) -> Option<String> {
-    let stem = path.file_stem()?.to_str()?;
+    let stem = csv_stem(path)?;
     let symbol = stem
         .split(|c| c == '_' || c == ' ' || c == '.')
         .next()?
@@ -26,64 +120,262 @@ pub fn extract_pair_from_filename(path: &Path) -> Option<String> {
     extract_symbol_from_filename(path)
 }
 
+/// Filename suffixes that identify an FX data source, and the source name
+/// stored for them.
+const KNOWN_FX_SOURCES: &[(&str, &str)] = &[
+    ("cbn", "cbn"),
+    ("nafem", "nafem"),
+    ("parallel", "parallel"),
+    ("investing", "investing.com"),
+];
+
+/// Extract a recognised FX source from filename: "USDNGN_cbn.csv" → "cbn".
+/// Returns `None` for unrecognised suffixes ("USDNGN_historical.csv") so the
+/// caller can fall back to its default source.
+pub fn extract_source_from_filename(path: &Path) -> Option<String> {
+    let stem = csv_stem(path)?;
+    stem.split(['_', ' ', '.'])
+        .skip(1)
+        .find_map(|part| {
+            let part = part.trim().to_lowercase();
+            KNOWN_FX_SOURCES
+                .iter()
+                .find(|(suffix, _)| *suffix == part)
+                .map(|(_, source)| source.to_string())
+        })
+}
+
 // ── Equity price CSV ──────────────────────────────────────────────────────────
 
+/// One equity file's bars, plus how many rows failed the OHLC consistency check.
+#[derive(Debug)]
+pub struct EquityLoad {
+    pub bars: Vec<DailyBar>,
+    pub ohlc_rejected: usize,
+}
+
+/// Where each equity field sits in a row; `None` for a column the file lacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct EquityColumns {
+    date: Option<usize>,
+    close: Option<usize>,
+    open: Option<usize>,
+    high: Option<usize>,
+    low: Option<usize>,
+    volume: Option<usize>,
+    change_pct: Option<usize>,
+}
+
+/// investing.com's layout: Date, Price, Open, High, Low, Vol., Change %
+const POSITIONAL_EQUITY_COLUMNS: EquityColumns = EquityColumns {
+    date: Some(0),
+    close: Some(1),
+    open: Some(2),
+    high: Some(3),
+    low: Some(4),
+    volume: Some(5),
+    change_pct: Some(6),
+};
+
+/// Header names (normalised by [`header_key`]) accepted for each field.
+const CLOSE_ALIASES: &[&str] = &["price", "close", "last", "adjclose"];
+const VOLUME_ALIASES: &[&str] = &["vol", "volume"];
+const CHANGE_PCT_ALIASES: &[&str] = &["change%", "chg%", "pctchange"];
+
+/// "Vol." → "vol", "Change %" → "change%": lowercase, keeping only letters,
+/// digits and '%'.
+fn header_key(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '%')
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Map `header` onto equity fields by name, so reordered or renamed columns
+/// still land in the right place. `None` unless both a date and a close
+/// column are recognised, in which case the caller falls back to positions.
+fn equity_columns(header: &csv::StringRecord) -> Option<EquityColumns> {
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for (i, name) in header.iter().enumerate() {
+        index.entry(header_key(name)).or_insert(i);
+    }
+    let find = |aliases: &[&str]| aliases.iter().find_map(|a| index.get(*a).copied());
+    let columns = EquityColumns {
+        date: find(&["date"]),
+        close: find(CLOSE_ALIASES),
+        open: find(&["open"]),
+        high: find(&["high"]),
+        low: find(&["low"]),
+        volume: find(VOLUME_ALIASES),
+        change_pct: find(CHANGE_PCT_ALIASES),
+    };
+    (columns.date.is_some() && columns.close.is_some()).then_some(columns)
+}
+
 /// Load investing.com equity CSV: Date, Price, Open, High, Low, Volume, Change%
-pub fn load_equity_csv(path: &Path) -> Result<(String, Vec<DailyBar>)> {
+/// Columns are matched by header name (so "Close" or a reordered export works),
+/// falling back to that positional layout when the header is unrecognisable
+/// or absent. Bars are tagged with `exchange` (e.g. "NGX").
+pub fn load_equity_csv(path: &Path, exchange: &str, opts: &LoaderConfig) -> Result<EquityLoad> {
     let symbol = extract_symbol_from_filename(path)
         .with_context(|| format!("No symbol in filename {:?}", path))?;
 
     debug!("Loading equity {} from {:?}", symbol, path);
 
-    let mut reader = csv::ReaderBuilder::new()
-        .has_headers(true)
-        .flexible(true)
-        .from_path(path)?;
+    let mut reader = csv_reader(path, opts)?;
+    let columns = if opts.has_headers {
+        let header = reader
+            .headers()
+            .with_context(|| format!("Could not read header of {:?}", path))?;
+        equity_columns(header).unwrap_or_else(|| {
+            debug!("{:?}: header not recognised, reading columns by position", path);
+            POSITIONAL_EQUITY_COLUMNS
+        })
+    } else {
+        POSITIONAL_EQUITY_COLUMNS
+    };
+    let records: Vec<(usize, csv::StringRecord)> = reader
+        .records()
+        .enumerate()
+        .filter_map(|(i, result)| match result {
+            Ok(r) => Some((i, r)),
+            Err(e) => {
+                warn!("Row {} in {:?}: {}", i + 1, path, e);
+                None
+            }
+        })
+        .collect();
+    if records.is_empty() {
+        return Err(LoaderError::Empty(path.to_path_buf()).into());
+    }
+
+    // Settle DD/MM vs MM/DD once for the whole file, so "03/04/2024" reads the
+    // same way as the "25/04/2024" further down.
+    let field = |record: &csv::StringRecord, column: Option<usize>| {
+        column.and_then(|i| record.get(i)).map(|s| s.to_string())
+    };
+    let date_order =
+        infer_date_order(records.iter().filter_map(|(_, r)| columns.date.and_then(|i| r.get(i))));
+    debug!("{}: slash-date order {:?}", symbol, date_order);
 
     let now = Utc::now().naive_utc();
     let mut bars = Vec::new();
+    let mut ohlc_rejected = 0;
 
-    for (i, result) in reader.records().enumerate() {
-        let record = match result {
-            Ok(r) => r,
+    for (i, record) in records {
+        let date = field(&record, columns.date).map(|s| {
+            parse_date_with_hint(&s, date_order).map_or(s, |d| d.to_string())
+        });
+        let raw = RawCsvRow {
+            date,
+            price: field(&record, columns.close),
+            open: field(&record, columns.open),
+            high: field(&record, columns.high),
+            low: field(&record, columns.low),
+            volume: field(&record, columns.volume),
+            change_pct: field(&record, columns.change_pct),
+        };
+
+        match DailyBar::try_from((&raw, symbol.as_str(), exchange, now)) {
+            Ok(bar) => bars.push(bar),
             Err(e) => {
-                warn!("Row {} in {:?}: {}", i + 1, path, e);
-                continue;
+                ohlc_rejected += usize::from(e.is_ohlc());
+                warn!("Row {} in {:?} dropped: {}", i + 1, path, e);
             }
-        };
+        }
+    }
 
-        let raw = RawCsvRow {
-            date: record.get(0).map(|s| s.to_string()),
-            price: record.get(1).map(|s| s.to_string()),
-            open: record.get(2).map(|s| s.to_string()),
-            high: record.get(3).map(|s| s.to_string()),
-            low: record.get(4).map(|s| s.to_string()),
-            volume: record.get(5).map(|s| s.to_string()),
-            change_pct: record.get(6).map(|s| s.to_string()),
-        };
+    info!(
+        "{}: {} bars loaded, {} rejected for OHLC inconsistency",
+        symbol,
+        bars.len(),
+        ohlc_rejected
+    );
+    Ok(EquityLoad { bars, ohlc_rejected })
+}
+
+/// Checks that the positional SQL fast path (`Repository::bulk_load_equity_csv`)
+/// reads `path` exactly as `load_equity_csv` would, and returns the file's
+/// slash-date order, settled over the whole date column as the Rust loader
+/// does. Fails with [`LoaderError::NotFastLoadable`] when the header names the
+/// columns in another order, or a price uses a form the SQL path can't parse
+/// ("(1.23)", "1.234,56", "₦610.00").
+pub fn fast_load_date_order(path: &Path, opts: &LoaderConfig) -> Result<Option<DateOrder>> {
+    let not_fast =
+        |reason: String| LoaderError::NotFastLoadable { path: path.to_path_buf(), reason };
+    let mut reader = csv_reader(path, opts)?;
+    if opts.has_headers {
+        let header = reader
+            .headers()
+            .with_context(|| format!("Could not read header of {:?}", path))?;
+        if equity_columns(header).is_some_and(|c| c != POSITIONAL_EQUITY_COLUMNS) {
+            let reason = "columns are not in Date, Price, Open, High, Low, Vol., Change % order";
+            return Err(not_fast(reason.to_string()).into());
+        }
+    }
 
-        if let Some(bar) = csv_row_to_bar(&symbol, &raw, now) {
-            bars.push(bar);
+    // What the SQL path's `TRY_CAST(replace(cell, ',', '') AS DOUBLE)` yields.
+    let sql_price = |cell: &str| cell.replace(',', "").trim().parse::<f64>().ok();
+    let mut dates = Vec::new();
+    for record in reader.records().filter_map(|r| r.ok()) {
+        for cell in (1..=4).filter_map(|i| record.get(i)) {
+            if parse_price(cell) != sql_price(cell) {
+                return Err(not_fast(format!("price {:?} needs the Rust parser", cell)).into());
+            }
         }
+        dates.extend(record.get(0).map(str::to_string));
     }
+    Ok(infer_date_order(dates.iter().map(String::as_str)))
+}
+
+// ── Diff against stored bars ──────────────────────────────────────────────────
 
-    info!("{}: {} bars loaded", symbol, bars.len());
-    Ok((symbol, bars))
+/// How a re-downloaded file's bars compare with those already stored.
+#[derive(Debug, Default)]
+pub struct BarDiff {
+    /// Bars whose date is not in the database yet
+    pub new: Vec<DailyBar>,
+    /// (stored, incoming) pairs whose close or volume differ
+    pub changed: Vec<(DailyBar, DailyBar)>,
+    pub unchanged: usize,
+}
+
+/// Closes closer than this count as equal (float noise from parsing/storage).
+const CLOSE_TOLERANCE: f64 = 1e-9;
+
+/// Classify each `incoming` bar against `stored` bars of the same symbol by date.
+pub fn diff_bars(incoming: &[DailyBar], stored: &[DailyBar]) -> BarDiff {
+    let by_date: HashMap<_, _> = stored.iter().map(|b| (b.date, b)).collect();
+    let mut diff = BarDiff::default();
+    for bar in incoming {
+        match by_date.get(&bar.date) {
+            None => diff.new.push(bar.clone()),
+            Some(old)
+                if (old.close - bar.close).abs() > CLOSE_TOLERANCE
+                    || old.volume != bar.volume =>
+            {
+                diff.changed.push(((*old).clone(), bar.clone()))
+            }
+            Some(_) => diff.unchanged += 1,
+        }
+    }
+    diff
 }
 
 // ── FX rate CSV ───────────────────────────────────────────────────────────────
 
 
-pub fn load_fx_csv(path: &Path, source: Option<&str>) -> Result<(String, Vec<FxRate>)> {
+pub fn load_fx_csv(
+    path: &Path,
+    source: Option<&str>,
+    opts: &LoaderConfig,
+) -> Result<(String, Vec<FxRate>)> {
     let pair = extract_pair_from_filename(path)
         .with_context(|| format!("No FX pair in filename {:?}", path))?;
 
     debug!("Loading FX pair {} from {:?}", pair, path);
 
-    let mut reader = csv::ReaderBuilder::new()
-        .has_headers(true)
-        .flexible(true)
-        .from_path(path)?;
+    let mut reader = csv_reader(path, opts)?;
 
     let now = Utc::now().naive_utc();
     let mut rates = Vec::new();
@@ -106,8 +398,9 @@ pub fn load_fx_csv(path: &Path, source: Option<&str>) -> Result<(String, Vec<FxR
             change_pct: record.get(5).map(|s| s.to_string()),
         };
 
-        if let Some(rate) = fx_csv_row_to_rate(&pair, &raw, source, now) {
-            rates.push(rate);
+        match FxRate::try_from((&raw, pair.as_str(), source, now)) {
+            Ok(rate) => rates.push(rate),
+            Err(e) => warn!("Row {} in {:?} dropped: {}", i + 1, path, e),
         }
     }
 
@@ -115,16 +408,105 @@ pub fn load_fx_csv(path: &Path, source: Option<&str>) -> Result<(String, Vec<FxR
     Ok((pair, rates))
 }
 
+/// Header names (normalised by [`header_key`]) of a per-row pair column.
+const PAIR_ALIASES: &[&str] = &["pair", "symbol"];
+
+fn pair_column(header: &csv::StringRecord) -> Option<usize> {
+    header
+        .iter()
+        .position(|name| PAIR_ALIASES.contains(&header_key(name).as_str()))
+}
+
+/// True when `path`'s header has a Pair/Symbol column, i.e. the file holds
+/// several pairs and should go through [`load_multi_fx_csv`]. Header-less
+/// files are always single-pair.
+pub fn is_multi_fx_csv(path: &Path, opts: &LoaderConfig) -> Result<bool> {
+    if !opts.has_headers {
+        return Ok(false);
+    }
+    let mut reader = csv_reader(path, opts)?;
+    let header = reader
+        .headers()
+        .with_context(|| format!("Could not read header of {:?}", path))?;
+    Ok(pair_column(header).is_some())
+}
+
+/// Load an FX CSV holding several pairs, e.g. a single `fx_rates.csv` with
+/// Pair, Date, Price, Open, High, Low, Change %. The pair comes from each row
+/// (normalised, so "USD/NGN" works) and the other columns are matched by the
+/// same header names as equity files. Rates are grouped by pair in the order
+/// pairs first appear.
+pub fn load_multi_fx_csv(
+    path: &Path,
+    source: Option<&str>,
+    opts: &LoaderConfig,
+) -> Result<Vec<(String, Vec<FxRate>)>> {
+    debug!("Loading FX pairs from {:?}", path);
+
+    let mut reader = csv_reader(path, opts)?;
+    let header = reader
+        .headers()
+        .with_context(|| format!("Could not read header of {:?}", path))?;
+    let pair_col =
+        pair_column(header).with_context(|| format!("No Pair/Symbol column in {:?}", path))?;
+    let columns = equity_columns(header)
+        .with_context(|| format!("No Date and Price/Close columns in {:?}", path))?;
+
+    let now = Utc::now().naive_utc();
+    let mut groups: Vec<(String, Vec<FxRate>)> = Vec::new();
+    let mut group_of: HashMap<String, usize> = HashMap::new();
+
+    for (i, result) in reader.records().enumerate() {
+        let record = match result {
+            Ok(r) => r,
+            Err(e) => {
+                warn!("Row {} in {:?}: {}", i + 1, path, e);
+                continue;
+            }
+        };
+        let pair = record.get(pair_col).map(normalise_pair).unwrap_or_default();
+        if pair.is_empty() {
+            warn!("Row {} in {:?} dropped: missing pair", i + 1, path);
+            continue;
+        }
+
+        let field = |column: Option<usize>| {
+            column.and_then(|i| record.get(i)).map(|s| s.to_string())
+        };
+        let raw = RawFxCsvRow {
+            date: field(columns.date),
+            price: field(columns.close),
+            open: field(columns.open),
+            high: field(columns.high),
+            low: field(columns.low),
+            change_pct: field(columns.change_pct),
+        };
+
+        match FxRate::try_from((&raw, pair.as_str(), source, now)) {
+            Ok(rate) => {
+                let g = *group_of.entry(pair).or_insert_with_key(|pair| {
+                    groups.push((pair.clone(), Vec::new()));
+                    groups.len() - 1
+                });
+                groups[g].1.push(rate);
+            }
+            Err(e) => warn!("Row {} in {:?} dropped: {}", i + 1, path, e),
+        }
+    }
+
+    for (pair, rates) in &groups {
+        info!("{}: {} rates loaded", pair, rates.len());
+    }
+    Ok(groups)
+}
+
 // ── Ticker metadata CSV ───────────────────────────────────────────────────────
 
-/// Load ticker metadata CSV: symbol, name, sector, industry, exchange
-pub fn load_tickers_csv(path: &Path) -> Result<Vec<Ticker>> {
+/// Load ticker metadata CSV: symbol, name, sector, industry, exchange[, isin, board, status]
+pub fn load_tickers_csv(path: &Path, opts: &LoaderConfig) -> Result<Vec<Ticker>> {
     debug!("Loading tickers from {:?}", path);
 
-    let mut reader = csv::ReaderBuilder::new()
-        .has_headers(true)
-        .flexible(true)
-        .from_path(path)?;
+    let mut reader = csv_reader(path, opts)?;
 
     let now = Utc::now().naive_utc();
     let mut tickers = Vec::new();
@@ -144,6 +526,9 @@ pub fn load_tickers_csv(path: &Path) -> Result<Vec<Ticker>> {
             sector: record.get(2).map(|s| s.to_string()),
             industry: record.get(3).map(|s| s.to_string()),
             exchange: record.get(4).map(|s| s.to_string()),
+            isin: record.get(5).map(|s| s.to_string()),
+            board: record.get(6).map(|s| s.to_string()),
+            status: record.get(7).map(|s| s.to_string()),
         };
 
         if let Some(ticker) = ticker_row_to_ticker(&raw, now) {
@@ -155,8 +540,44 @@ pub fn load_tickers_csv(path: &Path) -> Result<Vec<Ticker>> {
     Ok(tickers)
 }
 
+// ── Corporate actions CSV ─────────────────────────────────────────────────────
+
+/// Load corporate actions CSV: symbol, ex_date, action_type, ratio_or_amount
+pub fn load_actions_csv(path: &Path, opts: &LoaderConfig) -> Result<Vec<CorporateAction>> {
+    debug!("Loading corporate actions from {:?}", path);
+
+    let mut reader = csv_reader(path, opts)?;
+    let mut actions = Vec::new();
+
+    for (i, result) in reader.records().enumerate() {
+        let record = match result {
+            Ok(r) => r,
+            Err(e) => {
+                warn!("Row {} in {:?}: {}", i + 1, path, e);
+                continue;
+            }
+        };
+
+        let raw = RawActionRow {
+            symbol: record.get(0).map(|s| s.to_string()),
+            ex_date: record.get(1).map(|s| s.to_string()),
+            action_type: record.get(2).map(|s| s.to_string()),
+            ratio_or_amount: record.get(3).map(|s| s.to_string()),
+        };
+
+        match CorporateAction::try_from(&raw) {
+            Ok(action) => actions.push(action),
+            Err(e) => warn!("Row {} in {:?} dropped: {}", i + 1, path, e),
+        }
+    }
+
+    info!("Loaded {} corporate actions", actions.len());
+    Ok(actions)
+}
+
 // ── File discovery ────────────────────────────────────────────────────────────
 
+/// `.csv` and gzip-compressed `.csv.gz` files directly inside `dir`.
 pub fn discover_csv_files(dir: &Path) -> Result<Vec<PathBuf>> {
     if !dir.exists() {
         return Ok(vec![]);
@@ -165,9 +586,292 @@ pub fn discover_csv_files(dir: &Path) -> Result<Vec<PathBuf>> {
     let mut files = Vec::new();
     for entry in std::fs::read_dir(dir)? {
         let path = entry?.path();
-        if path.is_file() && path.extension().map(|e| e == "csv").unwrap_or(false) {
+        let is_csv = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.ends_with(".csv") || n.ends_with(".csv.gz"));
+        if path.is_file() && is_csv {
             files.push(path);
         }
     }
     Ok(files)
-}
\ No newline at end of file
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Writes `body` to `name` in `dir` (a `tempfile::tempdir()`, removed with
+    /// everything in it when dropped) and returns the file's path.
+    fn write_csv(dir: &TempDir, name: &str, body: &str) -> PathBuf {
+        let path = dir.path().join(name);
+        std::fs::write(&path, body).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_source_from_fx_filename() {
+        let path = Path::new("data/USDNGN_cbn.csv");
+        assert_eq!(extract_pair_from_filename(path).as_deref(), Some("USDNGN"));
+        assert_eq!(extract_source_from_filename(path).as_deref(), Some("cbn"));
+    }
+
+    #[test]
+    fn test_unrecognised_fx_suffix_has_no_source() {
+        let path = Path::new("data/USDNGN_historical.csv");
+        assert_eq!(extract_pair_from_filename(path).as_deref(), Some("USDNGN"));
+        assert_eq!(extract_source_from_filename(path), None);
+    }
+
+    #[test]
+    fn test_load_semicolon_delimited_equity_csv() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_csv(
+            &dir,
+            "DANGCEM_historical.csv",
+            "Date;Price;Open;High;Low;Vol.;Change %\n\
+             01/03/2024;305.00;300.00;310.00;295.00;1.2M;0.38%\n\
+             01/02/2024;\"1,300.00\";\"1,290.00\";\"1,310.00\";\"1,280.00\";850K;-0.10%\n",
+        );
+
+        let opts = LoaderConfig {
+            delimiter: ';',
+            ..Default::default()
+        };
+        let bars = load_equity_csv(&path, "NGX", &opts).unwrap().bars;
+
+        assert_eq!(bars[0].symbol, "DANGCEM");
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0].close, 305.0);
+        assert_eq!(bars[1].close, 1300.0);
+        assert_eq!(bars[0].volume, Some(1_200_000));
+    }
+
+    #[test]
+    fn test_equity_columns_follow_the_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let reordered = write_csv(
+            &dir,
+            "GTCO.csv",
+            "Vol.,Date,High,Low,Open,Price,Chg%\n1.5M,01/02/2024,42.00,39.50,40.00,41.00,2.50%\n",
+        );
+        let renamed = write_csv(
+            &dir,
+            "MTNN.csv",
+            "Date,Open,High,Low,Close,Volume\n01/02/2024,230,232,229,231,900K\n",
+        );
+
+        let opts = LoaderConfig::default();
+        let gtco = load_equity_csv(&reordered, "NGX", &opts).unwrap().bars;
+        let mtnn = load_equity_csv(&renamed, "NGX", &opts).unwrap().bars;
+
+        assert_eq!(gtco.len(), 1);
+        assert_eq!(
+            (gtco[0].open, gtco[0].high, gtco[0].low, gtco[0].close),
+            (Some(40.0), Some(42.0), Some(39.5), 41.0)
+        );
+        assert_eq!((gtco[0].volume, gtco[0].change_pct), (Some(1_500_000), Some(2.5)));
+
+        assert_eq!(mtnn.len(), 1);
+        assert_eq!((mtnn[0].close, mtnn[0].volume), (231.0, Some(900_000)));
+        assert_eq!(mtnn[0].change_pct, None);
+    }
+
+    #[test]
+    fn test_fast_path_refuses_files_the_rust_loader_reads_differently() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, body: &str| write_csv(&dir, name, body);
+        let standard = write(
+            "DANGCEM.csv",
+            "Date,Price,Open,High,Low,Vol.,Change %\n\
+             25/01/2024,\"1,305.00\",1300,1310,1295,1.2K,0.38%\n",
+        );
+        let reordered = write(
+            "GTCO.csv",
+            "Date,Open,High,Low,Price,Vol.,Change %\n03/01/2024,40,42,39.5,41,1.5M,2.50%\n",
+        );
+        let decimal_comma = write(
+            "MTNN.csv",
+            "Date,Price,Open,High,Low,Vol.,Change %\n03/01/2024,\"231,50\",230,232,229,900K,1%\n",
+        );
+
+        let opts = LoaderConfig::default();
+        let ok = fast_load_date_order(&standard, &opts);
+        let refused = [reordered, decimal_comma].map(|p| fast_load_date_order(&p, &opts));
+
+        assert_eq!(ok.unwrap(), Some(DateOrder::DayFirst));
+        for err in refused {
+            let err = err.unwrap_err();
+            assert!(
+                matches!(err.downcast_ref(), Some(LoaderError::NotFastLoadable { .. })),
+                "{:#}",
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_headerless_file_keeps_first_row() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_csv(&dir, "GTCO.csv", "01/03/2024,41.00\n01/02/2024,40.00\n");
+
+        let with_header = load_equity_csv(&path, "NGX", &LoaderConfig::default()).unwrap().bars;
+        let opts = LoaderConfig {
+            has_headers: false,
+            ..Default::default()
+        };
+        let bars = load_equity_csv(&path, "NGX", &opts).unwrap().bars;
+
+        assert_eq!(with_header.len(), 1);
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0].close, 41.0);
+    }
+
+    #[test]
+    fn test_equity_load_counts_ohlc_rejects() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_csv(
+            &dir,
+            "MTNN.csv",
+            "Date,Price,Open,High,Low,Vol.,Change %\n\
+             01/04/2024,231.00,230.00,232.00,229.00,1.1M,0.43%\n\
+             01/03/2024,240.00,230.00,232.00,229.00,1.0M,4.35%\n\
+             01/02/2024,230.00,230.00,228.00,232.00,0.9M,0.00%\n\
+             01/01/2024,N/A,,,,,\n",
+        );
+
+        let load = load_equity_csv(&path, "NGX", &LoaderConfig::default()).unwrap();
+
+        assert_eq!(load.bars.len(), 1);
+        assert_eq!(load.ohlc_rejected, 2);
+    }
+
+    #[test]
+    fn test_ambiguous_dates_follow_the_file_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_csv(
+            &dir,
+            "ZENITHBANK.csv",
+            "Date,Price,Open,High,Low,Vol.,Change %
+             04/15/2024,36.00,,,,2.0M,
+             04/03/2024,35.00,,,,1.5M,
+",
+        );
+
+        let load = load_equity_csv(&path, "NGX", &LoaderConfig::default()).unwrap();
+
+        // "04/15" can only be MM/DD, so "04/03" is 3 April, not 4 March.
+        let dates: Vec<String> = load.bars.iter().map(|b| b.date.to_string()).collect();
+        assert_eq!(dates, ["2024-04-15", "2024-04-03"]);
+    }
+
+    #[test]
+    fn test_gzipped_csv_is_discovered_and_loaded() {
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("DANGCEM_historical.csv.gz");
+        let mut gz = GzEncoder::new(File::create(&path).unwrap(), flate2::Compression::default());
+        gz.write_all(b"Date,Price\n01/03/2024,305.00\n01/02/2024,300.00\n").unwrap();
+        gz.finish().unwrap();
+
+        let found = discover_csv_files(dir.path()).unwrap();
+        let bars = load_equity_csv(&path, "NGX", &LoaderConfig::default()).unwrap().bars;
+
+        assert_eq!(found, vec![path.clone()]);
+        assert_eq!(extract_symbol_from_filename(&path).as_deref(), Some("DANGCEM"));
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0].close, 305.0);
+    }
+
+    #[test]
+    fn test_classify_csv_by_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, body: &str| write_csv(&dir, name, body);
+        // Names that fooled the old substring check
+        let equity = write("USTH.csv", "Date,Price,Open,High,Low,Vol.,Change %\n");
+        let fx = write("NAIRA.csv", "Date,Price,Open,High,Low,Change %\n");
+        let tickers = write("list.csv", "symbol,name,sector,industry,exchange\n");
+        let multi_fx = write("fx_rates.csv", "Symbol,Date,Price,Open,High,Low,Change %\n");
+        let empty = write("EUROBANK.csv", "");
+
+        let opts = LoaderConfig::default();
+        assert_eq!(classify_csv(&equity, &opts).unwrap(), CsvKind::Equity);
+        assert_eq!(classify_csv(&fx, &opts).unwrap(), CsvKind::Fx);
+        assert_eq!(classify_csv(&tickers, &opts).unwrap(), CsvKind::Tickers);
+        assert_eq!(classify_csv(&multi_fx, &opts).unwrap(), CsvKind::Fx);
+        let err = classify_csv(&empty, &opts).unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(LoaderError::Empty(_))));
+    }
+
+    #[test]
+    fn test_diff_bars_splits_new_changed_unchanged() {
+        use chrono::Datelike;
+        let bar = |day: u32, close: f64, volume: Option<i64>| DailyBar {
+            symbol: "GTCO".to_string(),
+            exchange: "NGX".to_string(),
+            date: chrono::NaiveDate::from_ymd_opt(2024, 3, day).unwrap(),
+            open: None,
+            high: None,
+            low: None,
+            close,
+            change_pct: None,
+            volume,
+            scraped_at: Utc::now().naive_utc(),
+        };
+        let stored = [bar(1, 40.0, Some(1_000)), bar(4, 41.0, Some(2_000)), bar(5, 42.0, None)];
+        let incoming = [
+            bar(1, 40.0, Some(1_000)),
+            bar(4, 41.0, Some(2_500)),
+            bar(5, 42.5, None),
+            bar(6, 43.0, Some(900)),
+        ];
+
+        let diff = diff_bars(&incoming, &stored);
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.new.iter().map(|b| b.date.day()).collect::<Vec<_>>(), vec![6]);
+        let changed: Vec<u32> = diff.changed.iter().map(|(old, _)| old.date.day()).collect();
+        assert_eq!(changed, vec![4, 5]);
+    }
+
+    #[test]
+    fn test_load_multi_fx_csv_groups_rows_by_pair() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_csv(
+            &dir,
+            "fx_rates.csv",
+            "Date,Pair,Price,Open,High,Low,Change %\n\
+             2024-03-04,USD/NGN,1600.50,1590.00,1610.00,1585.00,0.66%\n\
+             2024-03-04,eurngn,1735.20,1730.00,1740.00,1725.00,0.30%\n\
+             2024-03-05,USDNGN,1610.00,1600.50,1615.00,1598.00,0.59%\n\
+             2024-03-05,,1.00,1.00,1.00,1.00,0.00%\n",
+        );
+
+        let opts = LoaderConfig::default();
+        let single =
+            write_csv(&dir, "USDNGN_historical.csv", "Date,Price,Open,High,Low,Change %\n");
+        assert!(is_multi_fx_csv(&path, &opts).unwrap());
+        assert!(!is_multi_fx_csv(&single, &opts).unwrap());
+
+        let groups = load_multi_fx_csv(&path, Some("cbn"), &opts).unwrap();
+        let summary: Vec<(&str, usize)> =
+            groups.iter().map(|(pair, rates)| (pair.as_str(), rates.len())).collect();
+        assert_eq!(summary, vec![("USDNGN", 2), ("EURNGN", 1)]);
+        assert_eq!(groups[0].1[1].close, 1610.0);
+        assert_eq!(groups[1].1[0].source.as_deref(), Some("cbn"));
+    }
+
+    #[test]
+    fn test_headers_only_equity_csv_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let path =
+            write_csv(&dir, "MTNN_historical.csv", "Date,Price,Open,High,Low,Vol.,Change %\n");
+
+        let err = load_equity_csv(&path, "NGX", &LoaderConfig::default()).unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(LoaderError::Empty(p)) if *p == path));
+    }
+}
@@ -1,21 +1,42 @@
+mod analytics;
+mod chart;
 mod config;
+mod export;
 mod loader;
+#[cfg(feature = "metrics")]
+mod metrics;
 mod models;
+mod output;
 mod pipeline;
 mod scraper;
+mod shell;
 mod storage;
 mod utils;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use chrono::{Datelike, NaiveDate, Utc};
 use clap::{Parser, Subcommand};
+use rayon::prelude::*;
+use serde::Serialize;
 use std::path::PathBuf;
-use tracing::info;
+use tracing::{info, warn};
 use tracing_subscriber::{fmt, EnvFilter, prelude::*};
 
+use crate::analytics::{IndicatorKind, ReturnKind};
+use crate::chart::ChartKind;
 use crate::config::AppConfig;
-use crate::loader::{discover_csv_files, load_equity_csv, load_fx_csv, load_tickers_csv};
+use crate::export::{export_bars_csv, export_bars_xlsx, ExportFormat, PartitionBy};
+use crate::loader::{
+    classify_csv, discover_csv_files, extract_source_from_filename, fast_load_date_order,
+    extract_symbol_from_filename, diff_bars, is_multi_fx_csv, load_actions_csv, CsvKind,
+    load_equity_csv, load_fx_csv, load_multi_fx_csv, load_tickers_csv, LoaderError,
+};
+use crate::models::{Currency, DailyBar, DateRange, Resample};
+use crate::output::OutputFormat;
 use crate::pipeline::Pipeline;
-use crate::storage::Repository;
+use crate::scraper::cleaner::{normalise_pair, normalise_symbol};
+use crate::scraper::FxSource;
+use crate::storage::{Repository, BULK_INSERT_THRESHOLD};
 
 #[derive(Parser)]
 #[command(name = "ngx-etl", about = "NGX market data ETL", version)]
@@ -25,6 +46,56 @@ struct Cli {
 
     #[arg(short, long, action = clap::ArgAction::Count, global = true)]
     verbose: u8,
+
+    /// DuckDB file to use instead of `storage.db_path` from config
+    #[arg(long, global = true)]
+    db: Option<PathBuf>,
+
+    /// TOML config layered over config/default.toml in place of
+    /// config/local.toml (env vars and CLI flags still win)
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Restrict analytics to bars on or after this date (YYYY-MM-DD). Not
+    /// applied by compare, completeness (see --year), export or listings
+    #[arg(long, global = true, visible_alias = "min-date")]
+    from: Option<NaiveDate>,
+
+    /// Restrict analytics to bars on or before this date (YYYY-MM-DD)
+    #[arg(long, global = true, visible_alias = "max-date")]
+    to: Option<NaiveDate>,
+
+    /// Treat the first line of loaded CSVs as data (overrides `loader.has_headers`)
+    #[arg(long, global = true)]
+    no_header: bool,
+
+    /// Seed the scraper's request jitter (overrides `scraper.seed`)
+    #[arg(long, global = true)]
+    seed: Option<u64>,
+
+    /// Exchange whose bars commands read, and that loaded bars are tagged with
+    /// (defaults to `scraper.exchange`); `symbols` and `coverage` list every
+    /// exchange unless it is given
+    #[arg(long, global = true)]
+    exchange: Option<String>,
+
+    /// Leave out symbols flagged suspended or delisted (symbols, coverage, gaps,
+    /// breadth and detect-splits)
+    #[arg(long, global = true)]
+    active_only: bool,
+
+    /// Open the database read-only, even for commands that would write
+    #[arg(long, global = true, visible_alias = "read-only")]
+    readonly: bool,
+
+    /// Parse, validate and fetch as usual but write nothing to the database;
+    /// counts are the rows that would have been written
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Output format for command results
+    #[arg(long, value_enum, global = true, default_value = "text")]
+    format: OutputFormat,
 }
 
 #[derive(Subcommand)]
@@ -34,33 +105,429 @@ enum Command {
         path: PathBuf,
     },
 
+    /// Load splits/dividends CSV: symbol, ex_date, action_type, ratio_or_amount
+    LoadActions {
+        #[arg(default_value = "data/actions.csv")]
+        path: PathBuf,
+    },
+
     LoadEquities {
         #[arg(short, long, default_value = "data")]
         dir: PathBuf,
+
+        /// Let DuckDB parse the CSVs natively (fast; for trusted files — bad
+        /// rows are dropped without warnings). Files in another column order or
+        /// number format go through the Rust loader as usual
+        #[arg(long)]
+        fast: bool,
+
+        /// Replace each file's date span for its symbol instead of merging
+        /// (for corrected full histories)
+        #[arg(long, conflicts_with = "fast")]
+        replace: bool,
     },
 
+    /// Compare an equity CSV with the stored bars before loading it: rows
+    /// that are new or whose close/volume changed (writes nothing)
+    Diff { path: PathBuf },
+
+    /// Load FX CSVs: one pair per file named after it (USDNGN_historical.csv),
+    /// or several pairs in one file with a Pair/Symbol column
     LoadFx {
         #[arg(short, long, default_value = "data")]
         dir: PathBuf,
 
-        /// Data source attribution (e.g. "investing.com")
+        /// Data source attribution (e.g. "investing.com"); a recognised filename
+        /// suffix such as `USDNGN_cbn.csv` overrides it per file
         #[arg(long, default_value = "investing.com")]
         source: String,
+
+        /// Parse files in parallel (rates are still written one file at a time)
+        #[arg(long)]
+        parallel_files: bool,
+    },
+
+    /// Compare two FX sources for a pair (e.g. official vs parallel)
+    FxSpread {
+        /// FX pair, e.g. USDNGN
+        pair: String,
+
+        /// Reference source (e.g. "cbn")
+        a: String,
+
+        /// Compared source (e.g. "investing.com")
+        b: String,
+    },
+
+    /// Annualised change implied between the rates at --from and --to (as-of:
+    /// the latest rate on or before each date)
+    FxCarry {
+        /// FX pair, e.g. USDNGN
+        pair: String,
     },
 
     /// Scrape latest bars for all tickers (daily update mode)
     Update,
 
+    /// Run the daily update on a schedule (aligned to 18:00 WAT) until
+    /// SIGINT/SIGTERM; a failed cycle is logged and the loop carries on
+    Daemon {
+        #[arg(long, default_value_t = 24, value_parser = clap::value_parser!(u64).range(1..))]
+        interval_hours: u64,
+    },
+
+    /// Crawl the listing pages into the tickers table (no bars fetched)
+    Seed,
+
+    /// Walk each ticker's older history pages back to its newest stored bar
+    Backfill,
+
+    /// Fetch official FX rates into fx_rates, tagged with the source
+    UpdateFx {
+        #[arg(long, value_enum, default_value_t = FxSource::Cbn)]
+        source: FxSource,
+
+        /// Pairs to fetch (repeatable)
+        #[arg(long = "pair", default_value = "USDNGN")]
+        pairs: Vec<String>,
+    },
+
     /// Show database statistics
-    Stats,
+    Stats {
+        /// Shorthand for `--format json`
+        #[arg(long)]
+        json: bool,
+    },
 
-    /// List all stored ticker symbols
+    /// List all stored ticker symbols (only --exchange's, if given)
     Symbols,
 
+    /// Show stored metadata for one ticker
+    Ticker { symbol: String },
+
+    /// Show bar count and date span per symbol, including tickers with no bars
+    /// (only --exchange's, if given)
+    Coverage,
+
+    /// Export daily bars to CSV
+    Export {
+        /// Output file, or directory when partitioning
+        #[arg(short, long)]
+        out: PathBuf,
+
+        /// Only export this symbol
+        #[arg(long)]
+        symbol: Option<String>,
+
+        /// Split output into one file per partition (e.g. SYMBOL/year=2023/part.csv)
+        #[arg(long, value_enum)]
+        partition_by: Option<PartitionBy>,
+
+        /// File type to write (defaults from the --out extension)
+        #[arg(long, value_enum)]
+        file_format: Option<ExportFormat>,
+
+        /// Table to export (Parquet only: tickers, daily_bars, fx_rates, scrape_runs)
+        #[arg(long, default_value = "daily_bars")]
+        table: String,
+    },
+
+    /// Stored bars for one symbol, oldest first (within --from/--to)
+    Query {
+        symbol: String,
+
+        /// Add a split-adjusted close column (see load-actions)
+        #[arg(long)]
+        adjusted: bool,
+
+        /// Aggregate into weekly or monthly OHLC bars
+        #[arg(long, value_enum, conflicts_with = "adjusted")]
+        freq: Option<Resample>,
+
+        /// Report prices in naira or, at the as-of USDNGN rate, in dollars
+        #[arg(long, value_enum, default_value_t = Currency::Ngn,
+              conflicts_with_all = ["adjusted", "freq"])]
+        currency: Currency,
+    },
+
+    /// Show the maximum peak-to-trough drawdown of a symbol's closes
+    Drawdown { symbol: String },
+
+    /// Render a symbol's price history (within --from/--to) to a PNG
+    Chart {
+        symbol: String,
+
+        #[arg(long, default_value = "chart.png")]
+        out: PathBuf,
+
+        #[arg(long, value_enum, default_value_t = ChartKind::Line)]
+        kind: ChartKind,
+    },
+
+    /// Two symbols rebased to 100 on their first common date, side by side
+    Compare {
+        a: String,
+        b: String,
+
+        /// Rebase on the first common date on or after this one
+        #[arg(long)]
+        base: Option<NaiveDate>,
+    },
+
+    /// Close-to-close daily returns for a symbol (within --from/--to)
+    Returns {
+        symbol: String,
+
+        #[arg(long, value_enum, default_value_t = ReturnKind::Simple)]
+        kind: ReturnKind,
+    },
+
+    /// Advancers, decliners and unchanged symbols on a day, by change_pct sign
+    Breadth {
+        /// Trading day (YYYY-MM-DD)
+        date: NaiveDate,
+
+        /// Also report an equal-weighted return index on that day (base 100 at
+        /// --from, or the first stored bar)
+        #[arg(long)]
+        index: bool,
+    },
+
+    /// Day-over-day close moves beyond a threshold, e.g. decimal slips in a
+    /// scrape (report only; within --from/--to)
+    Outliers {
+        symbol: String,
+
+        /// Largest plausible daily move, in percent
+        #[arg(long, default_value_t = 25.0)]
+        threshold: f64,
+    },
+
+    /// Rolling volume-weighted average price for a symbol (within --from/--to)
+    Vwap {
+        symbol: String,
+
+        /// Trailing window in bars
+        #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u64).range(1..))]
+        window: u64,
+    },
+
+    /// Daily returns in naira and in dollars (as-of USDNGN; within --from/--to)
+    RealReturns { symbol: String },
+
+    /// Day-over-day moves matching common split ratios (heuristic; review
+    /// candidates before adjusting)
+    DetectSplits {
+        /// How close to an exact ratio (in %) a move must be
+        #[arg(long, default_value_t = 3.0)]
+        tolerance_pct: f64,
+    },
+
+    /// Moving average of a symbol's closes (within --from/--to)
+    Indicator {
+        symbol: String,
+
+        #[arg(long, value_enum, default_value_t = IndicatorKind::Sma)]
+        kind: IndicatorKind,
+
+        /// Window in bars
+        #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u64).range(1..))]
+        window: u64,
+    },
+
+    /// EMA-crossover buy/sell signals for a symbol (within --from/--to)
+    Signals {
+        symbol: String,
+
+        #[arg(long, default_value_t = 12)]
+        fast: usize,
+
+        #[arg(long, default_value_t = 26)]
+        slow: usize,
+    },
+
+    /// Share of expected trading days a symbol has bars for in a year
+    Completeness {
+        symbol: String,
+
+        /// Calendar year (defaults to the current year, counted up to today)
+        #[arg(long)]
+        year: Option<i32>,
+    },
+
+    /// Stretches between consecutive stored dates longer than --max-gap-days
+    Gaps {
+        /// Only this symbol (default: every stored symbol)
+        symbol: Option<String>,
+
+        /// Longest gap (in calendar days) that isn't reported; weekends make 3
+        #[arg(long, default_value_t = 3)]
+        max_gap_days: i64,
+    },
+
+    /// Symbols not written for a while, by last scrape time rather than bar
+    /// date (catches tickers the pipeline quietly skips or fails on)
+    Stale {
+        /// Report symbols last scraped more than this many hours ago
+        #[arg(long, default_value_t = 48)]
+        hours: i64,
+    },
+
+    /// Flag tickers whose close hasn't moved for N straight bars as suspended
+    InferSuspended {
+        #[arg(long, default_value_t = 10)]
+        days: usize,
+    },
+
+    /// Counts, date spans, last run and staleness in one report (for monitoring)
+    Health,
+
+    /// Move a symbol's bars and ticker to a new symbol (merging if it exists)
+    Rename { old: String, new: String },
+
+    /// Remove a symbol's bars, ticker and corporate actions (e.g. a bad load)
+    Delete {
+        symbol: String,
+
+        /// Skip the confirmation prompt (required when not on a terminal)
+        #[arg(long)]
+        yes: bool,
+    },
+
+    /// Drop bars and FX rates older than a cutoff date
+    #[command(group(clap::ArgGroup::new("cutoff").required(true).args(["before", "keep_years"])))]
+    Prune {
+        /// Delete rows dated before this day (YYYY-MM-DD)
+        #[arg(long)]
+        before: Option<NaiveDate>,
+
+        /// Keep only the last N years, counted back from today
+        #[arg(long)]
+        keep_years: Option<u32>,
+
+        /// Run CHECKPOINT afterwards so the database file shrinks
+        #[arg(long)]
+        checkpoint: bool,
+
+        /// Skip the confirmation prompt (required when not on a terminal)
+        #[arg(long)]
+        yes: bool,
+    },
+
+    /// Re-normalise stored symbols (case, stray whitespace), merging collisions;
+    /// with --dry-run, only report what would change
+    NormalizeSymbols,
+
+    /// Most recently written bars; with --follow, keep printing new ones
+    Tail {
+        /// Only this symbol
+        symbol: Option<String>,
+
+        #[arg(short = 'n', long, default_value_t = 20)]
+        lines: usize,
+
+        /// Poll for bars written after the last one shown (Ctrl-C to stop)
+        #[arg(long)]
+        follow: bool,
+
+        /// Seconds between polls with --follow
+        #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..))]
+        interval_secs: u64,
+    },
+
+    /// Most recent mutating batches recorded in the audit log
+    Audit {
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Check data invariants (orphan bars, duplicate keys, high < low, …);
+    /// exits non-zero if any fail
+    Verify,
+
+    /// Recent update/backfill runs with status, counts and duration
+    Runs {
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+
+    /// Write the empirical trading calendar (dates with any bar) plus
+    /// untraded weekdays as holiday candidates
+    Calendar {
+        #[arg(long, default_value = "calendar.csv")]
+        out: PathBuf,
+    },
+
+    /// Interactive prompt: symbol lookups and read-only SQL
+    Shell,
+
     /// Apply schema migrations without loading data
     Migrate,
 }
 
+impl Command {
+    /// Commands that read many symbols at once and so honour --active-only.
+    fn takes_active_only(&self) -> bool {
+        matches!(
+            self,
+            Command::Symbols
+                | Command::Coverage
+                | Command::Gaps { .. }
+                | Command::Breadth { .. }
+                | Command::DetectSplits { .. }
+        )
+    }
+
+    /// Commands that never write to the database; these open it read-only so
+    /// they don't contend with a running load for the lock.
+    fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            Command::FxSpread { .. }
+                | Command::FxCarry { .. }
+                | Command::Stats { .. }
+                | Command::Symbols
+                | Command::Ticker { .. }
+                | Command::Coverage
+                | Command::Export { .. }
+                | Command::Query { .. }
+                | Command::Drawdown { .. }
+                | Command::Chart { .. }
+                | Command::Compare { .. }
+                | Command::Returns { .. }
+                | Command::Vwap { .. }
+                | Command::Outliers { .. }
+                | Command::Breadth { .. }
+                | Command::Diff { .. }
+                | Command::RealReturns { .. }
+                | Command::DetectSplits { .. }
+                | Command::Indicator { .. }
+                | Command::Signals { .. }
+                | Command::Completeness { .. }
+                | Command::Gaps { .. }
+                | Command::Stale { .. }
+                | Command::Health
+                | Command::Tail { .. }
+                | Command::Audit { .. }
+                | Command::Runs { .. }
+                | Command::Verify
+                | Command::Calendar { .. }
+                | Command::Shell
+        )
+    }
+}
+
+#[derive(Serialize)]
+struct StatsReport {
+    tickers: i64,
+    equity_bars: i64,
+    bar_min_date: Option<NaiveDate>,
+    bar_max_date: Option<NaiveDate>,
+    fx_rates: i64,
+    fx_min_date: Option<NaiveDate>,
+    fx_max_date: Option<NaiveDate>,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -76,21 +543,60 @@ async fn main() -> Result<()> {
         .with(EnvFilter::new(filter))
         .init();
 
-    let config = AppConfig::load()?;
-    let repo = Repository::open(&config.storage.db_path)?;
+    anyhow::ensure!(
+        !cli.active_only || cli.command.takes_active_only(),
+        "--active-only applies only to symbols, coverage, gaps, breadth and detect-splits"
+    );
+    let mut config = AppConfig::load(cli.config.as_deref())?.with_db_override(cli.db.clone());
+    if cli.no_header {
+        config.loader.has_headers = false;
+    }
+    if cli.seed.is_some() {
+        config.scraper.seed = cli.seed;
+    }
+    let window = DateRange::try_new(cli.from, cli.to).context("Invalid --from/--to")?;
+    config.validate()?;
+    // Listing commands filter only on an explicit --exchange; bar reads, loads
+    // and scrapes always use one exchange, so a dual-listed symbol is one series.
+    let exchange_filter = cli.exchange.as_deref().map(normalise_symbol);
+    let exchange = exchange_filter
+        .clone()
+        .unwrap_or_else(|| normalise_symbol(&config.scraper.exchange));
+    config.scraper.exchange = exchange.clone();
+    let on_exchange = Some(exchange.as_str());
+    let repo = if cli.dry_run {
+        Repository::open_dry_run(&config.storage.db_path, config.storage.open_retries)?
+    } else if cli.readonly || cli.command.is_read_only() {
+        Repository::open_readonly(&config.storage.db_path, config.storage.open_retries)?
+    } else {
+        Repository::open(&config.storage.db_path, config.storage.open_retries)?
+            .with_audit(config.storage.audit)
+            .with_write_retries(config.storage.write_retries)
+    };
 
     match cli.command {
         Command::LoadTickers { path } => {
             let _t = utils::Timer::start("Load tickers");
             repo.run_migrations()?;
 
-            let tickers = load_tickers_csv(&path)?;
+            let tickers = load_tickers_csv(&path, &config.loader)?;
             repo.upsert_tickers(&tickers)?;
 
             info!("Loaded {} tickers", tickers.len());
         }
 
-        Command::LoadEquities { dir } => {
+        Command::LoadActions { path } => {
+            repo.run_migrations()?;
+            let actions = load_actions_csv(&path, &config.loader)?;
+            repo.upsert_corporate_actions(&actions)?;
+            info!("Loaded {} corporate actions", actions.len());
+        }
+
+        Command::LoadEquities { dir, mut fast, replace } => {
+            if fast && cli.dry_run {
+                info!("--dry-run validates through the Rust loader; ignoring --fast");
+                fast = false;
+            }
             let _t = utils::Timer::start("Load equities");
             repo.run_migrations()?;
 
@@ -98,27 +604,84 @@ async fn main() -> Result<()> {
             info!("Found {} CSV files in {:?}", files.len(), dir);
 
             let mut total_bars = 0usize;
+            let mut ohlc_rejected = 0usize;
+            let mut empty = 0usize;
             let mut errors = 0usize;
+            // Empty downloads are counted apart from real failures
+            let is_empty =
+                |e: &anyhow::Error| matches!(e.downcast_ref(), Some(LoaderError::Empty(_)));
 
             for path in &files {
-                // Skip tickers.csv (metadata file)
-                if path.file_name().map(|f| f == "tickers.csv").unwrap_or(false) {
-                    continue;
+                // Skip FX and ticker metadata files sharing the directory
+                match classify_csv(path, &config.loader) {
+                    Ok(kind) => {
+                        info!("{:?}: {:?}", path, kind);
+                        if kind != CsvKind::Equity {
+                            continue;
+                        }
+                    }
+                    Err(e) if is_empty(&e) => {
+                        warn!("Skipping {:#}", e);
+                        empty += 1;
+                        continue;
+                    }
+                    Err(e) => {
+                        info!("Error loading {:?}: {:#}", path, e);
+                        errors += 1;
+                        continue;
+                    }
                 }
-                // Skip FX files (e.g. USDNGN_*.csv)
-                if path
-                    .file_stem()
-                    .and_then(|s| s.to_str())
-                    .map(|s| s.to_uppercase().contains("USD") || s.to_uppercase().contains("EUR"))
-                    .unwrap_or(false)
-                {
-                    continue;
+
+                if fast {
+                    let Some(symbol) = extract_symbol_from_filename(path) else {
+                        info!("Error loading {:?}: no symbol in filename", path);
+                        errors += 1;
+                        continue;
+                    };
+                    let loaded = fast_load_date_order(path, &config.loader).and_then(|order| {
+                        repo.bulk_load_equity_csv(path, &symbol, &exchange, &config.loader, order)
+                    });
+                    match loaded {
+                        Ok(0) => {
+                            warn!("Skipping {:?}: no data rows", path);
+                            empty += 1;
+                            continue;
+                        }
+                        Ok(n) => {
+                            total_bars += n;
+                            continue;
+                        }
+                        Err(e)
+                            if matches!(
+                                e.downcast_ref(),
+                                Some(LoaderError::NotFastLoadable { .. })
+                            ) =>
+                        {
+                            info!("{:#}; using the Rust loader", e);
+                        }
+                        Err(e) => {
+                            info!("Error loading {:?}: {:#}", path, e);
+                            errors += 1;
+                            continue;
+                        }
+                    }
                 }
 
-                match load_equity_csv(path) {
-                    Ok((_symbol, bars)) => {
-                        repo.upsert_daily_bars(&bars)?;
-                        total_bars += bars.len();
+                match load_equity_csv(path, &exchange, &config.loader) {
+                    Ok(load) => {
+                        if replace {
+                            repo.replace_daily_bars(&load.bars)?;
+                        } else if load.bars.len() > BULK_INSERT_THRESHOLD {
+                            repo.bulk_insert_bars(&load.bars)?;
+                        } else {
+                            repo.upsert_daily_bars(&load.bars)?;
+                        }
+                        total_bars += load.bars.len();
+                        ohlc_rejected += load.ohlc_rejected;
+                    }
+                    Err(e) if is_empty(&e) => {
+                        warn!("Skipping {:#}", e);
+                        empty += 1;
                     }
                     Err(e) => {
                         info!("Error loading {:?}: {:#}", path, e);
@@ -127,38 +690,124 @@ async fn main() -> Result<()> {
                 }
             }
 
-            info!("Done: {} bars inserted, {} errors", total_bars, errors);
+            info!(
+                "Done: {} bars inserted, {} rejected for OHLC inconsistency, {} files empty, \
+                 {} errors",
+                total_bars, ohlc_rejected, empty, errors
+            );
+            pipeline::run_hooks(&repo, &config.pipeline.hooks);
         }
 
-        Command::LoadFx { dir, source } => {
+        Command::Diff { path } => {
+            let incoming = load_equity_csv(&path, &exchange, &config.loader)?.bars;
+            let dates = incoming.iter().map(|b| b.date);
+            if let (Some(head), Some(first), Some(last)) =
+                (incoming.first(), dates.clone().min(), dates.max())
+            {
+                let symbol = head.symbol.clone();
+                let span = DateRange::new(Some(first), Some(last));
+                let stored = repo.bars_in_range(&symbol, Some(&head.exchange), span, None)?;
+                let diff = diff_bars(&incoming, &stored);
+                let mut rows: Vec<serde_json::Value> = diff
+                    .new
+                    .iter()
+                    .map(|bar| {
+                        serde_json::json!({
+                            "date": bar.date,
+                            "status": "new",
+                            "stored_close": null,
+                            "csv_close": bar.close,
+                            "stored_volume": null,
+                            "csv_volume": bar.volume,
+                        })
+                    })
+                    .chain(diff.changed.iter().map(|(old, bar)| {
+                        serde_json::json!({
+                            "date": bar.date,
+                            "status": "changed",
+                            "stored_close": old.close,
+                            "csv_close": bar.close,
+                            "stored_volume": old.volume,
+                            "csv_volume": bar.volume,
+                        })
+                    }))
+                    .collect();
+                rows.sort_by(|a, b| a["date"].as_str().cmp(&b["date"].as_str()));
+                if !rows.is_empty() {
+                    cli.format.writer().write_values(&rows)?;
+                }
+                let summary = format!(
+                    "{}: {} new, {} changed, {} unchanged",
+                    symbol,
+                    diff.new.len(),
+                    diff.changed.len(),
+                    diff.unchanged
+                );
+                // Keep JSON/CSV on stdout parseable
+                if cli.format == OutputFormat::Text {
+                    println!("{}", summary);
+                } else {
+                    info!("{}", summary);
+                }
+            } else {
+                cli.format.notice(format!("No bars in {:?}.", path));
+            }
+        }
+
+        Command::LoadFx { dir, source, parallel_files } => {
             let _t = utils::Timer::start("Load FX rates");
             repo.run_migrations()?;
 
             let files = discover_csv_files(&dir)?;
             info!("Found {} CSV files in {:?}", files.len(), dir);
 
-            let mut total_rates = 0usize;
-            let mut errors = 0usize;
-
-            for path in &files {
-                // Only process files that look like FX pairs
-                let is_fx = path
-                    .file_stem()
-                    .and_then(|s| s.to_str())
-                    .map(|s| {
-                        let s = s.to_uppercase();
-                        s.contains("USD") || s.contains("EUR") || s.contains("GBP")
-                    })
-                    .unwrap_or(false);
+            // Only process files whose header marks them as FX, each tagged with its source
+            let jobs: Vec<(PathBuf, String)> = files
+                .into_iter()
+                .filter(|path| match classify_csv(path, &config.loader) {
+                    Ok(kind) => {
+                        info!("{:?}: {:?}", path, kind);
+                        kind == CsvKind::Fx
+                    }
+                    Err(e) => {
+                        warn!("Skipping {:?}: {:#}", path, e);
+                        false
+                    }
+                })
+                .map(|path| {
+                    let file_source =
+                        extract_source_from_filename(&path).unwrap_or_else(|| source.clone());
+                    (path, file_source)
+                })
+                .collect();
 
-                if !is_fx {
-                    continue;
+            // Parsing is CPU-bound and independent per file; writes stay serial
+            // through the single DuckDB connection below. A file with a
+            // Pair/Symbol column holds several pairs; otherwise the filename
+            // names the pair.
+            let parse = |(path, src): &(PathBuf, String)| -> Result<Vec<_>> {
+                if is_multi_fx_csv(path, &config.loader)? {
+                    load_multi_fx_csv(path, Some(src), &config.loader)
+                } else {
+                    Ok(vec![load_fx_csv(path, Some(src), &config.loader)?])
                 }
+            };
+            let parsed: Vec<_> = if parallel_files {
+                jobs.par_iter().map(parse).collect()
+            } else {
+                jobs.iter().map(parse).collect()
+            };
 
-                match load_fx_csv(path, Some(&source)) {
-                    Ok((_pair, rates)) => {
-                        repo.upsert_fx_rates(&rates)?;
-                        total_rates += rates.len();
+            let mut total_rates = 0usize;
+            let mut errors = 0usize;
+
+            for ((path, _), result) in jobs.iter().zip(parsed) {
+                match result {
+                    Ok(groups) => {
+                        for (_pair, rates) in groups {
+                            repo.upsert_fx_rates(&rates)?;
+                            total_rates += rates.len();
+                        }
                     }
                     Err(e) => {
                         info!("Error loading {:?}: {:#}", path, e);
@@ -170,52 +819,785 @@ async fn main() -> Result<()> {
             info!("Done: {} rates inserted, {} errors", total_rates, errors);
         }
 
+        Command::FxSpread { pair, a, b } => {
+            let pair = normalise_pair(&pair);
+            let spread = repo.fx_source_spread(&pair, &a, &b)?;
+            if spread.is_empty() && cli.format == OutputFormat::Text {
+                println!("No dates where both {} and {} quote {}.", a, b, pair);
+            } else if cli.format == OutputFormat::Text {
+                println!("{} spread of {} over {} ({} dates):", pair, b, a, spread.len());
+                for (date, pct) in &spread {
+                    println!("  {}  {:>+8.2}%", date, pct);
+                }
+            } else {
+                let rows: Vec<serde_json::Value> = spread
+                    .into_iter()
+                    .map(|(date, pct)| serde_json::json!({ "date": date, "spread_pct": pct }))
+                    .collect();
+                cli.format.writer().write_values(&rows)?;
+            }
+        }
+
+        Command::FxCarry { pair } => {
+            let pair = normalise_pair(&pair);
+            let (Some(from), Some(to)) = (window.from, window.to) else {
+                anyhow::bail!("fx-carry needs both --from and --to");
+            };
+            let change = repo.fx_annualised_change(&pair, from, to)?;
+            match (change, cli.format) {
+                (Some(change), OutputFormat::Text) => println!(
+                    "{} {} → {}: {:+.2}% annualised",
+                    pair,
+                    from,
+                    to,
+                    change * 100.0
+                ),
+                (None, OutputFormat::Text) => println!(
+                    "No annualised change for {} {} → {} (needs --to after --from and a \
+                     positive rate on or before each)",
+                    pair, from, to
+                ),
+                _ => cli.format.writer().write_record(&serde_json::json!({
+                    "pair": pair,
+                    "from": from,
+                    "to": to,
+                    "annualised_change_pct": change.map(|c| c * 100.0),
+                }))?,
+            }
+        }
+
         Command::Update => {
             let _t = utils::Timer::start("Daily update");
-            let stats = Pipeline::new(config).run().await?;
+            repo.run_migrations()?;
+            let hooks = config.pipeline.hooks.clone();
+            let stats = Pipeline::new(config)?.run(&repo).await?;
+            info!(
+                "Done: {} tickers, {} bars, {} errors, {} skipped as up to date",
+                stats.tickers_processed, stats.bars_inserted, stats.errors, stats.skipped
+            );
+            pipeline::run_hooks(&repo, &hooks);
+        }
+
+        Command::Daemon { interval_hours } => {
+            repo.run_migrations()?;
+            let hooks = config.pipeline.hooks.clone();
+            let catch_up_after = config.pipeline.catch_up_after_hours;
+            #[cfg(feature = "metrics")]
+            let metrics = match config.pipeline.metrics_port {
+                Some(port) => Some(metrics::start(port).await?),
+                None => None,
+            };
+            #[cfg(not(feature = "metrics"))]
+            if config.pipeline.metrics_port.is_some() {
+                warn!("pipeline.metrics_port is set but this build lacks the `metrics` feature");
+            }
+            let pipeline = Pipeline::new(config)?;
+            // Listen from the start so a signal mid-cycle lets that cycle (and
+            // its scrape_runs row) finish before we exit.
+            let mut shutdown = tokio::spawn(pipeline::shutdown_signal());
+            loop {
+                let now = Utc::now().naive_utc();
+                let catch_up = match repo.last_successful_run() {
+                    Ok(last_success) => {
+                        match &last_success {
+                            Some(run) => info!(
+                                "Last success was {} hours ago (run {})",
+                                (now - run.finished_at.unwrap_or(run.started_at)).num_hours(),
+                                run.id
+                            ),
+                            None => info!("No successful run recorded yet"),
+                        }
+                        pipeline::needs_catch_up(last_success.as_ref(), now, catch_up_after)
+                    }
+                    Err(e) => {
+                        warn!("Could not read scrape_runs: {:#}", e);
+                        false
+                    }
+                };
+
+                let timer = utils::Timer::start("Scheduled update");
+                let result = if catch_up {
+                    info!("Catching up with a backfill");
+                    pipeline.run_backfill(&repo).await
+                } else {
+                    pipeline.run(&repo).await
+                };
+                #[cfg(feature = "metrics")]
+                if let Some(metrics) = &metrics {
+                    let mut metrics = metrics.lock().unwrap();
+                    metrics.record_cycle(&result, timer.elapsed());
+                    if let Err(e) = metrics.record_counts(&repo) {
+                        warn!("Could not count rows for metrics: {:#}", e);
+                    }
+                }
+                match result {
+                    Ok(stats) => {
+                        info!(
+                            "Cycle done: {} tickers, {} bars, {} errors, {} skipped",
+                            stats.tickers_processed,
+                            stats.bars_inserted,
+                            stats.errors,
+                            stats.skipped
+                        );
+                        pipeline::run_hooks(&repo, &hooks);
+                    }
+                    Err(e) => warn!("Cycle failed: {:#}", e),
+                }
+                drop(timer);
+                if shutdown.is_finished() {
+                    break;
+                }
+
+                let next = pipeline::next_run_after(Utc::now(), interval_hours);
+                info!("Next update at {}", next);
+                let wait = (next - Utc::now()).to_std().unwrap_or_default();
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {}
+                    _ = &mut shutdown => break,
+                }
+            }
+            info!("Daemon stopped.");
+        }
+
+        Command::Seed => {
+            repo.run_migrations()?;
+            let stats = Pipeline::new(config)?.run_full_listing(&repo).await?;
+            info!("Seeded {} tickers from the listing", stats.tickers_processed);
+        }
+
+        Command::Backfill => {
+            let _t = utils::Timer::start("Backfill");
+            repo.run_migrations()?;
+            let hooks = config.pipeline.hooks.clone();
+            let stats = Pipeline::new(config)?.run_backfill(&repo).await?;
             info!(
                 "Done: {} tickers, {} bars, {} errors",
                 stats.tickers_processed, stats.bars_inserted, stats.errors
             );
+            pipeline::run_hooks(&repo, &hooks);
+        }
+
+        Command::UpdateFx { source, pairs } => {
+            repo.run_migrations()?;
+            let fx = source.build(&config.scraper)?;
+            for pair in pairs {
+                let pair = normalise_pair(&pair);
+                match fx.fetch_fx_rates(&pair).await {
+                    Ok(rates) => {
+                        let n = repo.upsert_fx_rates(&rates)?;
+                        info!("{} ({:?}): {} rates", pair, source, n);
+                    }
+                    Err(e) => warn!("{} ({:?}): {:#}", pair, source, e),
+                }
+            }
         }
 
-        Command::Stats => {
-            let bars = repo.bar_count()?;
-            let tickers = repo.ticker_count()?;
-            let fx = repo.fx_count()?;
-            let (min_bar, max_bar) = repo.date_range().unwrap_or((None, None));
-            let (min_fx, max_fx) = repo.fx_date_range().unwrap_or((None, None));
+        Command::Stats { json } => {
+            let (bar_min_date, bar_max_date) = repo.date_range().unwrap_or((None, None));
+            let (fx_min_date, fx_max_date) = repo.fx_date_range().unwrap_or((None, None));
+            let report = StatsReport {
+                tickers: repo.ticker_count()?,
+                equity_bars: repo.bar_count()?,
+                bar_min_date,
+                bar_max_date,
+                fx_rates: repo.fx_count()?,
+                fx_min_date,
+                fx_max_date,
+            };
+            let format = if json { OutputFormat::Json } else { cli.format };
+            format.writer().write_record(&report)?;
+        }
 
-            println!("─────────────────────────────────");
-            println!("  NGX ETL — Database Stats");
-            println!("─────────────────────────────────");
-            println!("  Tickers     : {}", utils::fmt_number(tickers));
-            println!("  Equity bars : {}", utils::fmt_number(bars));
-            println!("    From      : {}", min_bar.map(|d| d.to_string()).unwrap_or("—".into()));
-            println!("    To        : {}", max_bar.map(|d| d.to_string()).unwrap_or("—".into()));
-            println!("  FX rates    : {}", utils::fmt_number(fx));
-            println!("    From      : {}", min_fx.map(|d| d.to_string()).unwrap_or("—".into()));
-            println!("    To        : {}", max_fx.map(|d| d.to_string()).unwrap_or("—".into()));
-            println!("─────────────────────────────────");
+        Command::Health => {
+            cli.format.writer().write_record(&repo.health_snapshot()?)?;
         }
 
         Command::Symbols => {
-            let syms = repo.list_symbols()?;
-            if syms.is_empty() {
+            let syms = repo.list_symbols(exchange_filter.as_deref(), cli.active_only)?;
+            if syms.is_empty() && cli.format == OutputFormat::Text {
                 println!("No symbols — run `ngx-etl load-tickers` first.");
             } else {
-                println!("{} symbols:", syms.len());
-                for s in &syms {
-                    println!("  {}", s);
+                let rows: Vec<Vec<String>> = syms.into_iter().map(|s| vec![s]).collect();
+                cli.format.writer().write_table(&["symbol"], &rows)?;
+            }
+        }
+
+        Command::Ticker { symbol } => {
+            let symbol = normalise_symbol(&symbol);
+            match repo.get_ticker(&symbol)? {
+                Some(ticker) => cli.format.writer().write_record(&ticker)?,
+                None => cli.format.notice(format!(
+                    "Unknown ticker {} — run `ngx-etl load-tickers` first.",
+                    symbol
+                )),
+            }
+        }
+
+        Command::Coverage => {
+            let rows = repo.symbol_coverage(exchange_filter.as_deref(), cli.active_only)?;
+            if rows.is_empty() && cli.format == OutputFormat::Text {
+                println!("No tickers or bars — run `ngx-etl seed` or `load-equities` first.");
+            } else {
+                cli.format.writer().write_records(&rows)?;
+            }
+        }
+
+        Command::Export { out, symbol, partition_by, file_format, table } => {
+            let _t = utils::Timer::start("Export bars");
+            let symbol = symbol.map(|s| normalise_symbol(&s));
+            let format = file_format.unwrap_or_else(|| ExportFormat::from_path(&out));
+            anyhow::ensure!(
+                format == ExportFormat::Parquet || table == "daily_bars",
+                "--table only applies to Parquet exports; CSV and Excel export daily_bars"
+            );
+            let n = match format {
+                ExportFormat::Csv => export_bars_csv(&repo, symbol.as_deref(), &out, partition_by)?,
+                ExportFormat::Xlsx => {
+                    anyhow::ensure!(
+                        partition_by.is_none(),
+                        "--partition-by only applies to CSV exports"
+                    );
+                    export_bars_xlsx(&repo, symbol.as_deref(), &out)?
+                }
+                ExportFormat::Parquet => {
+                    anyhow::ensure!(
+                        symbol.is_none() && partition_by.is_none(),
+                        "Parquet exports whole tables; drop --symbol/--partition-by"
+                    );
+                    repo.export_parquet(&table, &out)?
+                }
+            };
+            info!("Exported {} rows from {} to {:?}", n, table, out);
+        }
+
+        Command::Query { symbol, adjusted, freq, currency } => {
+            let symbol = normalise_symbol(&symbol);
+            let mut bars = match freq {
+                Some(freq) => repo.resample(&symbol, on_exchange, window, freq)?,
+                None => repo.bars_in_range(&symbol, on_exchange, window, None)?,
+            };
+            if currency == Currency::Usd {
+                // Scale every price by the day's rate (close_ngn / close_usd);
+                // bars from before the first USDNGN rate are dropped.
+                let usd: std::collections::HashMap<NaiveDate, f64> =
+                    repo.bars_in_usd(&symbol, on_exchange)?.into_iter().collect();
+                bars.retain_mut(|b| {
+                    let Some(&close_usd) = usd.get(&b.date) else { return false };
+                    let fx = b.close / close_usd;
+                    for price in [&mut b.open, &mut b.high, &mut b.low] {
+                        *price = price.map(|p| p / fx);
+                    }
+                    b.close = close_usd;
+                    true
+                });
+            }
+            if bars.is_empty() && cli.format == OutputFormat::Text {
+                let bounded = window.from.is_some() || window.to.is_some();
+                println!(
+                    "No data for {}{}.",
+                    symbol,
+                    if bounded { " in that date range" } else { "" }
+                );
+            } else if adjusted {
+                let adj: std::collections::HashMap<NaiveDate, f64> =
+                    repo.adjusted_close_series(&symbol, on_exchange)?.into_iter().collect();
+                let rows = bars
+                    .iter()
+                    .map(|b| {
+                        let mut row = serde_json::to_value(b)?;
+                        row["adj_close"] = serde_json::json!(adj.get(&b.date));
+                        Ok(row)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                cli.format.writer().write_values(&rows)?;
+            } else {
+                cli.format.writer().write_records(&bars)?;
+            }
+        }
+
+        Command::Drawdown { symbol } => {
+            let symbol = normalise_symbol(&symbol);
+            match repo.max_drawdown(&symbol, on_exchange, window)? {
+                None => cli.format.notice(format!("No bars for {}.", symbol)),
+                Some((peak, trough, dd)) => {
+                    cli.format.writer().write_record(&serde_json::json!({
+                        "symbol": symbol,
+                        "peak_date": peak,
+                        "trough_date": trough,
+                        "drawdown_pct": dd * 100.0,
+                    }))?;
+                }
+            }
+        }
+
+        Command::Chart { symbol, out, kind } => {
+            let symbol = normalise_symbol(&symbol);
+            let bars =
+                repo.bars_in_range(&symbol, on_exchange, window, Some(chart::MAX_POINTS))?;
+            if bars.is_empty() {
+                println!("No bars for {}.", symbol);
+            } else {
+                chart::render(&symbol, &bars, kind, &out)?;
+                println!("Wrote {} bars for {} to {:?}", bars.len(), symbol, out);
+            }
+        }
+
+        Command::InferSuspended { days } => {
+            anyhow::ensure!(days >= 2, "--days must be at least 2");
+            let flagged = repo.infer_suspended(days)?;
+            if flagged.is_empty() && cli.format == OutputFormat::Text {
+                println!("No new suspensions ({} unchanged closes).", days);
+            } else {
+                let rows: Vec<Vec<String>> = flagged.into_iter().map(|s| vec![s]).collect();
+                cli.format.writer().write_table(&["symbol"], &rows)?;
+            }
+        }
+
+        Command::Compare { a, b, base } => {
+            let symbols = vec![normalise_symbol(&a), normalise_symbol(&b)];
+            let rebased = repo.rebased_series(&symbols, on_exchange, base)?;
+            let rows: Vec<Vec<String>> = rebased
+                .dates
+                .iter()
+                .enumerate()
+                .map(|(j, d)| {
+                    let mut row = vec![d.to_string()];
+                    row.extend(rebased.closes.iter().map(|c| format!("{:.2}", c[j])));
+                    row
+                })
+                .collect();
+            let headers = ["date", symbols[0].as_str(), symbols[1].as_str()];
+            cli.format.writer().write_table(&headers, &rows)?;
+        }
+
+        Command::Returns { symbol, kind } => {
+            let symbol = normalise_symbol(&symbol);
+            let bars = repo.bars_in_range(&symbol, on_exchange, window, None)?;
+            if bars.len() < 2 && cli.format == OutputFormat::Text {
+                println!("Need at least two bars for {} to compute returns.", symbol);
+            } else {
+                let rows: Vec<serde_json::Value> = analytics::daily_returns(&bars, kind)
+                    .into_iter()
+                    .map(|(date, r)| serde_json::json!({ "date": date, "return": r }))
+                    .collect();
+                cli.format.writer().write_values(&rows)?;
+            }
+        }
+
+        Command::Breadth { date, index } => {
+            let breadth = repo.market_breadth(date, on_exchange, cli.active_only)?;
+            let mut record = serde_json::to_value(&breadth)?;
+            if index {
+                let mut by_symbol: std::collections::HashMap<String, Vec<DailyBar>> =
+                    std::collections::HashMap::new();
+                let skipped: Vec<String> =
+                    if cli.active_only { repo.inactive_symbols()? } else { Vec::new() };
+                repo.for_each_bar(None, |bar| {
+                    if bar.exchange == exchange
+                        && !skipped.contains(&bar.symbol)
+                        && bar.date <= date
+                        && window.from.is_none_or(|from| bar.date >= from)
+                    {
+                        by_symbol.entry(bar.symbol.clone()).or_default().push(bar);
+                    }
+                    Ok(())
+                })?;
+                let level = analytics::equal_weight_index(&by_symbol).last().map(|(_, v)| *v);
+                record["equal_weight_index"] = serde_json::json!(level);
+            }
+            if breadth.advancers + breadth.decliners + breadth.unchanged == 0
+                && cli.format == OutputFormat::Text
+            {
+                println!("No bars with change_pct stored for {}; counts are zero.", date);
+            }
+            cli.format.writer().write_value(&record)?;
+        }
+
+        Command::Outliers { symbol, threshold } => {
+            anyhow::ensure!(threshold > 0.0, "--threshold must be positive");
+            let symbol = normalise_symbol(&symbol);
+            let bars = repo.bars_in_range(&symbol, on_exchange, window, None)?;
+            let flagged = analytics::flag_outliers(&bars, threshold);
+            if flagged.is_empty() && cli.format == OutputFormat::Text {
+                println!(
+                    "No daily moves beyond {}% for {} in {} bars.",
+                    threshold,
+                    symbol,
+                    bars.len()
+                );
+            } else {
+                let rows: Vec<serde_json::Value> = flagged
+                    .into_iter()
+                    .map(|(date, pct)| serde_json::json!({ "date": date, "move_pct": pct }))
+                    .collect();
+                cli.format.writer().write_values(&rows)?;
+            }
+        }
+
+        Command::Vwap { symbol, window: vwap_window } => {
+            let symbol = normalise_symbol(&symbol);
+            let bars = repo.bars_in_range(&symbol, on_exchange, window, None)?;
+            if bars.is_empty() && cli.format == OutputFormat::Text {
+                println!("No bars for {}.", symbol);
+            } else {
+                let rows: Vec<serde_json::Value> = analytics::vwap(&bars, vwap_window as usize)
+                    .into_iter()
+                    .zip(&bars)
+                    .map(|((date, vwap), bar)| {
+                        serde_json::json!({ "date": date, "close": bar.close, "vwap": vwap })
+                    })
+                    .collect();
+                cli.format.writer().write_values(&rows)?;
+            }
+        }
+
+        Command::RealReturns { symbol } => {
+            let symbol = normalise_symbol(&symbol);
+            let rows = repo.real_returns(&symbol, on_exchange, window)?;
+            if rows.is_empty() && cli.format == OutputFormat::Text {
+                println!("No bars for {}.", symbol);
+            } else {
+                if rows.iter().all(|r| r.fx.is_none()) {
+                    warn!("No USDNGN rates on or before {}'s bars; USD columns are empty", symbol);
+                }
+                cli.format.writer().write_records(&rows)?;
+            }
+        }
+
+        Command::DetectSplits { tolerance_pct } => {
+            let candidates =
+                repo.detect_possible_splits(on_exchange, cli.active_only, window, tolerance_pct)?;
+            if candidates.is_empty() && cli.format == OutputFormat::Text {
+                println!("No split-like moves found.");
+            } else {
+                let rows: Vec<serde_json::Value> = candidates
+                    .iter()
+                    .map(|(symbol, date, ratio)| {
+                        serde_json::json!({ "symbol": symbol, "date": date, "ratio": ratio })
+                    })
+                    .collect();
+                cli.format.writer().write_values(&rows)?;
+            }
+        }
+
+        Command::Indicator { symbol, kind, window: period } => {
+            let symbol = normalise_symbol(&symbol);
+            let series = repo.close_series(&symbol, on_exchange, window)?;
+            if series.closes.is_empty() && cli.format == OutputFormat::Text {
+                println!("No bars for {}.", symbol);
+            } else {
+                let rows: Vec<serde_json::Value> = kind
+                    .compute(&series.closes, period as usize)
+                    .into_iter()
+                    .zip(series.dates.iter().zip(&series.closes))
+                    .map(|(value, (date, close))| {
+                        serde_json::json!({ "date": date, "close": close, "value": value })
+                    })
+                    .collect();
+                cli.format.writer().write_values(&rows)?;
+            }
+        }
+
+        Command::Signals { symbol, fast, slow } => {
+            anyhow::ensure!(
+                fast >= 1 && fast < slow,
+                "--fast must be at least 1 and below --slow"
+            );
+            let symbol = normalise_symbol(&symbol);
+            let series = repo.close_series(&symbol, on_exchange, window)?;
+            let signals = analytics::ema_crossover(&series.closes, fast, slow);
+            if signals.is_empty() && cli.format == OutputFormat::Text {
+                println!(
+                    "No EMA({}/{}) crossovers for {} in {} bars.",
+                    fast,
+                    slow,
+                    symbol,
+                    series.closes.len()
+                );
+            } else {
+                let rows: Vec<serde_json::Value> = signals
+                    .iter()
+                    .map(|s| {
+                        serde_json::json!({
+                            "date": series.dates[s.date_idx],
+                            "signal": s.kind,
+                            "close": series.closes[s.date_idx],
+                        })
+                    })
+                    .collect();
+                cli.format.writer().write_values(&rows)?;
+            }
+        }
+
+        Command::Completeness { symbol, year } => {
+            let symbol = normalise_symbol(&symbol);
+            let year = year.unwrap_or_else(|| Utc::now().year());
+            let holidays = config.calendar.holidays_in_year(year);
+            let (present, expected) =
+                repo.trading_day_coverage(&symbol, on_exchange, year, &holidays)?;
+            let pct = if expected > 0 { present as f64 / expected as f64 * 100.0 } else { 0.0 };
+            match cli.format {
+                OutputFormat::Text => println!(
+                    "{}: {} of {} expected trading days in {} ({:.1}%)",
+                    symbol, present, expected, year, pct
+                ),
+                _ => cli.format.writer().write_record(&serde_json::json!({
+                    "symbol": symbol,
+                    "year": year,
+                    "present": present,
+                    "expected": expected,
+                    "coverage_pct": pct,
+                }))?,
+            }
+        }
+
+        Command::Gaps { symbol, max_gap_days } => {
+            let symbols = match symbol {
+                Some(s) => vec![normalise_symbol(&s)],
+                None => repo.list_symbols(exchange_filter.as_deref(), cli.active_only)?,
+            };
+            let mut rows = Vec::new();
+            for symbol in &symbols {
+                for (from, to) in repo.find_date_gaps(symbol, on_exchange, window, max_gap_days)? {
+                    rows.push(serde_json::json!({
+                        "symbol": symbol,
+                        "from": from,
+                        "to": to,
+                        "days": (to - from).num_days(),
+                    }));
+                }
+            }
+            if rows.is_empty() && cli.format == OutputFormat::Text {
+                println!("No gaps longer than {} days.", max_gap_days);
+            } else {
+                cli.format.writer().write_values(&rows)?;
+            }
+        }
+
+        Command::Stale { hours } => {
+            anyhow::ensure!(hours > 0, "--hours must be positive");
+            let stale = repo.stale_symbols(hours)?;
+            if stale.is_empty() && cli.format == OutputFormat::Text {
+                println!("Every symbol was scraped within the last {} hours.", hours);
+            } else {
+                let now = Utc::now().naive_utc();
+                let rows: Vec<serde_json::Value> = stale
+                    .into_iter()
+                    .map(|(symbol, last_scraped)| {
+                        serde_json::json!({
+                            "symbol": symbol,
+                            "last_scraped": last_scraped,
+                            "hours_ago": (now - last_scraped).num_hours(),
+                        })
+                    })
+                    .collect();
+                cli.format.writer().write_values(&rows)?;
+            }
+        }
+
+        Command::Shell => {
+            shell::run(&repo, cli.format)?;
+        }
+
+        Command::Rename { old, new } => {
+            repo.run_migrations()?;
+            let report = repo.rename_symbol(&normalise_symbol(&old), &normalise_symbol(&new))?;
+            if cli.format == OutputFormat::Text {
+                println!(
+                    "{} {} → {}: {} bars moved ({} merged into existing dates){}",
+                    if cli.dry_run { "Would rename" } else { "Renamed" },
+                    report.old,
+                    report.new,
+                    report.bars_moved,
+                    report.bars_merged,
+                    if report.ticker_moved { ", ticker moved" } else { "" }
+                );
+            } else {
+                cli.format.writer().write_record(&report)?;
+            }
+        }
+
+        Command::Delete { symbol, yes } => {
+            repo.run_migrations()?;
+            let symbol = normalise_symbol(&symbol);
+            if !yes && !utils::confirm(&format!("Delete all stored data for {}?", symbol))? {
+                println!("Aborted.");
+                return Ok(());
+            }
+            let removed = repo.delete_symbol(&symbol)?;
+            println!("Deleted {} rows for {}.", removed, symbol);
+        }
+
+        Command::Prune { before, keep_years, checkpoint, yes } => {
+            repo.run_migrations()?;
+            let cutoff = match (before, keep_years) {
+                (Some(date), _) => date,
+                (None, Some(years)) => Utc::now()
+                    .date_naive()
+                    .checked_sub_months(chrono::Months::new(years.saturating_mul(12)))
+                    .context("--keep-years reaches before the earliest representable date")?,
+                (None, None) => unreachable!("clap requires --before or --keep-years"),
+            };
+            let prompt = format!("Delete all bars and FX rates dated before {}?", cutoff);
+            if !yes && !utils::confirm(&prompt)? {
+                println!("Aborted.");
+                return Ok(());
+            }
+            let removed = repo.prune_before(cutoff)?;
+            if checkpoint {
+                repo.checkpoint()?;
+            }
+            println!(
+                "Deleted {} rows dated before {}; {} bars remain.",
+                removed,
+                cutoff,
+                repo.bar_count()?
+            );
+        }
+
+        Command::Calendar { out } => {
+            let trading = repo.distinct_trading_dates()?;
+            let holidays = utils::holiday_candidates(&trading);
+            let mut days: Vec<(NaiveDate, &str)> = trading
+                .iter()
+                .map(|d| (*d, "trading"))
+                .chain(holidays.iter().map(|d| (*d, "holiday_candidate")))
+                .collect();
+            days.sort();
+
+            let mut w = csv::Writer::from_path(&out)
+                .with_context(|| format!("Could not create {:?}", out))?;
+            w.write_record(["date", "kind"])?;
+            for (date, kind) in &days {
+                w.write_record([date.to_string().as_str(), kind])?;
+            }
+            w.flush()?;
+            println!(
+                "Wrote {} trading dates and {} holiday candidates to {:?}",
+                trading.len(),
+                holidays.len(),
+                out
+            );
+        }
+
+        Command::NormalizeSymbols => {
+            let reports = repo.normalize_symbols()?;
+            if reports.is_empty() && cli.format == OutputFormat::Text {
+                println!("All stored symbols are already normalised.");
+            } else {
+                if cli.dry_run && cli.format == OutputFormat::Text {
+                    println!("Dry run — nothing written:");
+                }
+                cli.format.writer().write_records(&reports)?;
+            }
+        }
+
+        Command::Tail { symbol, lines, follow, interval_secs } => {
+            let symbol = symbol.as_deref().map(normalise_symbol);
+            let bars = repo.recent_bars(symbol.as_deref(), lines)?;
+            if !bars.is_empty() {
+                cli.format.writer().write_records(&bars)?;
+            }
+            if follow {
+                let mut watermark = bars
+                    .iter()
+                    .map(|b| b.scraped_at)
+                    .max()
+                    .unwrap_or_else(|| Utc::now().naive_utc());
+                // Release the file between polls so a concurrent writer
+                // (e.g. a running update) can take its lock.
+                drop(repo);
+                let interval = std::time::Duration::from_secs(interval_secs);
+                loop {
+                    tokio::select! {
+                        _ = tokio::signal::ctrl_c() => break,
+                        _ = tokio::time::sleep(interval) => {}
+                    }
+                    let fresh = match Repository::open_readonly(
+                        &config.storage.db_path,
+                        config.storage.open_retries,
+                    ) {
+                        Ok(poll) => poll.bars_scraped_after(symbol.as_deref(), watermark)?,
+                        Err(e) => {
+                            warn!("Poll skipped: {:#}", e);
+                            continue;
+                        }
+                    };
+                    if let Some(latest) = fresh.iter().map(|b| b.scraped_at).max() {
+                        watermark = latest;
+                        cli.format.writer().write_records(&fresh)?;
+                    }
+                }
+            }
+        }
+
+        Command::Audit { limit } => {
+            let entries = repo.recent_audit(limit)?;
+            if entries.is_empty() && cli.format == OutputFormat::Text {
+                println!("Audit log is empty (enable with storage.audit = true).");
+            } else {
+                cli.format.writer().write_records(&entries)?;
+            }
+        }
+
+        Command::Verify => {
+            let issues = repo.verify()?;
+            if issues.is_empty() && cli.format == OutputFormat::Text {
+                println!("All checks passed.");
+                return Ok(());
+            }
+            cli.format.writer().write_records(&issues)?;
+            if !issues.is_empty() {
+                let mut counts = std::collections::BTreeMap::new();
+                for issue in &issues {
+                    *counts.entry(issue.check.as_str()).or_insert(0usize) += 1;
                 }
+                let summary: Vec<String> =
+                    counts.iter().map(|(check, n)| format!("{} {}", n, check)).collect();
+                anyhow::bail!("{} issues found: {}", issues.len(), summary.join(", "));
+            }
+        }
+
+        Command::Runs { limit } => {
+            let runs = repo.recent_runs(limit)?;
+            if runs.is_empty() && cli.format == OutputFormat::Text {
+                println!("No scrape runs recorded yet.");
+            } else {
+                cli.format.writer().write_records(&runs)?;
             }
         }
 
         Command::Migrate => {
             repo.run_migrations()?;
-            println!("Migrations applied.");
+            println!("Migrations applied; schema at version {}.", repo.schema_version()?);
         }
     }
 
+    if cli.dry_run {
+        info!("Dry run: nothing was written to the database");
+    }
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(args: &[&str]) -> Result<DateRange> {
+        let cli = Cli::try_parse_from(["ngx-etl"].iter().chain(args))?;
+        DateRange::try_new(cli.from, cli.to)
+    }
+
+    #[test]
+    fn test_date_window_parses_and_rejects_reversed_bounds() {
+        let d = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        let range = window(&["query", "GTCO", "--from", "2024-01-02", "--max-date", "2024-03-28"]);
+        assert_eq!(range.unwrap(), DateRange::new(Some(d("2024-01-02")), Some(d("2024-03-28"))));
+        assert_eq!(window(&["--to", "2024-01-02", "gaps"]).unwrap().from, None);
+        let one_day = window(&["--from", "2024-01-02", "--to", "2024-01-02", "gaps"]);
+        assert!(one_day.is_ok());
+
+        let err = window(&["--from", "2024-03-28", "--to", "2024-01-02", "gaps"]).unwrap_err();
+        assert!(err.to_string().contains("ends earlier"), "{}", err);
+        assert!(window(&["--from", "28/03/2024", "gaps"]).is_err());
+    }
+}
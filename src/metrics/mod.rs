@@ -0,0 +1,205 @@
+//! Prometheus text-format metrics for `daemon` (cargo feature `metrics`).
+//!
+//! The daemon records each cycle's outcome and the database row counts into
+//! a shared [`DaemonMetrics`]; a tiny HTTP listener on `pipeline.metrics_port`
+//! serves them at `GET /metrics`. Counts are as of the last cycle, so a scrape
+//! never waits on the database.
+
+use crate::pipeline::PipelineStats;
+use crate::storage::Repository;
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, info};
+
+/// Request heads longer than this are cut off; we only need the first line.
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+#[derive(Debug, Default, Clone)]
+pub struct DaemonMetrics {
+    runs_succeeded: u64,
+    runs_failed: u64,
+    last_bars_inserted: usize,
+    last_errors: usize,
+    last_duration_secs: f64,
+    db_bars: i64,
+    db_tickers: i64,
+    db_fx_rates: i64,
+}
+
+pub type SharedMetrics = Arc<Mutex<DaemonMetrics>>;
+
+impl DaemonMetrics {
+    /// Fold in one cycle. A failed cycle counts as one error with no bars.
+    pub fn record_cycle(&mut self, result: &Result<PipelineStats>, duration: Duration) {
+        match result {
+            Ok(stats) => {
+                self.runs_succeeded += 1;
+                self.last_bars_inserted = stats.bars_inserted;
+                self.last_errors = stats.errors;
+            }
+            Err(_) => {
+                self.runs_failed += 1;
+                self.last_bars_inserted = 0;
+                self.last_errors = 1;
+            }
+        }
+        self.last_duration_secs = duration.as_secs_f64();
+    }
+
+    /// Refresh the table row counts.
+    pub fn record_counts(&mut self, repo: &Repository) -> Result<()> {
+        self.db_bars = repo.bar_count()?;
+        self.db_tickers = repo.ticker_count()?;
+        self.db_fx_rates = repo.fx_count()?;
+        Ok(())
+    }
+
+    /// Prometheus text exposition format (version 0.0.4).
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, samples: &[(&str, String)]| {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+            for (labels, value) in samples {
+                let _ = writeln!(out, "{}{} {}", name, labels, value);
+            }
+        };
+        metric(
+            "ngx_scrape_runs_total",
+            "counter",
+            "Daemon cycles finished since start, by outcome.",
+            &[
+                (r#"{status="success"}"#, self.runs_succeeded.to_string()),
+                (r#"{status="error"}"#, self.runs_failed.to_string()),
+            ],
+        );
+        metric(
+            "ngx_last_run_bars_inserted",
+            "gauge",
+            "Bars written by the last cycle.",
+            &[("", self.last_bars_inserted.to_string())],
+        );
+        metric(
+            "ngx_last_run_errors",
+            "gauge",
+            "Symbols that failed in the last cycle (1 if the whole cycle failed).",
+            &[("", self.last_errors.to_string())],
+        );
+        metric(
+            "ngx_last_run_duration_seconds",
+            "gauge",
+            "Wall-clock length of the last cycle.",
+            &[("", format!("{:.3}", self.last_duration_secs))],
+        );
+        metric(
+            "ngx_db_rows",
+            "gauge",
+            "Rows per table as of the last cycle.",
+            &[
+                (r#"{table="daily_bars"}"#, self.db_bars.to_string()),
+                (r#"{table="tickers"}"#, self.db_tickers.to_string()),
+                (r#"{table="fx_rates"}"#, self.db_fx_rates.to_string()),
+            ],
+        );
+        out
+    }
+}
+
+/// Bind `0.0.0.0:port` and serve `/metrics` in the background.
+pub async fn start(port: u16) -> Result<SharedMetrics> {
+    let listener = TcpListener::bind(("0.0.0.0", port))
+        .await
+        .with_context(|| format!("Could not bind metrics port {}", port))?;
+    info!("Serving metrics on http://{}/metrics", listener.local_addr()?);
+    let metrics = SharedMetrics::default();
+    tokio::spawn(serve(listener, metrics.clone()));
+    Ok(metrics)
+}
+
+async fn serve(listener: TcpListener, metrics: SharedMetrics) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let metrics = metrics.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = respond(stream, &metrics).await {
+                        debug!("metrics request: {:#}", e);
+                    }
+                });
+            }
+            Err(e) => debug!("metrics accept: {}", e),
+        }
+    }
+}
+
+/// Answer one request: the metrics for `GET /metrics`, 404 otherwise.
+async fn respond(mut stream: TcpStream, metrics: &SharedMetrics) -> Result<()> {
+    let mut buf = vec![0u8; MAX_REQUEST_BYTES];
+    let mut len = 0;
+    while len < buf.len() && !buf[..len].windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut buf[len..]).await?;
+        if n == 0 {
+            break;
+        }
+        len += n;
+    }
+    let head = String::from_utf8_lossy(&buf[..len]);
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+
+    let (status, content_type, body) = match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some("/metrics")) => (
+            "200 OK",
+            "text/plain; version=0.0.4",
+            metrics.lock().unwrap().render(),
+        ),
+        _ => ("404 Not Found", "text/plain", "not found\n".to_string()),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_serves_last_cycle() {
+        let metrics = SharedMetrics::default();
+        let stats = PipelineStats { bars_inserted: 148, errors: 2, ..Default::default() };
+        metrics.lock().unwrap().record_cycle(&Ok(stats), Duration::from_millis(1500));
+        metrics.lock().unwrap().record_cycle(&Err(anyhow::anyhow!("down")), Duration::ZERO);
+
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, metrics));
+
+        let client = reqwest::Client::new();
+        let body = client
+            .get(format!("http://{}/metrics", addr))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert!(body.contains("ngx_scrape_runs_total{status=\"success\"} 1\n"), "{}", body);
+        assert!(body.contains("ngx_scrape_runs_total{status=\"error\"} 1\n"));
+        assert!(body.contains("ngx_last_run_errors 1\n"));
+        assert!(body.contains("# TYPE ngx_db_rows gauge\n"));
+
+        let missing = client.get(format!("http://{}/", addr)).send().await.unwrap();
+        assert_eq!(missing.status(), 404);
+    }
+}
@@ -1,4 +1,5 @@
 use chrono::{NaiveDate, NaiveDateTime};
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 
 // ── Ticker ────────────────────────────────────────────────────────────────────
@@ -6,18 +7,56 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Ticker {
     pub symbol: String,
-    pub name: String,
+    pub name: Option<String>,      // None = not reported, keeps the stored name
     pub sector: Option<String>,
     pub industry: Option<String>,
-    pub exchange: Option<String>,  // Lagos, Abuja
+    pub exchange: Option<String>,  // NGX, GSE, …
+    pub isin: Option<String>,
+    pub board: Option<String>,     // Premium, Main, Growth, …
+    pub status: Option<TickerStatus>,  // None = not reported, treated as active
     pub scraped_at: NaiveDateTime,
 }
 
+/// Trading status. Suspended names typically keep showing their last close,
+/// which reads as a run of zero returns.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TickerStatus {
+    Active,
+    Suspended,
+    Delisted,
+}
+
+impl TickerStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TickerStatus::Active => "active",
+            TickerStatus::Suspended => "suspended",
+            TickerStatus::Delisted => "delisted",
+        }
+    }
+
+    /// Lenient match on page/CSV labels ("Suspended", "Trading halted", …).
+    pub fn from_label(label: &str) -> Option<Self> {
+        let l = label.trim().to_lowercase();
+        if l.contains("delist") {
+            Some(TickerStatus::Delisted)
+        } else if l.contains("suspend") || l.contains("halt") {
+            Some(TickerStatus::Suspended)
+        } else if l == "active" || l == "trading" || l == "listed" {
+            Some(TickerStatus::Active)
+        } else {
+            None
+        }
+    }
+}
+
 // ── Equity daily bar ──────────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct DailyBar {
     pub symbol: String,
+    pub exchange: String,          // market the bar was sourced from, e.g. "NGX"
     pub date: NaiveDate,
     pub open: Option<f64>,
     pub high: Option<f64>,
@@ -28,6 +67,22 @@ pub struct DailyBar {
     pub scraped_at: NaiveDateTime,
 }
 
+impl DailyBar {
+    /// (high + low + close) / 3, or just the close when high/low are missing.
+    pub fn typical_price(&self) -> f64 {
+        match (self.high, self.low) {
+            (Some(high), Some(low)) => (high + low + self.close) / 3.0,
+            _ => self.close,
+        }
+    }
+
+    /// Day's high − low, when both are known.
+    #[allow(dead_code)]
+    pub fn range(&self) -> Option<f64> {
+        Some(self.high? - self.low?)
+    }
+}
+
 // ── FX rate ───────────────────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -43,6 +98,210 @@ pub struct FxRate {
     pub scraped_at: NaiveDateTime,
 }
 
+// ── Corporate actions ─────────────────────────────────────────────────────────
+
+/// A split or cash dividend taking effect on `ex_date`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CorporateAction {
+    pub symbol: String,
+    pub ex_date: NaiveDate,
+    pub action_type: ActionType,
+    /// Splits: new shares per old share (2.0 for a 2-for-1). Dividends: naira per share.
+    pub ratio_or_amount: f64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ActionType {
+    Split,
+    Dividend,
+}
+
+impl ActionType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ActionType::Split => "split",
+            ActionType::Dividend => "dividend",
+        }
+    }
+
+    /// Lenient match on CSV labels ("Stock split", "Bonus", "Cash dividend", …).
+    /// Bonus issues adjust prices exactly like splits.
+    pub fn from_label(label: &str) -> Option<Self> {
+        let l = label.trim().to_lowercase();
+        if l.contains("split") || l.contains("bonus") {
+            Some(ActionType::Split)
+        } else if l.contains("div") {
+            Some(ActionType::Dividend)
+        } else {
+            None
+        }
+    }
+}
+
+// ── Date window ───────────────────────────────────────────────────────────────
+
+/// Optional inclusive date bounds applied to analytics queries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DateRange {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+impl DateRange {
+    pub fn new(from: Option<NaiveDate>, to: Option<NaiveDate>) -> Self {
+        Self { from, to }
+    }
+
+    /// Like `new`, but refuses a window that ends before it starts, which
+    /// would otherwise just match nothing.
+    pub fn try_new(from: Option<NaiveDate>, to: Option<NaiveDate>) -> anyhow::Result<Self> {
+        if let (Some(from), Some(to)) = (from, to) {
+            anyhow::ensure!(from <= to, "date range starts {} but ends earlier, {}", from, to);
+        }
+        Ok(Self { from, to })
+    }
+}
+
+/// Higher timeframe for aggregating daily bars.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Resample {
+    /// ISO weeks, starting Monday
+    Weekly,
+    Monthly,
+}
+
+impl Resample {
+    /// DuckDB `date_trunc` part.
+    pub fn date_part(self) -> &'static str {
+        match self {
+            Resample::Weekly => "week",
+            Resample::Monthly => "month",
+        }
+    }
+}
+
+/// Currency to report equity prices in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Currency {
+    Ngn,
+    /// Converted at the as-of USDNGN rate (see `Repository::bars_in_usd`)
+    Usd,
+}
+
+// ── Coverage ──────────────────────────────────────────────────────────────────
+
+/// Per-symbol history coverage: how many bars we hold and over what span.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CoverageRow {
+    pub symbol: String,
+    pub bars: i64,
+    pub first_date: Option<NaiveDate>,
+    pub last_date: Option<NaiveDate>,
+}
+
+// ── Market breadth ────────────────────────────────────────────────────────────
+
+/// How many symbols rose, fell or held on one date, by the sign of their
+/// stored `change_pct` (bars without one are not counted).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Breadth {
+    pub date: NaiveDate,
+    pub advancers: i64,
+    pub decliners: i64,
+    pub unchanged: i64,
+}
+
+// ── Health snapshot ───────────────────────────────────────────────────────────
+
+/// Everything monitoring needs in one record: volumes, date spans, how the
+/// last scrape run went and how many symbols have fallen behind.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HealthSnapshot {
+    pub tickers: i64,
+    pub equity_bars: i64,
+    pub bar_min_date: Option<NaiveDate>,
+    pub bar_max_date: Option<NaiveDate>,
+    /// Symbols whose last bar is more than a week behind `bar_max_date`
+    pub stale_symbols: i64,
+    pub fx_rates: i64,
+    pub fx_min_date: Option<NaiveDate>,
+    pub fx_max_date: Option<NaiveDate>,
+    pub fx_pairs: Vec<String>,
+    pub last_run_status: Option<String>,
+    pub last_run_started_at: Option<NaiveDateTime>,
+    pub last_run_duration_secs: Option<i64>,
+}
+
+// ── Verification ──────────────────────────────────────────────────────────────
+
+/// One broken invariant found by `Repository::verify`, e.g.
+/// `high_below_low` in `daily_bars` at "GTCO 2024-01-02".
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VerifyIssue {
+    pub check: String,
+    pub table_name: String,
+    pub key: String,
+    pub detail: String,
+}
+
+// ── Symbol rename ─────────────────────────────────────────────────────────────
+
+/// Outcome of moving one symbol's data onto another.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RenameReport {
+    pub old: String,
+    pub new: String,
+    pub bars_moved: usize,
+    /// Dates both symbols had; resolved with upsert semantics (moved bar wins,
+    /// missing fields kept from the existing one).
+    pub bars_merged: usize,
+    pub ticker_moved: bool,
+}
+
+// ── Real returns ──────────────────────────────────────────────────────────────
+
+/// One row of `bars_usd` with day-over-day returns in both currencies.
+/// `fx`/`close_usd`/`return_usd_pct` are `None` before the first USDNGN rate.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RealReturn {
+    pub date: NaiveDate,
+    pub close_ngn: f64,
+    pub fx: Option<f64>,
+    pub close_usd: Option<f64>,
+    pub return_ngn_pct: Option<f64>,
+    pub return_usd_pct: Option<f64>,
+}
+
+// ── Audit log ─────────────────────────────────────────────────────────────────
+
+/// One mutating batch against the DB, e.g. "upsert_daily_bars, 142 rows, run 42".
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditEntry {
+    pub at: NaiveDateTime,
+    pub operation: String,
+    pub table_name: String,
+    pub row_key: Option<String>,   // the one symbol/pair touched, or "<n> keys"
+    pub row_count: i64,
+    pub run_id: Option<i64>,
+}
+
+// ── Scrape runs ───────────────────────────────────────────────────────────────
+
+/// One `update`/`backfill` invocation from `scrape_runs`. `duration_secs` is
+/// `finished_at - started_at`, `None` while the run is still going (or died).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScrapeRun {
+    pub id: i64,
+    pub started_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+    pub duration_secs: Option<i64>,
+    pub status: String,
+    pub tickers_processed: i64,
+    pub bars_inserted: i64,
+    pub error_msg: Option<String>,
+}
+
 // ── Raw CSV rows ──────────────────────────────────────────────────────────────
 
 /// investing.com equity CSV: Date, Price, Open, High, Low, Volume, Change%
@@ -69,7 +328,7 @@ pub struct RawFxCsvRow {
     pub change_pct: Option<String>,
 }
 
-/// Ticker metadata CSV: symbol, name, sector, industry, exchange
+/// Ticker metadata CSV: symbol, name, sector, industry, exchange[, isin, board, status]
 #[derive(Debug, Clone, Default)]
 pub struct RawTickerRow {
     pub symbol: Option<String>,
@@ -77,4 +336,45 @@ pub struct RawTickerRow {
     pub sector: Option<String>,
     pub industry: Option<String>,
     pub exchange: Option<String>,
-}
\ No newline at end of file
+    pub isin: Option<String>,
+    pub board: Option<String>,
+    pub status: Option<String>,
+}
+
+/// Corporate actions CSV: symbol, ex_date, action_type, ratio_or_amount
+#[derive(Debug, Clone, Default)]
+pub struct RawActionRow {
+    pub symbol: Option<String>,
+    pub ex_date: Option<String>,
+    pub action_type: Option<String>,
+    pub ratio_or_amount: Option<String>,
+}
+
+// ── Raw scraped rows ──────────────────────────────────────────────────────────
+
+/// kwayisi listing page: Symbol | Name | Price | Change | Change% | Volume | Deals
+/// (only symbol/name feed `tickers`; quotes come from the ticker pages)
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default)]
+pub struct RawEquityRow {
+    pub symbol: Option<String>,
+    pub name: Option<String>,
+    pub price: Option<String>,
+    pub change: Option<String>,
+    pub change_pct: Option<String>,
+    pub volume: Option<String>,
+    pub deals: Option<String>,
+}
+
+/// kwayisi ticker page history: Date | Close | Change | Change% | Volume | Deals
+/// (free pages carry no open/high/low; the fallback layout may)
+#[derive(Debug, Clone, Default)]
+pub struct RawHistoricalRow {
+    pub date: Option<String>,
+    pub open: Option<String>,
+    pub high: Option<String>,
+    pub low: Option<String>,
+    pub close: Option<String>,
+    pub change: Option<String>,   // absolute, in price units
+    pub volume: Option<String>,
+}
@@ -0,0 +1,335 @@
+//! Command output rendering: aligned text tables, JSON, or CSV.
+//!
+//! Commands hand their results to an [`OutputWriter`] chosen by the global
+//! `--format` flag instead of formatting inline, so every command is scriptable
+//! the same way.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::Display;
+use std::io::{self, Write};
+
+use crate::utils::fmt_number;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+impl OutputFormat {
+    /// Writer for this format targeting stdout.
+    pub fn writer(self) -> Box<dyn OutputWriter> {
+        match self {
+            OutputFormat::Text => Box::new(TextWriter::new(io::stdout())),
+            OutputFormat::Json => Box::new(JsonWriter::new(io::stdout())),
+            OutputFormat::Csv => Box::new(CsvWriter::new(io::stdout())),
+        }
+    }
+
+    /// Print a note for a person, e.g. "No bars for X." in place of a result.
+    /// It goes to stdout for text and to stderr otherwise, so JSON/CSV on
+    /// stdout stays parseable.
+    pub fn notice(self, message: impl Display) {
+        match self {
+            OutputFormat::Text => println!("{}", message),
+            OutputFormat::Json | OutputFormat::Csv => eprintln!("{}", message),
+        }
+    }
+}
+
+pub trait OutputWriter {
+    /// Write pre-formatted cells under the given headers.
+    fn write_table(&mut self, headers: &[&str], rows: &[Vec<String>]) -> Result<()>;
+
+    /// Write a list of records (JSON objects; keys become column headers).
+    fn write_values(&mut self, records: &[Value]) -> Result<()>;
+
+    /// Write a single record, e.g. a summary report.
+    fn write_value(&mut self, record: &Value) -> Result<()>;
+}
+
+impl dyn OutputWriter + '_ {
+    pub fn write_records<T: Serialize>(&mut self, items: &[T]) -> Result<()> {
+        let values = items
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.write_values(&values)
+    }
+
+    pub fn write_record<T: Serialize>(&mut self, item: &T) -> Result<()> {
+        self.write_value(&serde_json::to_value(item)?)
+    }
+}
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+/// Column headers from the first record's keys (serde field order).
+fn record_headers(records: &[Value]) -> Vec<String> {
+    match records.first() {
+        Some(Value::Object(map)) => map.keys().cloned().collect(),
+        _ => vec!["value".to_string()],
+    }
+}
+
+fn record_cells(record: &Value, headers: &[String], null: &str) -> Vec<String> {
+    match record {
+        Value::Object(map) => headers
+            .iter()
+            .map(|h| map.get(h).map(|v| cell(v, null)).unwrap_or_else(|| null.to_string()))
+            .collect(),
+        other => vec![cell(other, null)],
+    }
+}
+
+fn cell(value: &Value, null: &str) -> String {
+    match value {
+        Value::Null => null.to_string(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+// ── Text ──────────────────────────────────────────────────────────────────────
+
+/// Like [`cell`], but integers get thousands separators for readability.
+fn text_cell(value: &Value) -> String {
+    match value.as_i64() {
+        Some(n) => fmt_number(n),
+        None => cell(value, "—"),
+    }
+}
+
+/// Human-readable aligned table; numeric columns are right-aligned.
+pub struct TextWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> TextWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+}
+
+impl<W: Write> OutputWriter for TextWriter<W> {
+    fn write_table(&mut self, headers: &[&str], rows: &[Vec<String>]) -> Result<()> {
+        let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+        for row in rows {
+            for (i, c) in row.iter().enumerate().take(widths.len()) {
+                widths[i] = widths[i].max(c.chars().count());
+            }
+        }
+        let numeric: Vec<bool> = (0..headers.len())
+            .map(|i| {
+                !rows.is_empty()
+                    && rows.iter().all(|r| {
+                        r.get(i)
+                            .map(|c| c == "—" || c.replace(',', "").parse::<f64>().is_ok())
+                            .unwrap_or(true)
+                    })
+            })
+            .collect();
+
+        let line = |cells: Vec<&str>| -> String {
+            cells
+                .iter()
+                .enumerate()
+                .map(|(i, c)| {
+                    if numeric[i] {
+                        format!("{:>w$}", c, w = widths[i])
+                    } else {
+                        format!("{:<w$}", c, w = widths[i])
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+
+        let upper: Vec<String> = headers.iter().map(|h| h.to_uppercase()).collect();
+        writeln!(self.out, "{}", line(upper.iter().map(|s| s.as_str()).collect()))?;
+        for row in rows {
+            writeln!(self.out, "{}", line(row.iter().map(|s| s.as_str()).collect()))?;
+        }
+        Ok(())
+    }
+
+    fn write_values(&mut self, records: &[Value]) -> Result<()> {
+        let headers = record_headers(records);
+        let rows: Vec<Vec<String>> = records
+            .iter()
+            .map(|r| match r {
+                Value::Object(map) => headers
+                    .iter()
+                    .map(|h| map.get(h).map(text_cell).unwrap_or_else(|| "—".to_string()))
+                    .collect(),
+                other => vec![text_cell(other)],
+            })
+            .collect();
+        let header_refs: Vec<&str> = headers.iter().map(|s| s.as_str()).collect();
+        self.write_table(&header_refs, &rows)
+    }
+
+    fn write_value(&mut self, record: &Value) -> Result<()> {
+        let Value::Object(map) = record else {
+            writeln!(self.out, "{}", text_cell(record))?;
+            return Ok(());
+        };
+        let width = map.keys().map(|k| k.chars().count()).max().unwrap_or(0);
+        for (k, v) in map {
+            writeln!(self.out, "{:<w$} : {}", k, text_cell(v), w = width)?;
+        }
+        Ok(())
+    }
+}
+
+// ── JSON ──────────────────────────────────────────────────────────────────────
+
+/// Pretty-printed JSON: lists as arrays, single records as objects.
+pub struct JsonWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> JsonWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+}
+
+impl<W: Write> OutputWriter for JsonWriter<W> {
+    fn write_table(&mut self, headers: &[&str], rows: &[Vec<String>]) -> Result<()> {
+        let records: Vec<Value> = rows
+            .iter()
+            .map(|row| {
+                Value::Object(
+                    headers
+                        .iter()
+                        .zip(row)
+                        .map(|(h, c)| (h.to_string(), Value::String(c.clone())))
+                        .collect(),
+                )
+            })
+            .collect();
+        self.write_values(&records)
+    }
+
+    fn write_values(&mut self, records: &[Value]) -> Result<()> {
+        serde_json::to_writer_pretty(&mut self.out, records)?;
+        writeln!(self.out)?;
+        Ok(())
+    }
+
+    fn write_value(&mut self, record: &Value) -> Result<()> {
+        serde_json::to_writer_pretty(&mut self.out, record)?;
+        writeln!(self.out)?;
+        Ok(())
+    }
+}
+
+// ── CSV ───────────────────────────────────────────────────────────────────────
+
+/// RFC 4180 CSV with a header row; nulls become empty cells.
+pub struct CsvWriter<W: Write> {
+    out: csv::Writer<W>,
+}
+
+impl<W: Write> CsvWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out: csv::Writer::from_writer(out),
+        }
+    }
+}
+
+impl<W: Write> OutputWriter for CsvWriter<W> {
+    fn write_table(&mut self, headers: &[&str], rows: &[Vec<String>]) -> Result<()> {
+        self.out.write_record(headers)?;
+        for row in rows {
+            self.out.write_record(row)?;
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+
+    fn write_values(&mut self, records: &[Value]) -> Result<()> {
+        let headers = record_headers(records);
+        self.out.write_record(&headers)?;
+        for record in records {
+            self.out.write_record(record_cells(record, &headers, ""))?;
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+
+    fn write_value(&mut self, record: &Value) -> Result<()> {
+        self.write_values(std::slice::from_ref(record))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CoverageRow;
+    use chrono::NaiveDate;
+    use serde_json::json;
+
+    #[test]
+    fn test_text_writer_aligns_columns() {
+        let mut buf = Vec::new();
+        TextWriter::new(&mut buf)
+            .write_values(&[
+                json!({"symbol": "DANGCEM", "bars": 1200}),
+                json!({"symbol": "GTCO", "bars": 35}),
+            ])
+            .unwrap();
+        let out = String::from_utf8(buf).unwrap();
+        assert_eq!(out, "SYMBOL    BARS\nDANGCEM  1,200\nGTCO        35\n");
+    }
+
+    #[test]
+    fn test_csv_writer_keeps_field_order_and_blanks_nulls() {
+        let mut buf = Vec::new();
+        CsvWriter::new(&mut buf)
+            .write_values(&[json!({"symbol": "GTCO", "first_date": null, "bars": 0})])
+            .unwrap();
+        let out = String::from_utf8(buf).unwrap();
+        assert_eq!(out, "symbol,first_date,bars\nGTCO,,0\n");
+    }
+
+    #[test]
+    fn test_json_writer_emits_coverage_rows_in_order() {
+        let rows = [
+            CoverageRow {
+                symbol: "GTCO".to_string(),
+                bars: 2,
+                first_date: NaiveDate::from_ymd_opt(2024, 1, 2),
+                last_date: NaiveDate::from_ymd_opt(2024, 1, 3),
+            },
+            CoverageRow {
+                symbol: "ZENITHBANK".to_string(),
+                bars: 0,
+                first_date: None,
+                last_date: None,
+            },
+        ];
+        let mut buf = Vec::new();
+        let mut writer: Box<dyn OutputWriter> = Box::new(JsonWriter::new(&mut buf));
+        writer.write_records(&rows).unwrap();
+        drop(writer);
+
+        let out: Value = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(
+            out,
+            json!([
+                {"symbol": "GTCO", "bars": 2, "first_date": "2024-01-02",
+                 "last_date": "2024-01-03"},
+                {"symbol": "ZENITHBANK", "bars": 0, "first_date": null, "last_date": null},
+            ])
+        );
+    }
+}
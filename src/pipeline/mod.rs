@@ -1,33 +1,633 @@
-//! Pipeline orchestrator - currently minimal (CSV-only mode)
+//! Pipeline orchestrator: scrape kwayisi into the repository.
 //!
-//! This module is a placeholder for future scraper-based daily updates.
-//! Right now, all data loading happens via CLI commands (load-tickers, load-equities, load-fx).
+//! `run` is the daily update: (1) crawl the listing pages and upsert tickers,
+//! (2) fetch every listed ticker's page — `concurrency` at a time, each within
+//! `per_symbol_timeout_secs` — and upsert its recent bars and metadata.
+//! `run_backfill` replaces step 2 with a walk back through each ticker's
+//! history pages to the newest stored bar, and `run_full_listing` stops after
+//! step 1 to seed the tickers table without touching any ticker page. Every
+//! run records a `scrape_runs` row. CSV data goes through the `load-*`
+//! commands instead.
 
 use crate::config::AppConfig;
-use anyhow::Result;
+use crate::models::{DailyBar, DateRange, ScrapeRun};
+use crate::scraper::cleaner::normalise_symbol;
+use crate::scraper::{KwayisiScraper, MarketDataSource};
+use crate::storage::{Repository, BULK_INSERT_THRESHOLD};
+use crate::utils::last_trading_day;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{debug, info, warn};
+
+/// History pages walked per symbol before a backfill gives up on reaching
+/// stored data (guards against a site that ignores `?page=`).
+const MAX_HISTORY_PAGES: u32 = 500;
 
 pub struct Pipeline {
-    #[allow(dead_code)]
     config: AppConfig,
+    source: Arc<dyn MarketDataSource>,
 }
 
 impl Pipeline {
-    pub fn new(config: AppConfig) -> Self {
-        Self { config }
+    /// Pipeline over the kwayisi scraper configured by `config.scraper`.
+    pub fn new(config: AppConfig) -> Result<Self> {
+        let source = Arc::new(KwayisiScraper::new(&config.scraper)?);
+        Ok(Self::with_source(config, source))
+    }
+
+    /// Pipeline over any source, e.g. a canned one in tests.
+    pub fn with_source(config: AppConfig, source: Arc<dyn MarketDataSource>) -> Self {
+        Self { config, source }
+    }
+
+    /// Daily update: listing, then each ticker's recent bars. With
+    /// `pipeline.backfill` set this is a [`Pipeline::run_backfill`] instead.
+    pub async fn run(&self, repo: &Repository) -> Result<PipelineStats> {
+        if self.config.pipeline.backfill {
+            return self.run_backfill(repo).await;
+        }
+        record_run(repo, async {
+            let symbols = self.select_symbols(self.refresh_listing(repo).await?);
+            let mut stats = PipelineStats::default();
+            let symbols = if self.config.pipeline.skip_up_to_date {
+                let (stale, fresh) = self.split_up_to_date(repo, symbols)?;
+                if !fresh.is_empty() {
+                    info!("Skipping {} symbols already up to date", fresh.len());
+                    debug!("Up to date: {}", fresh.join(", "));
+                }
+                stats.skipped = fresh.len();
+                stale
+            } else {
+                symbols
+            };
+            let mut fetches = self.spawn_per_symbol(symbols, |source, symbol| async move {
+                source.fetch_recent_bars(&symbol).await
+            });
+            while let Some(joined) = fetches.join_next().await {
+                let (symbol, page) = joined?;
+                match page {
+                    Ok(page) => {
+                        repo.upsert_tickers(std::slice::from_ref(&page.ticker))?;
+                        stats.bars_inserted += repo.upsert_daily_bars(&page.bars)?;
+                        stats.tickers_processed += 1;
+                    }
+                    Err(e) => {
+                        warn!("{}: {:#}", symbol, e);
+                        stats.errors += 1;
+                    }
+                }
+            }
+            Ok(stats)
+        })
+        .await
+    }
+
+    /// Listing, then each ticker's history pages, newest first, until one
+    /// reaches the symbol's latest stored date (or the history runs out).
+    pub async fn run_backfill(&self, repo: &Repository) -> Result<PipelineStats> {
+        record_run(repo, async {
+            let symbols = self.select_symbols(self.refresh_listing(repo).await?);
+            let exchange = Some(self.config.scraper.exchange.as_str());
+            let mut stop_at = std::collections::HashMap::new();
+            for symbol in &symbols {
+                stop_at.insert(symbol.clone(), repo.latest_date_for_symbol(symbol, exchange)?);
+            }
+            let stop_at = Arc::new(stop_at);
+
+            let mut stats = PipelineStats::default();
+            let mut fetches = self.spawn_per_symbol(symbols, move |source, symbol| {
+                let stop_at = stop_at.get(&symbol).copied().flatten();
+                async move { walk_history(source.as_ref(), &symbol, stop_at).await }
+            });
+            while let Some(joined) = fetches.join_next().await {
+                let (symbol, bars) = joined?;
+                match bars {
+                    Ok(bars) => {
+                        debug!("{}: {} bars backfilled", symbol, bars.len());
+                        stats.bars_inserted += if bars.len() > BULK_INSERT_THRESHOLD {
+                            repo.bulk_insert_bars(&bars)?
+                        } else {
+                            repo.upsert_daily_bars(&bars)?
+                        };
+                        stats.tickers_processed += 1;
+                    }
+                    Err(e) => {
+                        warn!("{}: {:#}", symbol, e);
+                        stats.errors += 1;
+                    }
+                }
+            }
+            Ok(stats)
+        })
+        .await
+    }
+
+    /// Step 1 only: crawl the listing and upsert tickers, no bars fetched.
+    pub async fn run_full_listing(&self, repo: &Repository) -> Result<PipelineStats> {
+        record_run(repo, async {
+            let symbols = self.refresh_listing(repo).await?;
+            Ok(PipelineStats { tickers_processed: symbols.len(), ..Default::default() })
+        })
+        .await
+    }
+
+    /// Step 1: crawl the listing, upsert its tickers, return their symbols.
+    async fn refresh_listing(&self, repo: &Repository) -> Result<Vec<String>> {
+        let tickers = self.source.fetch_ticker_list().await?;
+        repo.upsert_tickers(&tickers)?;
+        Ok(tickers.into_iter().map(|t| t.symbol).collect())
+    }
+
+    /// Partition into `(to_fetch, up_to_date)`: a symbol is up to date when
+    /// its newest stored bar on `scraper.exchange` is already the last
+    /// trading day.
+    fn split_up_to_date(
+        &self,
+        repo: &Repository,
+        symbols: Vec<String>,
+    ) -> Result<(Vec<String>, Vec<String>)> {
+        let today = Utc::now().date_naive();
+        let calendar = &self.config.calendar;
+        let mut holidays = calendar.holidays_in_year(today.year());
+        holidays.extend(calendar.holidays_in_year(today.year() - 1));
+        let session = last_trading_day(today, &holidays);
+
+        let exchange = Some(self.config.scraper.exchange.as_str());
+        let mut to_fetch = Vec::new();
+        let mut fresh = Vec::new();
+        for symbol in symbols {
+            if repo.latest_date_for_symbol(&symbol, exchange)? == Some(session) {
+                fresh.push(symbol);
+            } else {
+                to_fetch.push(symbol);
+            }
+        }
+        Ok((to_fetch, fresh))
+    }
+
+    /// Apply `pipeline.include_symbols` / `exclude_symbols` to the listing.
+    fn select_symbols(&self, symbols: Vec<String>) -> Vec<String> {
+        let pipeline = &self.config.pipeline;
+        let listed = symbols.len();
+        let selected =
+            filter_symbols(symbols, pipeline.include_symbols.as_deref(), &pipeline.exclude_symbols);
+        if selected.len() < listed {
+            info!(
+                "Fetching {} of {} listed symbols ({} filtered out)",
+                selected.len(),
+                listed,
+                listed - selected.len()
+            );
+        }
+        selected
     }
 
-    /// Placeholder for future scraper-based updates
-    pub async fn run(&self) -> Result<PipelineStats> {
-        anyhow::bail!("Scraper mode not implemented yet. Use CSV loading commands instead:\n  \
-                       cargo run -- load-tickers data/tickers.csv\n  \
-                       cargo run -- load-equities --dir data\n  \
-                       cargo run -- load-fx --dir data")
+    /// Run `fetch` for every symbol, at most `concurrency` at once, each
+    /// abandoned after `per_symbol_timeout_secs`. Results come back in
+    /// completion order, so the caller can write while others still fetch.
+    fn spawn_per_symbol<T, F, Fut>(
+        &self,
+        symbols: Vec<String>,
+        fetch: F,
+    ) -> JoinSet<(String, Result<T>)>
+    where
+        T: Send + 'static,
+        F: Fn(Arc<dyn MarketDataSource>, String) -> Fut,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+    {
+        let permits = Arc::new(Semaphore::new(self.config.pipeline.concurrency.max(1)));
+        let deadline = Duration::from_secs(self.config.pipeline.per_symbol_timeout_secs);
+        let mut set = JoinSet::new();
+        for symbol in symbols {
+            let permits = permits.clone();
+            let work = fetch(self.source.clone(), symbol.clone());
+            set.spawn(async move {
+                let _permit = permits.acquire_owned().await.expect("semaphore never closed");
+                let result = tokio::time::timeout(deadline, work)
+                    .await
+                    .unwrap_or_else(|_| Err(anyhow!("timed out after {:?}", deadline)));
+                (symbol, result)
+            });
+        }
+        set
+    }
+}
+
+/// Keep the symbols in `include` (all, if `None`) minus those in `exclude`,
+/// comparing normalised symbols so config casing doesn't matter.
+fn filter_symbols(
+    symbols: Vec<String>,
+    include: Option<&[String]>,
+    exclude: &[String],
+) -> Vec<String> {
+    let normalised = |list: &[String]| -> HashSet<String> {
+        list.iter().map(|s| normalise_symbol(s)).collect()
+    };
+    let include = include.map(normalised);
+    let exclude = normalised(exclude);
+    symbols
+        .into_iter()
+        .filter(|s| {
+            let s = normalise_symbol(s);
+            include.as_ref().is_none_or(|inc| inc.contains(&s)) && !exclude.contains(&s)
+        })
+        .collect()
+}
+
+/// Walk `symbol`'s history pages until one reaches `stop_at` (keeping only
+/// newer bars), a page comes back empty, or pages stop getting older.
+async fn walk_history(
+    source: &dyn MarketDataSource,
+    symbol: &str,
+    stop_at: Option<NaiveDate>,
+) -> Result<Vec<DailyBar>> {
+    let mut bars = Vec::new();
+    let mut oldest_seen: Option<NaiveDate> = None;
+    for page in 1..=MAX_HISTORY_PAGES {
+        let rows = source.fetch_history_page(symbol, page).await?;
+        let Some(oldest) = rows.iter().map(|b| b.date).min() else {
+            break;
+        };
+        if oldest_seen.is_some_and(|seen| oldest >= seen) {
+            warn!("{}: history page {} is no older than the last, stopping", symbol, page);
+            break;
+        }
+        oldest_seen = Some(oldest);
+        bars.extend(rows.into_iter().filter(|b| stop_at.is_none_or(|d| b.date > d)));
+        if stop_at.is_some_and(|d| oldest <= d) {
+            break;
+        }
     }
+    Ok(bars)
 }
 
-#[derive(Debug)]
+/// Bracket `work` with a `scrape_runs` row: success with its counts, or the
+/// error message if it failed outright.
+async fn record_run(
+    repo: &Repository,
+    work: impl Future<Output = Result<PipelineStats>>,
+) -> Result<PipelineStats> {
+    let run_id = repo.begin_scrape_run()?;
+    let result = work.await;
+    match &result {
+        Ok(stats) => {
+            repo.finish_scrape_run(run_id, stats.tickers_processed, stats.bars_inserted, None)?
+        }
+        Err(e) => repo.finish_scrape_run(run_id, 0, 0, Some(&format!("{:#}", e)))?,
+    }
+    result
+}
+
+#[derive(Debug, Default)]
 pub struct PipelineStats {
     pub tickers_processed: usize,
     pub bars_inserted: usize,
     pub errors: usize,
-}
\ No newline at end of file
+    /// Symbols not fetched because they already had the last session's bar
+    pub skipped: usize,
+}
+
+// ── Scheduling ────────────────────────────────────────────────────────────────
+
+/// Daemon cycles are aligned to 17:00 UTC (18:00 WAT), after the NGX close.
+const SCHEDULE_ANCHOR_UTC_HOUR: u32 = 17;
+
+/// First slot strictly after `now` on the grid `anchor + k * interval_hours`,
+/// where the anchor is today's 17:00 UTC. Intervals that divide 24 land on
+/// the same wall-clock times every day.
+pub fn next_run_after(now: DateTime<Utc>, interval_hours: u64) -> DateTime<Utc> {
+    let interval = chrono::Duration::hours(interval_hours.max(1) as i64);
+    let anchor = now
+        .date_naive()
+        .and_hms_opt(SCHEDULE_ANCHOR_UTC_HOUR, 0, 0)
+        .expect("valid time")
+        .and_utc();
+    let mut next = anchor;
+    while next > now {
+        next -= interval;
+    }
+    while next <= now {
+        next += interval;
+    }
+    next
+}
+
+/// Whether the daemon should backfill rather than run the daily update: no
+/// successful run on record, or the last one finished `after_hours` or more
+/// before `now` (the daily update only sees each ticker's recent bars).
+pub fn needs_catch_up(
+    last_success: Option<&ScrapeRun>,
+    now: NaiveDateTime,
+    after_hours: u64,
+) -> bool {
+    match last_success {
+        Some(run) => {
+            let finished = run.finished_at.unwrap_or(run.started_at);
+            (now - finished).num_hours() >= after_hours as i64
+        }
+        None => true,
+    }
+}
+
+/// Resolves on Ctrl-C, or SIGTERM on unix (e.g. `systemctl stop`).
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Could not listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let term = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut term) => {
+                term.recv().await;
+            }
+            Err(e) => {
+                warn!("Could not listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let term = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => info!("Ctrl-C received"),
+        _ = term => info!("SIGTERM received"),
+    }
+}
+
+// ── Post-ingest hooks ─────────────────────────────────────────────────────────
+
+/// Split candidates closer than this (in %) to an exact ratio are logged by
+/// the `detect_splits` hook; matches the `detect-splits` default.
+const HOOK_SPLIT_TOLERANCE_PCT: f64 = 3.0;
+
+/// Built-in actions run, in `pipeline.hooks` order, after a successful load
+/// or update so derived data stays fresh without a second command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Hook {
+    /// Fill missing `change_pct` from the previous close
+    RecomputeChangePct,
+    /// Recreate the `bars_usd` view (e.g. after its definition changed)
+    RefreshBarsUsdView,
+    /// Log split-like moves for review
+    DetectSplits,
+}
+
+impl Hook {
+    fn run(self, repo: &Repository) -> Result<String> {
+        Ok(match self {
+            Hook::RecomputeChangePct => {
+                format!("{} bars filled", repo.recompute_change_pct()?)
+            }
+            Hook::RefreshBarsUsdView => {
+                repo.refresh_views()?;
+                "views recreated".to_string()
+            }
+            Hook::DetectSplits => {
+                let candidates = repo.detect_possible_splits(
+                    None,
+                    false,
+                    DateRange::default(),
+                    HOOK_SPLIT_TOLERANCE_PCT,
+                )?;
+                for (symbol, date, ratio) in &candidates {
+                    warn!("Possible split: {} on {} (ratio {:.2})", symbol, date, ratio);
+                }
+                format!("{} candidates", candidates.len())
+            }
+        })
+    }
+}
+
+/// Run each hook in order, logging its outcome. A failing hook is logged and
+/// skipped — the load it follows has already committed.
+pub fn run_hooks(repo: &Repository, hooks: &[Hook]) {
+    for hook in hooks {
+        match hook.run(repo) {
+            Ok(summary) => info!("Hook {:?}: {}", hook, summary),
+            Err(e) => warn!("Hook {:?} failed: {:#}", hook, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Ticker;
+    use crate::scraper::SymbolPage;
+    use async_trait::async_trait;
+
+    /// History served as fixed pages of bars, newest page first.
+    struct PagedSource(Vec<Vec<DailyBar>>);
+
+    #[async_trait]
+    impl MarketDataSource for PagedSource {
+        async fn fetch_ticker_list(&self) -> Result<Vec<Ticker>> {
+            Ok(vec![])
+        }
+
+        async fn fetch_recent_bars(&self, _symbol: &str) -> Result<SymbolPage> {
+            anyhow::bail!("PagedSource only serves history pages")
+        }
+
+        async fn fetch_history_page(&self, _symbol: &str, page: u32) -> Result<Vec<DailyBar>> {
+            Ok(self.0.get(page as usize - 1).cloned().unwrap_or_default())
+        }
+    }
+
+    fn bars(dates: &[&str]) -> Vec<DailyBar> {
+        dates
+            .iter()
+            .map(|d| DailyBar {
+                symbol: "GTCO".to_string(),
+                exchange: "NGX".to_string(),
+                date: d.parse().unwrap(),
+                open: None,
+                high: None,
+                low: None,
+                close: 40.0,
+                change_pct: None,
+                volume: None,
+                scraped_at: chrono::Utc::now().naive_utc(),
+            })
+            .collect()
+    }
+
+    /// Canned listing and ticker pages; a symbol without bars fails to fetch
+    /// and one in `hangs` never answers.
+    struct MockSource {
+        tickers: Vec<Ticker>,
+        bars: std::collections::HashMap<String, Vec<DailyBar>>,
+        hangs: Vec<String>,
+    }
+
+    #[async_trait]
+    impl MarketDataSource for MockSource {
+        async fn fetch_ticker_list(&self) -> Result<Vec<Ticker>> {
+            Ok(self.tickers.clone())
+        }
+
+        async fn fetch_recent_bars(&self, symbol: &str) -> Result<SymbolPage> {
+            if self.hangs.iter().any(|s| s == symbol) {
+                std::future::pending::<()>().await;
+            }
+            let bars = self.bars.get(symbol).cloned().ok_or_else(|| anyhow!("page not found"))?;
+            let ticker = self.tickers.iter().find(|t| t.symbol == symbol).unwrap().clone();
+            Ok(SymbolPage { bars, ticker })
+        }
+
+        async fn fetch_history_page(&self, _symbol: &str, _page: u32) -> Result<Vec<DailyBar>> {
+            Ok(vec![])
+        }
+    }
+
+    fn ticker(symbol: &str) -> Ticker {
+        Ticker {
+            symbol: symbol.to_string(),
+            name: Some(format!("{} Plc", symbol)),
+            sector: None,
+            industry: None,
+            exchange: Some("NGX".to_string()),
+            isin: None,
+            board: None,
+            status: None,
+            scraped_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_upserts_mock_source_and_counts() {
+        let source = MockSource {
+            tickers: vec![ticker("GTCO"), ticker("MTNN")],
+            bars: [("GTCO".to_string(), bars(&["2024-01-03", "2024-01-02"]))].into(),
+            hangs: vec![],
+        };
+        let mut config = AppConfig::default();
+        config.pipeline.skip_up_to_date = false;
+        let repo = Repository::open_in_memory().unwrap();
+        repo.run_migrations().unwrap();
+
+        let stats = Pipeline::with_source(config, Arc::new(source)).run(&repo).await.unwrap();
+        assert_eq!(
+            (stats.tickers_processed, stats.bars_inserted, stats.errors, stats.skipped),
+            (1, 2, 1, 0)
+        );
+        assert_eq!(repo.ticker_count().unwrap(), 2);
+        assert_eq!(repo.bar_count().unwrap(), 2);
+        assert_eq!(repo.recent_runs(1).unwrap()[0].bars_inserted, 2);
+    }
+
+    #[tokio::test]
+    async fn test_hanging_symbol_times_out_without_blocking_the_rest() {
+        let source = MockSource {
+            tickers: vec![ticker("GTCO"), ticker("MTNN"), ticker("ZENITHBANK")],
+            bars: [
+                ("GTCO".to_string(), bars(&["2024-01-03"])),
+                ("ZENITHBANK".to_string(), bars(&["2024-01-03"])),
+            ]
+            .into(),
+            hangs: vec!["MTNN".to_string()],
+        };
+        let mut config = AppConfig::default();
+        config.pipeline.skip_up_to_date = false;
+        config.pipeline.concurrency = 1;
+        config.pipeline.per_symbol_timeout_secs = 1;
+        let repo = Repository::open_in_memory().unwrap();
+        repo.run_migrations().unwrap();
+
+        let pipeline = Pipeline::with_source(config, Arc::new(source));
+        let stats = tokio::time::timeout(Duration::from_secs(10), pipeline.run(&repo))
+            .await
+            .expect("a hanging symbol stalled the whole run")
+            .unwrap();
+        assert_eq!((stats.tickers_processed, stats.errors), (2, 1));
+    }
+
+    #[tokio::test]
+    async fn test_walk_history_stops_at_stored_date() {
+        let source = PagedSource(vec![
+            bars(&["2024-01-10", "2024-01-09"]),
+            bars(&["2024-01-08", "2024-01-05"]),
+            bars(&["2024-01-04", "2024-01-03"]),
+        ]);
+        let stop_at = "2024-01-05".parse().ok();
+        let got = walk_history(&source, "GTCO", stop_at).await.unwrap();
+        let dates: Vec<String> = got.iter().map(|b| b.date.to_string()).collect();
+        assert_eq!(dates, ["2024-01-10", "2024-01-09", "2024-01-08"]);
+
+        // Nothing stored: walk until the history runs out
+        assert_eq!(walk_history(&source, "GTCO", None).await.unwrap().len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_walk_history_stops_when_pages_repeat() {
+        let page = bars(&["2024-01-10", "2024-01-09"]);
+        let source = PagedSource(vec![page.clone(), page.clone(), page]);
+        assert_eq!(walk_history(&source, "GTCO", None).await.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_filter_symbols_include_then_exclude() {
+        let listed = || ["DANGCEM", "GTCO", "MTNN", "OANDO"].map(String::from).to_vec();
+        let list = |s: &[&str]| s.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(filter_symbols(listed(), None, &[]), listed());
+        assert_eq!(
+            filter_symbols(listed(), Some(&list(&["gtco", "mtnn "])), &[]),
+            ["GTCO", "MTNN"]
+        );
+        assert_eq!(
+            filter_symbols(listed(), Some(&list(&["GTCO", "MTNN"])), &list(&["mtnn"])),
+            ["GTCO"]
+        );
+        assert_eq!(filter_symbols(listed(), None, &list(&["oando"])).len(), 3);
+    }
+
+    #[test]
+    fn test_next_run_aligns_to_evening_anchor() {
+        let at = |s: &str| {
+            chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap().and_utc()
+        };
+        assert_eq!(next_run_after(at("2024-03-04 09:30"), 24), at("2024-03-04 17:00"));
+        assert_eq!(next_run_after(at("2024-03-04 17:00"), 24), at("2024-03-05 17:00"));
+        assert_eq!(next_run_after(at("2024-03-04 18:10"), 6), at("2024-03-04 23:00"));
+        assert_eq!(next_run_after(at("2024-03-04 02:00"), 6), at("2024-03-04 05:00"));
+    }
+
+    #[test]
+    fn test_needs_catch_up_after_a_long_gap() {
+        let at = |s: &str| chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+        let run = ScrapeRun {
+            id: 1,
+            started_at: at("2024-03-01 17:00"),
+            finished_at: Some(at("2024-03-01 17:20")),
+            duration_secs: Some(1200),
+            status: "success".to_string(),
+            tickers_processed: 150,
+            bars_inserted: 150,
+            error_msg: None,
+        };
+        assert!(!needs_catch_up(Some(&run), at("2024-03-02 17:00"), 72));
+        assert!(needs_catch_up(Some(&run), at("2024-03-04 17:20"), 72));
+        assert!(needs_catch_up(None, at("2024-03-02 17:00"), 72));
+    }
+
+    #[test]
+    fn test_hooks_parse_from_config_names() {
+        let hooks: Vec<Hook> =
+            serde_json::from_str(r#"["recompute_change_pct", "detect_splits"]"#).unwrap();
+        assert_eq!(hooks, vec![Hook::RecomputeChangePct, Hook::DetectSplits]);
+        assert!(serde_json::from_str::<Vec<Hook>>(r#"["vacuum"]"#).is_err());
+    }
+}
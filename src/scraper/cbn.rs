@@ -0,0 +1,146 @@
+//! Central Bank of Nigeria official exchange rates.
+//!
+//! The CBN publishes one row per currency per day with buying, central and
+//! selling rates; the central rate is stored as the close, tagged
+//! `source = "cbn"` so it sits alongside investing.com rows in `fx_rates`.
+
+use crate::config::ScraperConfig;
+use crate::models::{FxRate, RawFxCsvRow};
+use crate::scraper::cleaner::normalise_pair;
+use crate::scraper::http_client::HttpClient;
+use crate::scraper::FxDataSource;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, Utc};
+use scraper::{Html, Selector};
+use tracing::{debug, warn};
+
+const CBN_RATES_URL: &str = "https://www.cbn.gov.ng/rates/ExchRateByCurrency.html";
+
+const CBN_SOURCE: &str = "cbn";
+
+/// How the CBN table names the base currency of an `XXXNGN` pair.
+const CURRENCY_LABELS: &[(&str, &str)] = &[
+    ("USD", "US DOLLAR"),
+    ("GBP", "POUNDS STERLING"),
+    ("EUR", "EURO"),
+    ("CHF", "SWISS FRANC"),
+    ("JPY", "YEN"),
+    ("CNY", "YUAN/RENMINBI"),
+    ("ZAR", "SOUTH AFRICAN RAND"),
+    ("SAR", "RIYAL"),
+    ("DKK", "DANISH KRONA"),
+    ("XOF", "CFA"),
+];
+
+pub struct CbnFxSource {
+    client: HttpClient,
+    url: String,
+}
+
+impl CbnFxSource {
+    pub fn new(config: &ScraperConfig) -> Result<Self> {
+        Ok(Self {
+            client: HttpClient::new(config)?,
+            url: CBN_RATES_URL.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl FxDataSource for CbnFxSource {
+    async fn fetch_fx_rates(&self, pair: &str) -> Result<Vec<FxRate>> {
+        let html = self
+            .client
+            .get_text(&self.url)
+            .await
+            .context("Failed to fetch CBN exchange rates")?;
+        parse_cbn_rates(&html, pair, Utc::now().naive_utc())
+    }
+}
+
+/// Rates for `pair` (e.g. "USDNGN") from the CBN rates table, in page order.
+/// Rows with an unparseable date or central rate are skipped with a warning.
+pub fn parse_cbn_rates(html: &str, pair: &str, now: NaiveDateTime) -> Result<Vec<FxRate>> {
+    let pair = normalise_pair(pair);
+    let label = pair
+        .strip_suffix("NGN")
+        .and_then(|base| CURRENCY_LABELS.iter().find(|(code, _)| *code == base))
+        .map(|(_, label)| *label)
+        .with_context(|| format!("CBN does not publish a rate for {}", pair))?;
+
+    let doc = Html::parse_document(html);
+    let row_sel = Selector::parse("table tr")
+        .map_err(|e| anyhow::anyhow!("row selector: {:?}", e))?;
+    let cell_sel = Selector::parse("th, td")
+        .map_err(|e| anyhow::anyhow!("cell selector: {:?}", e))?;
+
+    let mut columns: Option<(usize, usize, usize)> = None; // date, currency, central
+    let mut rates = Vec::new();
+
+    for tr in doc.select(&row_sel) {
+        let cells: Vec<String> = tr
+            .select(&cell_sel)
+            .map(|c| c.text().collect::<String>().trim().to_string())
+            .collect();
+
+        let Some((date_idx, ccy_idx, rate_idx)) = columns else {
+            let find = |name: &str| cells.iter().position(|c| c.to_lowercase().contains(name));
+            if let (Some(d), Some(c), Some(r)) = (find("date"), find("currency"), find("central")) {
+                columns = Some((d, c, r));
+            }
+            continue;
+        };
+
+        if !cells.get(ccy_idx).is_some_and(|c| c.eq_ignore_ascii_case(label)) {
+            continue;
+        }
+        let row = RawFxCsvRow {
+            date: cells.get(date_idx).cloned(),
+            price: cells.get(rate_idx).cloned(),
+            ..Default::default()
+        };
+        match FxRate::try_from((&row, pair.as_str(), Some(CBN_SOURCE), now)) {
+            Ok(rate) => rates.push(rate),
+            Err(e) => warn!("CBN {}: skipping row {:?}: {}", pair, cells, e),
+        }
+    }
+
+    if columns.is_none() {
+        anyhow::bail!("CBN rates page has no table with date/currency/central rate columns");
+    }
+    debug!("CBN {}: {} rates", pair, rates.len());
+    Ok(rates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn fixture() -> String {
+        std::fs::read_to_string("tests/fixtures/cbn_rates.html").unwrap()
+    }
+
+    #[test]
+    fn test_parse_cbn_rates_picks_central_rate_for_pair() {
+        let now = Utc::now().naive_utc();
+        let rates = parse_cbn_rates(&fixture(), "usd/ngn", now).unwrap();
+        assert_eq!(rates.len(), 2); // the "pending" row is dropped
+        assert_eq!(rates[0].pair, "USDNGN");
+        assert_eq!(rates[0].date, NaiveDate::from_ymd_opt(2024, 10, 15).unwrap());
+        assert_eq!(rates[0].close, 1637.95);
+        assert_eq!(rates[0].source.as_deref(), Some("cbn"));
+
+        let eur = parse_cbn_rates(&fixture(), "EURNGN", now).unwrap();
+        assert_eq!(eur.len(), 1);
+        assert_eq!(eur[0].close, 1767.8455);
+    }
+
+    #[test]
+    fn test_parse_cbn_rates_rejects_unknown_pair_and_layout() {
+        let now = Utc::now().naive_utc();
+        assert!(parse_cbn_rates(&fixture(), "USDGHS", now).is_err());
+        assert!(parse_cbn_rates("<html><p>maintenance</p></html>", "USDNGN", now).is_err());
+    }
+}
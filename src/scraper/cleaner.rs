@@ -1,22 +1,58 @@
 
-use crate::models::{DailyBar, FxRate, RawCsvRow, RawFxCsvRow, RawTickerRow, Ticker};
+use crate::models::{
+    ActionType, CorporateAction, DailyBar, FxRate, RawActionRow, RawCsvRow, RawEquityRow,
+    RawFxCsvRow, RawHistoricalRow, RawTickerRow, Ticker, TickerStatus,
+};
 use chrono::{NaiveDate, NaiveDateTime, Utc};
+use thiserror::Error;
 use tracing::warn;
 
 // ── Parsers ───────────────────────────────────────────────────────────────────
 
-/// Parse price: strip everything except digits, dot, minus.
-/// "NGN 1,234.56" → 1234.56 | "610.00" → 610.0
+/// Currency codes and glyphs seen in price cells (investing.com, CBN exports).
+const CURRENCY_MARKS: &[&str] = &["NGN", "USD", "EUR", "GBP", "₦", "$", "€", "£"];
+
+/// Parse price: drop currency marks, then everything except digits,
+/// separators, minus. Accounting-style parentheses mean negative.
+/// "NGN 1,234.56" → 1234.56 | "₦610.00" → 610.0 | "(1.23)" → -1.23
+/// European-locale exports use a decimal comma: "1.234,56" → 1234.56.
 pub fn parse_price(s: &str) -> Option<f64> {
     let s = s.trim();
     if s.is_empty() || s == "N/A" || s == "-" || s == "—" {
         return None;
     }
-    let cleaned: String = s
+    let (s, negative) = match s.strip_prefix('(').and_then(|s| s.strip_suffix(')')) {
+        Some(inner) => (inner, true),
+        None => (s, false),
+    };
+    let cleaned: String = CURRENCY_MARKS
+        .iter()
+        .fold(s.to_string(), |s, mark| s.replace(mark, ""))
         .chars()
-        .filter(|c| c.is_ascii_digit() || *c == '.' || *c == '-')
+        .filter(|c| c.is_ascii_digit() || matches!(c, '.' | ',' | '-'))
         .collect();
-    cleaned.parse().ok()
+    let value: f64 = normalise_decimal_separator(&cleaned).parse().ok()?;
+    Some(if negative { -value } else { value })
+}
+
+/// Rewrite a number's separators to Rust's format (no grouping, '.' decimal).
+/// With both '.' and ',' present, whichever comes last is the decimal point.
+/// A lone comma style counts as decimal only with exactly two digits after
+/// the last comma ("12,50"); otherwise commas group thousands ("1,234").
+fn normalise_decimal_separator(s: &str) -> String {
+    let decimal_comma = match (s.rfind('.'), s.rfind(',')) {
+        (Some(dot), Some(comma)) => comma > dot,
+        (None, Some(comma)) => {
+            let tail = &s[comma + 1..];
+            tail.len() == 2 && tail.bytes().all(|b| b.is_ascii_digit())
+        }
+        _ => false,
+    };
+    if decimal_comma {
+        s.replace('.', "").replace(',', ".")
+    } else {
+        s.replace(',', "")
+    }
 }
 
 /// Parse volume with K/M/B suffixes.
@@ -36,9 +72,12 @@ pub fn parse_volume_shorthand(s: &str) -> Option<i64> {
     } else if s.ends_with('K') {
         (s.trim_end_matches('K'), 1_000.0)
     } else {
-        // No suffix — just a plain integer
-        let cleaned: String = s.chars().filter(|c| c.is_ascii_digit()).collect();
-        return cleaned.parse().ok();
+        // No suffix — a plain number, possibly with a spurious decimal part
+        // ("1,234.00"). Parse as f64 and truncate so the fraction's digits are
+        // never folded into the integer.
+        let cleaned: String = s.chars().filter(|c| c.is_ascii_digit() || *c == '.').collect();
+        let num: f64 = cleaned.parse().ok()?;
+        return Some(num.trunc() as i64);
     };
 
     // Parse the numeric part (can be decimal like "1.2")
@@ -81,6 +120,51 @@ pub fn parse_date(s: &str) -> Option<NaiveDate> {
     None
 }
 
+/// Field order of slash-separated dates ("03/04/2024"), which a single value
+/// often can't settle but a whole column usually can.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateOrder {
+    DayFirst,
+    MonthFirst,
+}
+
+/// Decide a column's slash-date order: a first field above 12 can only be a
+/// day, a second field above 12 only a day in month-first order. `None` when
+/// no value settles it (or the column contradicts itself).
+pub fn infer_date_order<'a>(dates: impl IntoIterator<Item = &'a str>) -> Option<DateOrder> {
+    let (mut day_first, mut month_first) = (false, false);
+    for date in dates {
+        let mut parts = date.trim().split('/').map(|p| p.trim().parse::<u32>());
+        if let (Some(Ok(a)), Some(Ok(b)), Some(Ok(_)), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        {
+            day_first |= a > 12;
+            month_first |= b > 12;
+        }
+    }
+    match (day_first, month_first) {
+        (true, false) => Some(DateOrder::DayFirst),
+        (false, true) => Some(DateOrder::MonthFirst),
+        _ => None,
+    }
+}
+
+/// `parse_date`, but slash dates are read in `hint` order only; without a
+/// hint this is plain `parse_date`.
+pub fn parse_date_with_hint(s: &str, hint: Option<DateOrder>) -> Option<NaiveDate> {
+    let format = match hint {
+        Some(DateOrder::DayFirst) => "%d/%m/%Y",
+        Some(DateOrder::MonthFirst) => "%m/%d/%Y",
+        None => return parse_date(s),
+    };
+    let s = s.trim();
+    if s.contains('/') {
+        NaiveDate::parse_from_str(s, format).ok()
+    } else {
+        parse_date(s)
+    }
+}
+
 pub fn normalise_symbol(s: &str) -> String {
     s.trim().to_uppercase()
 }
@@ -89,94 +173,363 @@ pub fn normalise_pair(s: &str) -> String {
     s.trim().to_uppercase().replace("/", "").replace(" ", "")
 }
 
+// ── Row conversion errors ─────────────────────────────────────────────────────
+
+/// Why a raw row couldn't become a domain record.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum RowError {
+    #[error("missing symbol")]
+    MissingSymbol,
+    #[error("missing date")]
+    MissingDate,
+    #[error("unparseable date {0:?}")]
+    BadDate(String),
+    #[error("date {0} is in the future (day/month swapped?)")]
+    FutureDate(NaiveDate),
+    #[error("missing close")]
+    MissingClose,
+    #[error("unparseable close {0:?}")]
+    BadClose(String),
+    #[error("non-positive close {0}")]
+    NonPositiveClose(f64),
+    #[error("OHLC inconsistent: high {high} < low {low}")]
+    OhlcViolation { high: f64, low: f64 },
+    #[error("OHLC inconsistent: {field} {value} outside [{low}, {high}]")]
+    OhlcOutOfRange { field: &'static str, value: f64, low: f64, high: f64 },
+    #[error("unknown action type {0:?}")]
+    BadActionType(String),
+    #[error("unparseable ratio or amount {0:?}")]
+    BadAmount(String),
+}
+
+impl RowError {
+    /// True for the OHLC consistency failures, which loaders count separately.
+    pub fn is_ohlc(&self) -> bool {
+        matches!(self, RowError::OhlcViolation { .. } | RowError::OhlcOutOfRange { .. })
+    }
+}
+
+fn required_date(s: Option<&str>) -> Result<NaiveDate, RowError> {
+    let s = s.map(str::trim).filter(|s| !s.is_empty()).ok_or(RowError::MissingDate)?;
+    parse_date(s).ok_or_else(|| RowError::BadDate(s.to_string()))
+}
+
+/// Like `required_date`, but a bar can't be dated after the day it was
+/// scraped; that is nearly always an ambiguous "03/04/2024" read the wrong way.
+fn required_bar_date(s: Option<&str>, now: NaiveDateTime) -> Result<NaiveDate, RowError> {
+    let date = required_date(s)?;
+    if date > now.date() {
+        return Err(RowError::FutureDate(date));
+    }
+    Ok(date)
+}
+
+/// `high >= low` whenever both are known, and with open/high/low all present,
+/// open and close inside `[low, high]`.
+fn check_ohlc(bar: &DailyBar) -> Result<(), RowError> {
+    let (Some(high), Some(low)) = (bar.high, bar.low) else {
+        return Ok(());
+    };
+    if high < low {
+        return Err(RowError::OhlcViolation { high, low });
+    }
+    let Some(open) = bar.open else {
+        return Ok(());
+    };
+    for (field, value) in [("open", open), ("close", bar.close)] {
+        if !(low..=high).contains(&value) {
+            return Err(RowError::OhlcOutOfRange { field, value, low, high });
+        }
+    }
+    Ok(())
+}
+
+/// Whether a bar's prices are mutually consistent (see `check_ohlc`).
+#[allow(dead_code)]
+pub fn validate_ohlc(bar: &DailyBar) -> bool {
+    check_ohlc(bar).is_ok()
+}
+
+fn required_close(s: Option<&str>) -> Result<f64, RowError> {
+    let s = s.map(str::trim).filter(|s| !s.is_empty()).ok_or(RowError::MissingClose)?;
+    let close = parse_price(s).ok_or_else(|| RowError::BadClose(s.to_string()))?;
+    if close <= 0.0 {
+        return Err(RowError::NonPositiveClose(close));
+    }
+    Ok(close)
+}
+
 // ── Equity CSV → DailyBar ─────────────────────────────────────────────────────
 
+/// `(row, symbol, exchange, scraped_at)`
+impl TryFrom<(&RawCsvRow, &str, &str, NaiveDateTime)> for DailyBar {
+    type Error = RowError;
+
+    fn try_from(
+        (row, symbol, exchange, now): (&RawCsvRow, &str, &str, NaiveDateTime),
+    ) -> Result<Self, RowError> {
+        let date = required_bar_date(row.date.as_deref(), now)?;
+        let close = required_close(row.price.as_deref())?;
+        let high = row.high.as_deref().and_then(parse_price);
+        let low = row.low.as_deref().and_then(parse_price);
+
+        let bar = DailyBar {
+            symbol: normalise_symbol(symbol),
+            exchange: normalise_symbol(exchange),
+            date,
+            open: row.open.as_deref().and_then(parse_price),
+            high,
+            low,
+            close,
+            change_pct: row.change_pct.as_deref().and_then(parse_pct),
+            volume: row.volume.as_deref().and_then(parse_volume_shorthand),
+            scraped_at: now,
+        };
+        check_ohlc(&bar)?;
+        Ok(bar)
+    }
+}
+
+/// `Option` form of `DailyBar::try_from`, for callers that don't need the reason.
+#[allow(dead_code)]
 pub fn csv_row_to_bar(
     symbol: &str,
+    exchange: &str,
     row: &RawCsvRow,
     now: NaiveDateTime,
 ) -> Option<DailyBar> {
-    let date_str = row.date.as_deref()?.trim();
-    let date = parse_date(date_str)?;
+    DailyBar::try_from((row, symbol, exchange, now)).ok()
+}
 
-    let close_str = row.price.as_deref()?.trim();
-    let close = parse_price(close_str)?;
+// ── FX CSV → FxRate ───────────────────────────────────────────────────────────
 
-    if close <= 0.0 {
-        warn!("Invalid close {} for {} on {}", close, symbol, date);
-        return None;
-    }
+/// `(row, pair, source, scraped_at)`
+impl TryFrom<(&RawFxCsvRow, &str, Option<&str>, NaiveDateTime)> for FxRate {
+    type Error = RowError;
 
-    Some(DailyBar {
-        symbol: normalise_symbol(symbol),
-        date,
-        open: row.open.as_deref().and_then(parse_price),
-        high: row.high.as_deref().and_then(parse_price),
-        low: row.low.as_deref().and_then(parse_price),
-        close,
-        change_pct: row.change_pct.as_deref().and_then(parse_pct),
-        volume: row.volume.as_deref().and_then(parse_volume_shorthand),
-        scraped_at: now,
-    })
-}
+    fn try_from(
+        (row, pair, source, now): (&RawFxCsvRow, &str, Option<&str>, NaiveDateTime),
+    ) -> Result<Self, RowError> {
+        let date = required_date(row.date.as_deref())?;
+        let close = required_close(row.price.as_deref())?;
 
-// ── FX CSV → FxRate ───────────────────────────────────────────────────────────
+        Ok(FxRate {
+            pair: normalise_pair(pair),
+            date,
+            open: row.open.as_deref().and_then(parse_price),
+            high: row.high.as_deref().and_then(parse_price),
+            low: row.low.as_deref().and_then(parse_price),
+            close,
+            change_pct: row.change_pct.as_deref().and_then(parse_pct),
+            source: source.map(|s| s.to_string()),
+            scraped_at: now,
+        })
+    }
+}
 
+/// `Option` form of `FxRate::try_from`, for callers that don't need the reason.
+#[allow(dead_code)]
 pub fn fx_csv_row_to_rate(
     pair: &str,
     row: &RawFxCsvRow,
     source: Option<&str>,
     now: NaiveDateTime,
 ) -> Option<FxRate> {
-    let date_str = row.date.as_deref()?.trim();
-    let date = parse_date(date_str)?;
+    FxRate::try_from((row, pair, source, now)).ok()
+}
 
-    let close_str = row.price.as_deref()?.trim();
-    let close = parse_price(close_str)?;
+// ── Ticker metadata CSV → Ticker ──────────────────────────────────────────────
 
-    if close <= 0.0 {
-        warn!("Invalid FX rate {} for {} on {}", close, pair, date);
-        return None;
+/// `(row, scraped_at)`
+impl TryFrom<(&RawTickerRow, NaiveDateTime)> for Ticker {
+    type Error = RowError;
+
+    fn try_from((row, now): (&RawTickerRow, NaiveDateTime)) -> Result<Self, RowError> {
+        let symbol = row
+            .symbol
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .ok_or(RowError::MissingSymbol)?;
+
+        Ok(Ticker {
+            symbol: normalise_symbol(symbol),
+            name: row.name.clone().and_then(|s| {
+                let s = s.trim();
+                if s.is_empty() { None } else { Some(s.to_string()) }
+            }),
+            sector: row.sector.clone().and_then(|s| {
+                let s = s.trim();
+                if s.is_empty() { None } else { Some(s.to_string()) }
+            }),
+            industry: row.industry.clone().and_then(|s| {
+                let s = s.trim();
+                if s.is_empty() { None } else { Some(s.to_string()) }
+            }),
+            exchange: row.exchange.clone().and_then(|s| {
+                let s = s.trim();
+                if s.is_empty() { None } else { Some(s.to_string()) }
+            }),
+            isin: row.isin.clone().and_then(|s| {
+                let s = s.trim().to_uppercase();
+                if s.is_empty() { None } else { Some(s) }
+            }),
+            board: row.board.clone().and_then(|s| {
+                let s = s.trim();
+                if s.is_empty() { None } else { Some(s.to_string()) }
+            }),
+            status: row.status.as_deref().and_then(TickerStatus::from_label),
+            scraped_at: now,
+        })
+    }
+}
+
+/// `Option` form of `Ticker::try_from`, for callers that don't need the reason.
+pub fn ticker_row_to_ticker(row: &RawTickerRow, now: NaiveDateTime) -> Option<Ticker> {
+    Ticker::try_from((row, now)).ok()
+}
+
+// ── Corporate actions CSV → CorporateAction ───────────────────────────────────
+
+/// Split ratio or dividend amount: "2:1" → 2.0, "1:10" (reverse split) → 0.1,
+/// otherwise a plain number. Must be positive.
+pub fn parse_ratio(s: &str) -> Option<f64> {
+    let value = match s.split_once(':') {
+        Some((new, old)) => parse_price(new)? / parse_price(old)?,
+        None => parse_price(s)?,
+    };
+    (value.is_finite() && value > 0.0).then_some(value)
+}
+
+impl TryFrom<&RawActionRow> for CorporateAction {
+    type Error = RowError;
+
+    fn try_from(row: &RawActionRow) -> Result<Self, RowError> {
+        let symbol = row
+            .symbol
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .ok_or(RowError::MissingSymbol)?;
+        let ex_date = required_date(row.ex_date.as_deref())?;
+        let label = row.action_type.as_deref().unwrap_or_default();
+        let action_type = ActionType::from_label(label)
+            .ok_or_else(|| RowError::BadActionType(label.to_string()))?;
+        let amount = row.ratio_or_amount.as_deref().unwrap_or_default();
+        let ratio_or_amount =
+            parse_ratio(amount).ok_or_else(|| RowError::BadAmount(amount.to_string()))?;
+
+        Ok(CorporateAction {
+            symbol: normalise_symbol(symbol),
+            ex_date,
+            action_type,
+            ratio_or_amount,
+        })
+    }
+}
+
+// ── Scraped ticker-page history → DailyBar ────────────────────────────────────
+
+/// `(row, symbol, exchange, scraped_at)`. Ticker pages show the day's change
+/// in price units; it becomes `change_pct` against the implied previous close.
+impl TryFrom<(&RawHistoricalRow, &str, &str, NaiveDateTime)> for DailyBar {
+    type Error = RowError;
+
+    fn try_from(
+        (row, symbol, exchange, now): (&RawHistoricalRow, &str, &str, NaiveDateTime),
+    ) -> Result<Self, RowError> {
+        let date = required_bar_date(row.date.as_deref(), now)?;
+        let close = required_close(row.close.as_deref())?;
+        let high = row.high.as_deref().and_then(parse_price);
+        let low = row.low.as_deref().and_then(parse_price);
+        let change_pct = row
+            .change
+            .as_deref()
+            .and_then(parse_price)
+            .map(|change| (close, close - change))
+            .filter(|&(_, prev)| prev > 0.0)
+            .map(|(close, prev)| (close / prev - 1.0) * 100.0);
+
+        let bar = DailyBar {
+            symbol: normalise_symbol(symbol),
+            exchange: normalise_symbol(exchange),
+            date,
+            open: row.open.as_deref().and_then(parse_price),
+            high,
+            low,
+            close,
+            change_pct,
+            volume: row.volume.as_deref().and_then(parse_volume_shorthand),
+            scraped_at: now,
+        };
+        check_ohlc(&bar)?;
+        Ok(bar)
     }
+}
 
-    Some(FxRate {
-        pair: normalise_pair(pair),
-        date,
-        open: row.open.as_deref().and_then(parse_price),
-        high: row.high.as_deref().and_then(parse_price),
-        low: row.low.as_deref().and_then(parse_price),
-        close,
-        change_pct: row.change_pct.as_deref().and_then(parse_pct),
-        source: source.map(|s| s.to_string()),
-        scraped_at: now,
-    })
+/// Convert a ticker page's history rows, logging and dropping bad ones.
+pub fn clean_historical_rows(
+    symbol: &str,
+    exchange: &str,
+    rows: Vec<RawHistoricalRow>,
+    now: NaiveDateTime,
+) -> Vec<DailyBar> {
+    rows.iter()
+        .filter_map(|row| match DailyBar::try_from((row, symbol, exchange, now)) {
+            Ok(bar) => Some(bar),
+            Err(e) => {
+                warn!("{}: history row {:?} dropped: {}", symbol, row.date, e);
+                None
+            }
+        })
+        .collect()
 }
 
-// ── Ticker metadata CSV → Ticker ──────────────────────────────────────────────
+// ── Scraped listing row → Ticker ──────────────────────────────────────────────
 
-pub fn ticker_row_to_ticker(row: &RawTickerRow, now: NaiveDateTime) -> Option<Ticker> {
-    let symbol = row.symbol.as_deref()?.trim();
-    if symbol.is_empty() {
-        return None;
+/// `(row, exchange, scraped_at)`. The listing only names the company; sector,
+/// ISIN etc. come from the ticker page (see `TickerMeta`).
+impl TryFrom<(&RawEquityRow, &str, NaiveDateTime)> for Ticker {
+    type Error = RowError;
+
+    fn try_from(
+        (row, exchange, now): (&RawEquityRow, &str, NaiveDateTime),
+    ) -> Result<Self, RowError> {
+        let symbol = row
+            .symbol
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .ok_or(RowError::MissingSymbol)?;
+
+        Ok(Ticker {
+            symbol: normalise_symbol(symbol),
+            name: row.name.clone().and_then(|s| {
+                let s = s.trim();
+                if s.is_empty() { None } else { Some(s.to_string()) }
+            }),
+            sector: None,
+            industry: None,
+            exchange: Some(normalise_symbol(exchange)),
+            isin: None,
+            board: None,
+            status: None,
+            scraped_at: now,
+        })
     }
+}
 
-    Some(Ticker {
-        symbol: normalise_symbol(symbol),
-        name: row.name.clone().unwrap_or_default().trim().to_string(),
-        sector: row.sector.clone().and_then(|s| {
-            let s = s.trim();
-            if s.is_empty() { None } else { Some(s.to_string()) }
-        }),
-        industry: row.industry.clone().and_then(|s| {
-            let s = s.trim();
-            if s.is_empty() { None } else { Some(s.to_string()) }
-        }),
-        exchange: row.exchange.clone().and_then(|s| {
-            let s = s.trim();
-            if s.is_empty() { None } else { Some(s.to_string()) }
-        }),
-        scraped_at: now,
-    })
+/// Convert listing rows, logging and dropping ones without a symbol.
+pub fn clean_ticker_rows(rows: Vec<RawEquityRow>, exchange: &str, now: NaiveDateTime) -> Vec<Ticker> {
+    rows.iter()
+        .filter_map(|row| match Ticker::try_from((row, exchange, now)) {
+            Ok(t) => Some(t),
+            Err(e) => {
+                warn!("Listing row {:?} dropped: {}", row.name, e);
+                None
+            }
+        })
+        .collect()
 }
 
 // ── Tests ─────────────────────────────────────────────────────────────────────
@@ -185,6 +538,26 @@ pub fn ticker_row_to_ticker(row: &RawTickerRow, now: NaiveDateTime) -> Option<Ti
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_price_decimal_comma() {
+        assert_eq!(parse_price("1.234,56"), Some(1234.56));
+        assert_eq!(parse_price("1,234.56"), Some(1234.56));
+        assert_eq!(parse_price("€ 12,50"), Some(12.5));
+        assert_eq!(parse_price("1,234"), Some(1234.0));
+        assert_eq!(parse_price("1.234.567,8"), Some(1234567.8));
+    }
+
+    #[test]
+    fn test_parse_price_negatives_and_currency() {
+        assert_eq!(parse_price("(0.50)"), Some(-0.5));
+        assert_eq!(parse_price("(1,234.00)"), Some(-1234.0));
+        assert_eq!(parse_price("₦1,234.56"), Some(1234.56));
+        assert_eq!(parse_price("$2.00"), Some(2.0));
+        assert_eq!(parse_price("NGN 610.00"), Some(610.0));
+        assert_eq!(parse_price("-0.25"), Some(-0.25));
+        assert_eq!(parse_price("( )"), None);
+    }
+
     #[test]
     fn test_parse_volume_shorthand() {
         assert_eq!(parse_volume_shorthand("1.2M"), Some(1_200_000));
@@ -193,10 +566,172 @@ mod tests {
         assert_eq!(parse_volume_shorthand("12345"), Some(12345));
     }
 
+    #[test]
+    fn test_parse_volume_with_decimals() {
+        assert_eq!(parse_volume_shorthand("1,234.00"), Some(1234));
+        assert_eq!(parse_volume_shorthand("12,345.678"), Some(12345));
+        assert_eq!(parse_volume_shorthand("1,234,567"), Some(1_234_567));
+    }
+
     #[test]
     fn test_normalise_pair() {
         assert_eq!(normalise_pair("USD/NGN"), "USDNGN");
         assert_eq!(normalise_pair("usd ngn"), "USDNGN");
         assert_eq!(normalise_pair("USDNGN"), "USDNGN");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_ticker_status_from_label() {
+        let row = RawTickerRow {
+            symbol: Some("oando".to_string()),
+            status: Some("Trading Halted".to_string()),
+            ..Default::default()
+        };
+        let t = ticker_row_to_ticker(&row, Utc::now().naive_utc()).unwrap();
+        assert_eq!(t.status, Some(TickerStatus::Suspended));
+        assert_eq!(TickerStatus::from_label("Delisted"), Some(TickerStatus::Delisted));
+        assert_eq!(TickerStatus::from_label("n/a"), None);
+    }
+
+    #[test]
+    fn test_try_from_reports_why_a_row_was_dropped() {
+        let now = Utc::now().naive_utc();
+        let row = |date: &str, price: &str, high: &str, low: &str| RawCsvRow {
+            date: Some(date.to_string()),
+            price: Some(price.to_string()),
+            high: Some(high.to_string()),
+            low: Some(low.to_string()),
+            ..Default::default()
+        };
+
+        let err = |r: RawCsvRow| DailyBar::try_from((&r, "GTCO", "NGX", now)).unwrap_err();
+        assert_eq!(err(row("", "40", "", "")), RowError::MissingDate);
+        assert_eq!(err(row("Highest: 45", "40", "", "")), RowError::BadDate("Highest: 45".into()));
+        assert_eq!(err(row("2024-01-02", "N/A", "", "")), RowError::BadClose("N/A".into()));
+        assert_eq!(err(row("2024-01-02", "0.00", "", "")), RowError::NonPositiveClose(0.0));
+        assert_eq!(
+            err(row("2024-01-02", "40", "39", "41")),
+            RowError::OhlcViolation { high: 39.0, low: 41.0 }
+        );
+
+        let ok = row("2024-01-02", "40.50", "41", "39");
+        assert_eq!(csv_row_to_bar("gtco", "ngx", &ok, now).unwrap().close, 40.5);
+        assert!(csv_row_to_bar("GTCO", "NGX", &row("", "40", "", ""), now).is_none());
+    }
+
+    #[test]
+    fn test_ohlc_out_of_range_is_rejected() {
+        let now = Utc::now().naive_utc();
+        let row = |open: &str, price: &str| RawCsvRow {
+            date: Some("2024-01-02".to_string()),
+            price: Some(price.to_string()),
+            open: Some(open.to_string()),
+            high: Some("41".to_string()),
+            low: Some("39".to_string()),
+            ..Default::default()
+        };
+        let bar = |r: RawCsvRow| DailyBar::try_from((&r, "GTCO", "NGX", now));
+
+        assert!(validate_ohlc(&bar(row("40", "40.5")).unwrap()));
+        let err = bar(row("40", "42")).unwrap_err();
+        assert_eq!(
+            err,
+            RowError::OhlcOutOfRange { field: "close", value: 42.0, low: 39.0, high: 41.0 }
+        );
+        assert!(err.is_ohlc());
+        assert!(bar(row("38", "40")).unwrap_err().is_ohlc());
+        assert!(!RowError::MissingClose.is_ohlc());
+    }
+
+    #[test]
+    fn test_date_order_inferred_from_whole_column() {
+        assert_eq!(infer_date_order(["03/04/2024", "25/04/2024"]), Some(DateOrder::DayFirst));
+        assert_eq!(infer_date_order(["03/04/2024", "04/25/2024"]), Some(DateOrder::MonthFirst));
+        assert_eq!(infer_date_order(["03/04/2024", "2024-04-25"]), None);
+        assert_eq!(infer_date_order(["13/04/2024", "04/25/2024"]), None);
+
+        let d = |y, m, d| NaiveDate::from_ymd_opt(y, m, d);
+        assert_eq!(parse_date_with_hint("03/04/2024", Some(DateOrder::MonthFirst)), d(2024, 3, 4));
+        assert_eq!(parse_date_with_hint("03/04/2024", Some(DateOrder::DayFirst)), d(2024, 4, 3));
+        assert_eq!(parse_date_with_hint("03/04/2024", None), d(2024, 4, 3));
+        let named = parse_date_with_hint("Feb 20, 2024", Some(DateOrder::MonthFirst));
+        assert_eq!(named, d(2024, 2, 20));
+    }
+
+    #[test]
+    fn test_future_dated_bars_are_dropped() {
+        let now = Utc::now().naive_utc();
+        let next_year = (now.date() + chrono::Days::new(365)).format("%Y-%m-%d").to_string();
+
+        let csv = RawCsvRow {
+            date: Some(next_year.clone()),
+            price: Some("40".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            DailyBar::try_from((&csv, "GTCO", "NGX", now)),
+            Err(RowError::FutureDate(_))
+        ));
+
+        let today = RawHistoricalRow {
+            date: Some(now.date().format("%Y-%m-%d").to_string()),
+            close: Some("40".to_string()),
+            ..Default::default()
+        };
+        let ahead = RawHistoricalRow { date: Some(next_year), ..today.clone() };
+        let bars = clean_historical_rows("GTCO", "NGX", vec![ahead, today], now);
+        assert_eq!(bars.len(), 1);
+        assert_eq!(bars[0].date, now.date());
+    }
+
+    #[test]
+    fn test_history_row_change_becomes_pct() {
+        let now = Utc::now().naive_utc();
+        let rows = vec![
+            RawHistoricalRow {
+                date: Some("2024-01-03".to_string()),
+                close: Some("330.00".to_string()),
+                change: Some("+30.00".to_string()),
+                volume: Some("1,234,567".to_string()),
+                ..Default::default()
+            },
+            RawHistoricalRow {
+                date: Some("2024-01-02".to_string()),
+                close: Some("-".to_string()),
+                ..Default::default()
+            },
+        ];
+        let bars = clean_historical_rows("dangcem", "ngx", rows, now);
+        assert_eq!(bars.len(), 1);
+        assert_eq!(bars[0].symbol, "DANGCEM");
+        assert!((bars[0].change_pct.unwrap() - 10.0).abs() < 1e-9);
+        assert_eq!(bars[0].volume, Some(1_234_567));
+    }
+
+    #[test]
+    fn test_action_row_parses_ratio_forms() {
+        let row = |kind: &str, amount: &str| RawActionRow {
+            symbol: Some("mtnn".to_string()),
+            ex_date: Some("2024-03-01".to_string()),
+            action_type: Some(kind.to_string()),
+            ratio_or_amount: Some(amount.to_string()),
+        };
+        let split = CorporateAction::try_from(&row("Stock split", "2:1")).unwrap();
+        assert_eq!(split.symbol, "MTNN");
+        assert_eq!(split.action_type, ActionType::Split);
+        assert_eq!(split.ratio_or_amount, 2.0);
+        assert_eq!(CorporateAction::try_from(&row("bonus", "1:10")).unwrap().ratio_or_amount, 0.1);
+        assert_eq!(
+            CorporateAction::try_from(&row("Final dividend", "NGN 10.50")).unwrap().action_type,
+            ActionType::Dividend
+        );
+        assert_eq!(
+            CorporateAction::try_from(&row("merger", "1")).unwrap_err(),
+            RowError::BadActionType("merger".into())
+        );
+        assert_eq!(
+            CorporateAction::try_from(&row("split", "0:1")).unwrap_err(),
+            RowError::BadAmount("0:1".into())
+        );
+    }
+}
@@ -1,85 +1,399 @@
-// use crate::config::ScraperConfig;
-// use anyhow::{Context, Result};
-// use rand::thread_rng;
-// use rand::Rng;
-// use std::time::Duration;
-// use tokio::time::sleep;
-// use tracing::{debug, warn};
-
-
-// pub struct HttpClient {
-//     inner: reqwest::Client,
-//     config: ScraperConfig,
-// }
-
-// impl HttpClient {
-//     pub fn new(config: &ScraperConfig) -> Result<Self> {
-//         let inner = reqwest::Client::builder()
-//             .user_agent(&config.user_agent)
-//             .timeout(Duration::from_secs(config.timeout_secs))
-//             .gzip(true)
-//             // Accept cookies so session-based pages work
-//             .cookie_store(true)
-//             .build()
-//             .context("Failed to build HTTP client")?;
-
-//         Ok(Self {
-//             inner,
-//             config: config.clone(),
-//         })
-//     }
-
-//     /// Fetch a URL as text with rate-limiting and retry.
-//     pub async fn get_text(&self, url: &str) -> Result<String> {
-//         self.polite_delay().await;
-
-//         let mut last_err = anyhow::anyhow!("No attempts made");
-
-//         for attempt in 1..=(self.config.max_retries + 1) {
-//             debug!("GET {} (attempt {})", url, attempt);
-
-//             match self.inner.get(url).send().await {
-//                 Ok(resp) => {
-//                     let status = resp.status();
-//                     if status.is_success() {
-//                         let text = resp
-//                             .text()
-//                             .await
-//                             .context("Failed to read response body")?;
-//                         return Ok(text);
-//                     } else if status.as_u16() == 429 || status.as_u16() == 503 {
-//                         // Rate limited — back off harder
-//                         let backoff = Duration::from_millis(
-//                             self.config.request_delay_ms * (2u64.pow(attempt)),
-//                         );
-//                         warn!(
-//                             "Rate limited ({}) on attempt {}, sleeping {:?}",
-//                             status, attempt, backoff
-//                         );
-//                         sleep(backoff).await;
-//                         last_err = anyhow::anyhow!("HTTP {}", status);
-//                     } else {
-//                         last_err = anyhow::anyhow!("HTTP error {}", status);
-//                         break; // Don't retry 4xx other than 429
-//                     }
-//                 }
-//                 Err(e) => {
-//                     last_err = anyhow::anyhow!("Request error: {}", e);
-//                     let backoff =
-//                         Duration::from_millis(self.config.request_delay_ms * (attempt as u64));
-//                     warn!("Request failed on attempt {}: {}", attempt, e);
-//                     sleep(backoff).await;
-//                 }
-//             }
-//         }
-
-//         Err(last_err).with_context(|| format!("All retries exhausted for {}", url))
-//     }
-
-//     /// Sleep for the configured delay + random jitter.
-//     async fn polite_delay(&self) {
-//         let jitter = rand::thread_rng().gen_range(0..=self.config.jitter_ms);
-//         let total = Duration::from_millis(self.config.request_delay_ms + jitter);
-//         sleep(total).await;
-//     }
-// }
\ No newline at end of file
+use crate::config::ScraperConfig;
+use anyhow::{Context, Result};
+use rand::rngs::StdRng;
+use rand::{RngExt, SeedableRng};
+use reqwest::header::{HeaderName, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::{sleep, sleep_until, Instant};
+use tracing::{debug, warn};
+
+/// Source of request jitter: thread-local randomness in production, or a
+/// seeded generator (`scraper.seed` / `--seed`) for reproducible timing.
+pub enum JitterRng {
+    Thread,
+    Seeded(Box<Mutex<StdRng>>),
+}
+
+impl JitterRng {
+    pub fn from_seed(seed: Option<u64>) -> Self {
+        match seed {
+            Some(s) => JitterRng::Seeded(Box::new(Mutex::new(StdRng::seed_from_u64(s)))),
+            None => JitterRng::Thread,
+        }
+    }
+
+    /// Uniform jitter in `0..=max_ms`.
+    pub fn jitter_ms(&self, max_ms: u64) -> u64 {
+        match self {
+            JitterRng::Thread => rand::rng().random_range(0..=max_ms),
+            JitterRng::Seeded(rng) => rng.lock().unwrap().random_range(0..=max_ms),
+        }
+    }
+}
+
+// ── Conditional-GET cache ─────────────────────────────────────────────────────
+
+/// Validators and body from the last 200 for a URL.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedPage {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: String,
+}
+
+/// One JSON file per URL under `scraper.cache_dir`. Read/write failures are
+/// logged and treated as misses: the cache only ever saves bandwidth.
+struct PageCache {
+    dir: PathBuf,
+}
+
+impl PageCache {
+    fn path_for(&self, url: &str) -> PathBuf {
+        let mut h = std::hash::DefaultHasher::new();
+        url.hash(&mut h);
+        self.dir.join(format!("{:016x}.json", h.finish()))
+    }
+
+    fn get(&self, url: &str) -> Option<CachedPage> {
+        let raw = std::fs::read(self.path_for(url)).ok()?;
+        // A hash collision would hand back another URL's page; check.
+        serde_json::from_slice::<CachedPage>(&raw).ok().filter(|p| p.url == url)
+    }
+
+    fn put(&self, page: &CachedPage) {
+        let write = |path: &Path| -> Result<()> {
+            std::fs::create_dir_all(&self.dir)?;
+            std::fs::write(path, serde_json::to_vec(page)?)?;
+            Ok(())
+        };
+        let path = self.path_for(&page.url);
+        if let Err(e) = write(&path) {
+            warn!("Could not cache {} at {:?}: {:#}", page.url, path, e);
+        }
+    }
+}
+
+// ── Client ────────────────────────────────────────────────────────────────────
+
+pub struct HttpClient {
+    inner: reqwest::Client,
+    config: ScraperConfig,
+    rng: JitterRng,
+    /// Earliest time the next request to each host may start. Shared by every
+    /// task holding this client, so concurrency never turns into bursts.
+    next_slot: Mutex<HashMap<String, Instant>>,
+    cache: Option<PageCache>,
+}
+
+impl HttpClient {
+    pub fn new(config: &ScraperConfig) -> Result<Self> {
+        let inner = reqwest::Client::builder()
+            .user_agent(&config.user_agent)
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .gzip(true)
+            // Accept cookies so session-based pages work
+            .cookie_store(true)
+            .build()
+            .context("Failed to build HTTP client")?;
+
+        Ok(Self {
+            inner,
+            config: config.clone(),
+            rng: JitterRng::from_seed(config.seed),
+            next_slot: Mutex::new(HashMap::new()),
+            cache: config.cache_dir.clone().map(|dir| PageCache { dir }),
+        })
+    }
+
+    /// Fetch a URL as text with rate-limiting and retry. With a cache dir
+    /// configured, the request is conditional on the last ETag/Last-Modified
+    /// and a 304 returns the cached body.
+    pub async fn get_text(&self, url: &str) -> Result<String> {
+        self.polite_delay(url).await;
+
+        let cached = self.cache.as_ref().and_then(|c| c.get(url));
+        let mut last_err = anyhow::anyhow!("No attempts made");
+
+        for attempt in 1..=(self.config.max_retries + 1) {
+            debug!("GET {} (attempt {})", url, attempt);
+
+            let mut req = self.inner.get(url);
+            if let Some(page) = &cached {
+                if let Some(etag) = &page.etag {
+                    req = req.header(IF_NONE_MATCH, etag);
+                }
+                if let Some(modified) = &page.last_modified {
+                    req = req.header(IF_MODIFIED_SINCE, modified);
+                }
+            }
+
+            match req.send().await {
+                Ok(resp) => {
+                    let status = resp.status();
+                    if status == reqwest::StatusCode::NOT_MODIFIED
+                        && let Some(page) = cached
+                    {
+                        debug!("{} not modified, using cached body", url);
+                        return Ok(page.body);
+                    } else if status.is_success() {
+                        let header = |name: HeaderName| {
+                            resp.headers()
+                                .get(name)
+                                .and_then(|v| v.to_str().ok())
+                                .map(str::to_string)
+                        };
+                        let (etag, last_modified) = (header(ETAG), header(LAST_MODIFIED));
+                        let body = self.read_body_capped(url, resp).await?;
+                        if let Some(cache) = &self.cache
+                            && (etag.is_some() || last_modified.is_some())
+                        {
+                            cache.put(&CachedPage {
+                                url: url.to_string(),
+                                etag,
+                                last_modified,
+                                body: body.clone(),
+                            });
+                        }
+                        return Ok(body);
+                    } else if status.as_u16() == 429 || status.as_u16() == 503 {
+                        // Rate limited — back off harder
+                        let backoff = self.retry_backoff(Duration::from_millis(
+                            self.config.request_delay_ms.saturating_mul(2u64.pow(attempt)),
+                        ));
+                        warn!(
+                            "Rate limited ({}) on attempt {}, sleeping {:?}",
+                            status, attempt, backoff
+                        );
+                        sleep(backoff).await;
+                        last_err = anyhow::anyhow!("HTTP {}", status);
+                    } else if status.is_server_error() {
+                        last_err = anyhow::anyhow!("HTTP error {}", status);
+                        let backoff = self.retry_backoff(Duration::from_millis(
+                            self.config.request_delay_ms * (attempt as u64),
+                        ));
+                        warn!("Server error ({}) on attempt {}", status, attempt);
+                        sleep(backoff).await;
+                    } else {
+                        last_err = anyhow::anyhow!("HTTP error {}", status);
+                        break; // Don't retry 4xx other than 429
+                    }
+                }
+                Err(e) => {
+                    last_err = anyhow::anyhow!("Request error: {}", e);
+                    let backoff = self.retry_backoff(Duration::from_millis(
+                        self.config.request_delay_ms * (attempt as u64),
+                    ));
+                    warn!("Request failed on attempt {}: {}", attempt, e);
+                    sleep(backoff).await;
+                }
+            }
+        }
+
+        Err(last_err).with_context(|| format!("All retries exhausted for {}", url))
+    }
+
+    /// `base` plus up to `jitter_ms` of random jitter, capped at
+    /// `max_backoff_secs`. The jitter keeps clients that were rate-limited
+    /// together from retrying in lockstep.
+    fn retry_backoff(&self, base: Duration) -> Duration {
+        let jitter = Duration::from_millis(self.rng.jitter_ms(self.config.jitter_ms));
+        (base + jitter).min(Duration::from_secs(self.config.max_backoff_secs))
+    }
+
+    /// Read the body chunk by chunk, bailing out as soon as it passes
+    /// `max_response_bytes` instead of buffering whatever the server sends.
+    async fn read_body_capped(&self, url: &str, mut resp: reqwest::Response) -> Result<String> {
+        let limit = self.config.max_response_bytes;
+        if let Some(len) = resp.content_length()
+            && len > limit
+        {
+            anyhow::bail!("{}: response is {} bytes, over the {} byte limit", url, len, limit);
+        }
+        let mut body: Vec<u8> = Vec::new();
+        while let Some(chunk) = resp.chunk().await.context("Failed to read response body")? {
+            if body.len() as u64 + chunk.len() as u64 > limit {
+                anyhow::bail!("{}: response exceeded the {} byte limit", url, limit);
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+
+    /// Wait for this host's next request slot. Slots are handed out under a
+    /// lock, each at least the configured delay (+ jitter) after the previous
+    /// one, so requests from concurrent tasks are spaced out globally rather
+    /// than each task sleeping independently.
+    async fn polite_delay(&self, url: &str) {
+        let host = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(str::to_string))
+            .unwrap_or_default();
+        let gap = Duration::from_millis(
+            self.config.request_delay_ms + self.rng.jitter_ms(self.config.jitter_ms),
+        );
+        let slot = {
+            let mut slots = self.next_slot.lock().unwrap();
+            let now = Instant::now();
+            let slot = slots.get(&host).map_or(now, |&next| next.max(now));
+            slots.insert(host, slot + gap);
+            slot
+        };
+        sleep_until(slot).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn fast_config(max_retries: u32) -> ScraperConfig {
+        ScraperConfig {
+            request_delay_ms: 1,
+            jitter_ms: 0,
+            max_retries,
+            ..AppConfig::default().scraper
+        }
+    }
+
+    #[tokio::test]
+    async fn test_not_modified_returns_cached_body() {
+        use wiremock::matchers::header;
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(header("if-none-match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("ETag", "\"v1\"")
+                    .set_body_string("<html>DANGCEM</html>"),
+            )
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = ScraperConfig { cache_dir: Some(dir.path().to_path_buf()), ..fast_config(0) };
+        let url = format!("{}/ngx/DANGCEM/", server.uri());
+
+        let first = HttpClient::new(&config).unwrap().get_text(&url).await.unwrap();
+        // A fresh client (next run) revalidates and gets the body from disk.
+        let second = HttpClient::new(&config).unwrap().get_text(&url).await.unwrap();
+        assert_eq!(first, "<html>DANGCEM</html>");
+        assert_eq!(second, first);
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].headers.get("if-none-match").unwrap(), "\"v1\"");
+    }
+
+    #[tokio::test]
+    async fn test_get_text_retries_through_503s() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/ngx/"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/ngx/"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<html>ok</html>"))
+            .mount(&server)
+            .await;
+
+        let client = HttpClient::new(&fast_config(2)).unwrap();
+        let body = client.get_text(&format!("{}/ngx/", server.uri())).await.unwrap();
+        assert_eq!(body, "<html>ok</html>");
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests_share_one_rate_limit() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .mount(&server)
+            .await;
+
+        let delay_ms = 30;
+        let client = std::sync::Arc::new(
+            HttpClient::new(&ScraperConfig { request_delay_ms: delay_ms, ..fast_config(0) })
+                .unwrap(),
+        );
+        let started = std::time::Instant::now();
+        let mut tasks = tokio::task::JoinSet::new();
+        for i in 0..10 {
+            let (client, url) = (client.clone(), format!("{}/ngx/{}", server.uri(), i));
+            tasks.spawn(async move { client.get_text(&url).await });
+        }
+        while let Some(res) = tasks.join_next().await {
+            res.unwrap().unwrap();
+        }
+        assert!(started.elapsed() >= Duration::from_millis(9 * delay_ms));
+        assert_eq!(server.received_requests().await.unwrap().len(), 10);
+    }
+
+    #[test]
+    fn test_retry_backoff_adds_bounded_jitter_under_the_cap() {
+        let config = ScraperConfig {
+            jitter_ms: 250,
+            max_backoff_secs: 2,
+            seed: Some(7),
+            ..fast_config(3)
+        };
+        let client = HttpClient::new(&config).unwrap();
+        let (base, jitter, cap) =
+            (Duration::from_millis(1_000), Duration::from_millis(250), Duration::from_secs(2));
+
+        let samples: Vec<Duration> = (0..200).map(|_| client.retry_backoff(base)).collect();
+        assert!(samples.iter().all(|b| *b >= base && *b <= base + jitter));
+        assert!(samples.iter().any(|b| *b != samples[0]), "jitter should vary");
+        for _ in 0..50 {
+            assert_eq!(client.retry_backoff(Duration::from_secs(30)), cap);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_oversized_body_is_rejected() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("x".repeat(4096)))
+            .mount(&server)
+            .await;
+
+        let config = ScraperConfig { max_response_bytes: 1024, ..fast_config(2) };
+        let client = HttpClient::new(&config).unwrap();
+        let err = client.get_text(&format!("{}/ngx/", server.uri())).await.unwrap_err();
+        assert!(format!("{:#}", err).contains("1024 byte limit"), "{:#}", err);
+        // A size violation is not retried
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_text_gives_up_on_404() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let client = HttpClient::new(&fast_config(3)).unwrap();
+        let url = format!("{}/ngx/nope.html", server.uri());
+        let err = client.get_text(&url).await.unwrap_err();
+        assert!(format!("{:#}", err).contains("404"), "{:#}", err);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+}
@@ -1,123 +1,374 @@
+pub mod cbn;
 pub mod cleaner;
-// pub mod http_client;
-// pub mod parsers;
-
-// use crate::config::ScraperConfig;
-// use crate::models::{DailyBar, Ticker};
-// use anyhow::{Context, Result};
-// use async_trait::async_trait;
-// use tracing::{debug, info, warn};
-
-// use self::cleaner::{clean_historical_rows, clean_ticker_rows};
-// use self::http_client::HttpClient;
-// use self::parsers::{parse_listing_page, parse_ticker_meta, parse_ticker_page};
-
-// // ── Source trait ──────────────────────────────────────────────────────────────
-
-// /// Swappable data source abstraction.
-// #[async_trait]
-// pub trait MarketDataSource: Send + Sync {
-//     async fn fetch_ticker_list(&self) -> Result<Vec<Ticker>>;
-//     async fn fetch_recent_bars(&self, symbol: &str) -> Result<Vec<DailyBar>>;
-// }
-
-// // ── kwayisi scraper ───────────────────────────────────────────────────────────
-
-// pub struct KwayisiScraper {
-//     client: HttpClient,
-//     base_url: String,
-// }
-
-// impl KwayisiScraper {
-//     pub fn new(config: &ScraperConfig) -> Result<Self> {
-//         Ok(Self {
-//             client: HttpClient::new(config)?,
-//             base_url: config.base_url.trim_end_matches('/').to_string(),
-//         })
-//     }
-
-//     /// URL for the listing index page (paginated).
-//     fn listing_url(&self, page: u32) -> String {
-//         if page <= 1 {
-//             format!("{}/", self.base_url)
-//         } else {
-//             format!("{}/?page={}", self.base_url, page)
-//         }
-//     }
-
-//     /// URL for a specific ticker's page.  e.g. DANGCEM → /ngx/dangcem.html
-//     fn ticker_url(&self, symbol: &str) -> String {
-//         format!("{}/{}.html", self.base_url, symbol.to_lowercase())
-//     }
-// }
-
-// #[async_trait]
-// impl MarketDataSource for KwayisiScraper {
-//     async fn fetch_ticker_list(&self) -> Result<Vec<Ticker>> {
-//         let mut all_tickers = Vec::new();
-//         let mut page = 1u32;
-
-//         loop {
-//             let url = self.listing_url(page);
-//             info!("Fetching listing page {} ({})", page, url);
-
-//             let html = self.client.get_text(&url).await
-//                 .with_context(|| format!("Failed to fetch listing page {}", page))?;
-
-//             let (raw_rows, _hrefs) = parse_listing_page(&html)?;
-
-//             if raw_rows.is_empty() {
-//                 debug!("Empty page {} — stopping pagination", page);
-//                 break;
-//             }
-
-//             let tickers = clean_ticker_rows(raw_rows);
-//             info!("  Page {}: {} tickers", page, tickers.len());
-//             all_tickers.extend(tickers);
-
-//             if !parsers::has_next_page(&html) {
-//                 break;
-//             }
-
-//             page += 1;
-
-//             if page > 15 {
-//                 warn!("Reached page limit (15), stopping");
-//                 break;
-//             }
-//         }
-
-//         info!("Total tickers discovered: {}", all_tickers.len());
-//         Ok(all_tickers)
-//     }
-
-    
-//     async fn fetch_recent_bars(&self, symbol: &str) -> Result<Vec<DailyBar>> {
-//         let url = self.ticker_url(symbol);
-//         debug!("Fetching ticker page: {}", url);
-
-//         let html = self.client.get_text(&url).await
-//             .with_context(|| format!("Failed to fetch ticker page for {}", symbol))?;
-
-//         let raw_rows = parse_ticker_page(&html, symbol)?;
-
-//         if raw_rows.is_empty() {
-//             warn!("{}: no rows found on ticker page", symbol);
-//         }
-
-//         let bars = clean_historical_rows(symbol, raw_rows);
-
-//         // Also grab metadata for ticker enrichment
-//         let meta = parse_ticker_meta(&html);
-//         debug!("{}: {} bars, sector={:?}", symbol, bars.len(), meta.sector);
-
-//         Ok(bars)
-//     }
-// }
-
-// /// Returns the ticker symbol list extracted from the listing pages.
-// /// Useful for seeding the DB before scraping individual pages.
-// pub async fn discover_all_symbols(scraper: &KwayisiScraper) -> Result<Vec<String>> {
-//     let tickers = scraper.fetch_ticker_list().await?;
-//     Ok(tickers.into_iter().map(|t| t.symbol).collect())
-// }
\ No newline at end of file
+pub mod http_client;
+pub mod parsers;
+
+use crate::config::ScraperConfig;
+use crate::models::{DailyBar, FxRate, Ticker};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, Utc};
+use clap::ValueEnum;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::task::JoinSet;
+use tracing::{debug, info, warn};
+
+use self::cbn::CbnFxSource;
+use self::cleaner::{clean_historical_rows, clean_ticker_rows};
+use self::http_client::HttpClient;
+use self::parsers::{parse_listing_page, parse_ticker_meta, parse_ticker_page};
+
+/// A ticker page at least this large that parses to no bars is assumed to
+/// be a rendering hiccup rather than a symbol without history, and is
+/// refetched (up to `scraper.max_retries` times).
+const SUSPECT_EMPTY_PAGE_BYTES: usize = 4 * 1024;
+
+/// Listing pages requested concurrently. The client's per-host rate limit
+/// still spaces the requests; this only overlaps their waits.
+const LISTING_WINDOW: u32 = 3;
+
+// ── Source trait ──────────────────────────────────────────────────────────────
+
+/// One ticker page: its recent bars plus the metadata shown above the table.
+#[derive(Debug, Clone)]
+pub struct SymbolPage {
+    /// Newest first, at most `scraper.recent_bars`
+    pub bars: Vec<DailyBar>,
+    /// Sector/ISIN/board etc. for upserting into `tickers`
+    pub ticker: Ticker,
+}
+
+/// Swappable data source abstraction.
+#[async_trait]
+pub trait MarketDataSource: Send + Sync {
+    async fn fetch_ticker_list(&self) -> Result<Vec<Ticker>>;
+    async fn fetch_recent_bars(&self, symbol: &str) -> Result<SymbolPage>;
+
+    /// One page of a symbol's price history, newest page first (page 1 is
+    /// the ticker page itself). Empty once the history runs out.
+    async fn fetch_history_page(&self, symbol: &str, page: u32) -> Result<Vec<DailyBar>>;
+}
+
+/// A source of daily FX rates, tagged with its own `source` so several can
+/// accumulate side by side in `fx_rates`.
+#[async_trait]
+pub trait FxDataSource: Send + Sync {
+    async fn fetch_fx_rates(&self, pair: &str) -> Result<Vec<FxRate>>;
+}
+
+/// FX sources selectable from the CLI (`update-fx --source`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FxSource {
+    /// Central Bank of Nigeria official window (central rate)
+    Cbn,
+}
+
+impl FxSource {
+    pub fn build(self, config: &ScraperConfig) -> Result<Box<dyn FxDataSource>> {
+        Ok(match self {
+            FxSource::Cbn => Box::new(CbnFxSource::new(config)?),
+        })
+    }
+}
+
+// ── kwayisi scraper ───────────────────────────────────────────────────────────
+
+pub struct KwayisiScraper {
+    client: Arc<HttpClient>,
+    base_url: String,
+    exchange: String,
+    recent_bars: usize,
+    max_listing_pages: u32,
+    max_retries: u32,
+}
+
+impl KwayisiScraper {
+    pub fn new(config: &ScraperConfig) -> Result<Self> {
+        Ok(Self {
+            client: Arc::new(HttpClient::new(config)?),
+            base_url: config.base_url.trim_end_matches('/').to_string(),
+            exchange: config.exchange.clone(),
+            recent_bars: config.recent_bars,
+            max_listing_pages: config.max_listing_pages,
+            max_retries: config.max_retries,
+        })
+    }
+
+    /// URL for the listing index page (paginated).
+    fn listing_url(&self, page: u32) -> String {
+        if page <= 1 {
+            format!("{}/", self.base_url)
+        } else {
+            format!("{}/?page={}", self.base_url, page)
+        }
+    }
+
+    /// URL for a specific ticker's page.  e.g. DANGCEM → /ngx/dangcem.html
+    fn ticker_url(&self, symbol: &str) -> String {
+        format!("{}/{}.html", self.base_url, symbol.to_lowercase())
+    }
+
+    /// Older pages of a ticker's history, paginated like the listing.
+    fn history_url(&self, symbol: &str, page: u32) -> String {
+        if page <= 1 {
+            self.ticker_url(symbol)
+        } else {
+            format!("{}?page={}", self.ticker_url(symbol), page)
+        }
+    }
+
+    /// Fetch `url` and clean its history table into bars (unsorted, untruncated).
+    async fn fetch_bars(
+        &self,
+        url: &str,
+        symbol: &str,
+        now: NaiveDateTime,
+    ) -> Result<(String, Vec<DailyBar>)> {
+        debug!("Fetching ticker page: {}", url);
+        let html = self.client.get_text(url).await
+            .with_context(|| format!("Failed to fetch ticker page for {}", symbol))?;
+
+        let raw_rows = parse_ticker_page(&html, symbol)?;
+        if raw_rows.is_empty() {
+            warn!("{}: no rows found on {}", symbol, url);
+        }
+
+        let bars = clean_historical_rows(symbol, &self.exchange, raw_rows, now);
+        Ok((html, bars))
+    }
+}
+
+#[async_trait]
+impl MarketDataSource for KwayisiScraper {
+    async fn fetch_ticker_list(&self) -> Result<Vec<Ticker>> {
+        let mut all_tickers = Vec::new();
+        let mut seen = HashSet::new();
+        let mut first = 1u32;
+
+        // Request a window of pages at once and walk them in page order. Pages
+        // past the last one are wasted requests, so the window stays small.
+        // The missing "next" link normally ends the walk; the page cap is only
+        // a safety stop.
+        'pages: loop {
+            if first > self.max_listing_pages {
+                warn!(
+                    "Stopped at scraper.max_listing_pages ({}) with more pages listed; \
+                     raise it to crawl the rest",
+                    self.max_listing_pages
+                );
+                break;
+            }
+            let last = (first + LISTING_WINDOW - 1).min(self.max_listing_pages);
+            let mut fetches = JoinSet::new();
+            for page in first..=last {
+                let (client, url) = (self.client.clone(), self.listing_url(page));
+                info!("Fetching listing page {} ({})", page, url);
+                fetches.spawn(async move { (page, client.get_text(&url).await) });
+            }
+            let mut pages = fetches.join_all().await;
+            pages.sort_by_key(|(page, _)| *page);
+
+            for (page, html) in pages {
+                let html = html.with_context(|| format!("Failed to fetch listing page {}", page))?;
+                let (raw_rows, _hrefs) = parse_listing_page(&html)?;
+
+                if raw_rows.is_empty() {
+                    debug!("Empty page {} — stopping pagination", page);
+                    break 'pages;
+                }
+
+                // A symbol can straddle a page boundary; keep its first row.
+                let tickers = clean_ticker_rows(raw_rows, &self.exchange, Utc::now().naive_utc());
+                let before = all_tickers.len();
+                all_tickers.extend(tickers.into_iter().filter(|t| seen.insert(t.symbol.clone())));
+                info!("  Page {}: {} tickers", page, all_tickers.len() - before);
+
+                if !parsers::has_next_page(&html) {
+                    break 'pages;
+                }
+            }
+            first = last + 1;
+        }
+
+        info!("Total tickers discovered: {}", all_tickers.len());
+        Ok(all_tickers)
+    }
+
+    async fn fetch_recent_bars(&self, symbol: &str) -> Result<SymbolPage> {
+        let now = Utc::now().naive_utc();
+        let url = self.ticker_url(symbol);
+        let mut attempt = 0;
+        let (html, mut bars) = loop {
+            let (html, bars) = self.fetch_bars(&url, symbol, now).await?;
+            if !bars.is_empty()
+                || html.len() < SUSPECT_EMPTY_PAGE_BYTES
+                || attempt >= self.max_retries
+            {
+                break (html, bars);
+            }
+            attempt += 1;
+            warn!(
+                "{}: {}-byte page parsed to no bars, refetching ({}/{})",
+                symbol,
+                html.len(),
+                attempt,
+                self.max_retries
+            );
+        };
+        bars.sort_by_key(|b| std::cmp::Reverse(b.date));
+        bars.truncate(self.recent_bars);
+
+        // The page header carries sector/ISIN/board, which the listing lacks
+        let ticker = parse_ticker_meta(&html).into_ticker(symbol, &self.exchange, now);
+        debug!("{}: {} bars, sector={:?}", symbol, bars.len(), ticker.sector);
+
+        Ok(SymbolPage { bars, ticker })
+    }
+
+    async fn fetch_history_page(&self, symbol: &str, page: u32) -> Result<Vec<DailyBar>> {
+        let now = Utc::now().naive_utc();
+        let (_, bars) = self.fetch_bars(&self.history_url(symbol, page), symbol, now).await?;
+        Ok(bars)
+    }
+}
+
+/// Returns the ticker symbol list extracted from the listing pages.
+/// Useful for seeding the DB before scraping individual pages.
+#[allow(dead_code)]
+pub async fn discover_all_symbols(source: &dyn MarketDataSource) -> Result<Vec<String>> {
+    let tickers = source.fetch_ticker_list().await?;
+    Ok(tickers.into_iter().map(|t| t.symbol).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use wiremock::matchers::{method, query_param, query_param_is_missing};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn listing(symbols: &[&str], next: bool) -> String {
+        let rows: String = symbols
+            .iter()
+            .map(|s| format!("<tr><td><a href=\"/{0}.html\">{0}</a></td><td>{0} Plc</td></tr>", s))
+            .collect();
+        let more = if next { "<a href=\"?page=2\">Next</a>" } else { "" };
+        format!("<html><table><tbody>{}</tbody></table>{}</html>", rows, more)
+    }
+
+    /// Three listing pages (a symbol straddling pages 1–2); later pages are empty.
+    async fn listing_server() -> MockServer {
+        let server = MockServer::start().await;
+        let pages: [(&str, &[&str], bool); 3] = [
+            ("1", &["ACCESSCORP", "DANGCEM"], true),
+            ("2", &["DANGCEM", "GTCO"], true),
+            ("3", &["MTNN"], false),
+        ];
+        for (page, symbols, next) in pages {
+            let mock = Mock::given(method("GET"));
+            let mock = if page == "1" {
+                mock.and(query_param_is_missing("page"))
+            } else {
+                mock.and(query_param("page", page))
+            };
+            mock.respond_with(ResponseTemplate::new(200).set_body_string(listing(symbols, next)))
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(listing(&[], false)))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    async fn listed_symbols(server: &MockServer, max_listing_pages: u32) -> Vec<String> {
+        let config = ScraperConfig {
+            base_url: server.uri(),
+            request_delay_ms: 1,
+            jitter_ms: 0,
+            max_listing_pages,
+            ..AppConfig::default().scraper
+        };
+        let tickers = KwayisiScraper::new(&config).unwrap().fetch_ticker_list().await.unwrap();
+        tickers.into_iter().map(|t| t.symbol).collect()
+    }
+
+    #[tokio::test]
+    async fn test_listing_pages_keep_order_and_dedup() {
+        let server = listing_server().await;
+        let symbols = listed_symbols(&server, 20).await;
+        assert_eq!(symbols, ["ACCESSCORP", "DANGCEM", "GTCO", "MTNN"]);
+    }
+
+    #[tokio::test]
+    async fn test_recent_bars_refetch_a_large_page_with_no_rows() {
+        let server = MockServer::start().await;
+        let blank = format!("<html><body>{}</body></html>", " ".repeat(SUSPECT_EMPTY_PAGE_BYTES));
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(blank))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(include_str!("../../tests/fixtures/kwayisi_ticker.html")),
+            )
+            .mount(&server)
+            .await;
+
+        let config = ScraperConfig {
+            base_url: server.uri(),
+            request_delay_ms: 1,
+            jitter_ms: 0,
+            max_retries: 2,
+            ..AppConfig::default().scraper
+        };
+        let page = KwayisiScraper::new(&config).unwrap().fetch_recent_bars("DANGCEM").await.unwrap();
+        assert!(!page.bars.is_empty());
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_recent_bars_keep_only_the_newest_rows() {
+        // 50 sessions, oldest first, so keeping the newest needs a sort
+        let start = chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let rows: String = (0..50)
+            .map(|i| {
+                let date = start + chrono::Duration::days(i);
+                format!("<tr><td>{}</td><td>{}.00</td><td>1,000</td></tr>", date, 100 + i)
+            })
+            .collect();
+        let html = format!(
+            "<html><table id=\"t\"><thead><tr><th>Date</th><th>Close</th><th>Volume</th>\
+             </tr></thead><tbody>{}</tbody></table></html>",
+            rows
+        );
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(html))
+            .mount(&server)
+            .await;
+
+        let config = ScraperConfig {
+            base_url: server.uri(),
+            request_delay_ms: 1,
+            jitter_ms: 0,
+            recent_bars: 5,
+            ..AppConfig::default().scraper
+        };
+        let page = KwayisiScraper::new(&config).unwrap().fetch_recent_bars("GTCO").await.unwrap();
+        let dates: Vec<String> = page.bars.iter().map(|b| b.date.to_string()).collect();
+        assert_eq!(dates, ["2024-02-19", "2024-02-18", "2024-02-17", "2024-02-16", "2024-02-15"]);
+        assert_eq!(page.bars[0].close, 149.0);
+    }
+
+    #[tokio::test]
+    async fn test_listing_stops_at_max_listing_pages() {
+        let server = listing_server().await;
+        let symbols = listed_symbols(&server, 2).await;
+        assert_eq!(symbols, ["ACCESSCORP", "DANGCEM", "GTCO"]);
+    }
+}
@@ -1,232 +1,392 @@
-// use crate::models::{RawEquityRow, RawHistoricalRow};
-// use anyhow::Result;
-// use scraper::{Html, Selector};
-// use tracing::warn;
-
-// // ── Listing page ──────────────────────────────────────────────────────────────
-
-// pub fn parse_listing_page(html: &str) -> Result<(Vec<RawEquityRow>, Vec<String>)> {
-//     let doc = Html::parse_document(html);
-
-//     let row_sel = Selector::parse("table tbody tr")
-//         .map_err(|e| anyhow::anyhow!("row selector: {:?}", e))?;
-//     let td_sel = Selector::parse("td")
-//         .map_err(|e| anyhow::anyhow!("td selector: {:?}", e))?;
-//     let a_sel = Selector::parse("a")
-//         .map_err(|e| anyhow::anyhow!("a selector: {:?}", e))?;
-
-//     let mut rows = Vec::new();
-//     let mut hrefs = Vec::new();
-
-//     for tr in doc.select(&row_sel) {
-//         let cells: Vec<String> = tr
-//             .select(&td_sel)
-//             .map(|td| td.text().collect::<String>().trim().to_string())
-//             .collect();
-
-//         if cells.len() < 2 {
-//             continue;
-//         }
-
-//         let href = tr
-//             .select(&td_sel)
-//             .next()
-//             .and_then(|td| td.select(&a_sel).next())
-//             .and_then(|a| a.value().attr("href"))
-//             .map(|h| h.to_string());
-
-//         let symbol = cells.first().map(|s| s.trim().to_uppercase());
-
-//         if let Some(href) = href {
-//             hrefs.push(href);
-//         }
-
-//         rows.push(RawEquityRow {
-//             symbol,
-//             name: cells.get(1).cloned(),
-//             price: cells.get(2).cloned(),
-//             change: cells.get(3).cloned(),
-//             change_pct: cells.get(4).cloned(),
-//             volume: cells.get(5).cloned(),
-//             deals: cells.get(6).cloned(),
-//             ..Default::default()
-//         });
-//     }
-
-//     Ok((rows, hrefs))
-// }
-
-// pub fn has_next_page(html: &str) -> bool {
-//     html.contains("?page=") && html.contains("Next")
-//         || html.contains("next")
-//         || html.contains("›")
-// }
-
-// // ── Per-ticker page ───────────────────────────────────────────────────────────
-
-// pub fn parse_ticker_page(html: &str, symbol: &str) -> Result<Vec<RawHistoricalRow>> {
-//     let doc = Html::parse_document(html);
-
-//     // Find the price history table — kwayisi uses id="t" consistently
-//     let row_sel = find_history_rows(&doc);
-
-//     let Some(rows_html) = row_sel else {
-//         warn!("No price history table found for {}", symbol);
-//         return Ok(vec![]);
-//     };
-
-//     Ok(rows_html)
-// }
-
-// /// Find and extract raw history rows from the price table.
-// fn find_history_rows(doc: &Html) -> Option<Vec<RawHistoricalRow>> {
-//     // Try id="t" first (kwayisi convention)
-//     let table_candidates = ["table#t", "table.prices", "table"];
-
-//     for selector_str in &table_candidates {
-//         let Ok(sel) = Selector::parse(selector_str) else { continue };
-//         let Some(table) = doc.select(&sel).next() else { continue };
-
-//         // Check if this table has a date-like header
-//         let Ok(th_sel) = Selector::parse("thead th") else { continue };
-//         let headers: Vec<String> = table
-//             .select(&th_sel)
-//             .map(|th| th.text().collect::<String>().to_lowercase())
-//             .collect();
-
-//         let has_date = headers.iter().any(|h| h.contains("date"));
-//         let has_price = headers.iter().any(|h| {
-//             h.contains("price") || h.contains("close") || h.contains("last")
-//         });
-
-//         if !has_date && !has_price && *selector_str == "table" {
-//             // Generic table without recognisable headers — skip
-//             continue;
-//         }
-
-//         // Determine column positions from headers
-//         let date_idx = headers.iter().position(|h| h.contains("date")).unwrap_or(0);
-//         let close_idx = headers
-//             .iter()
-//             .position(|h| h.contains("close") || h.contains("price") || h.contains("last"))
-//             .unwrap_or(1);
-//         let change_idx = headers.iter().position(|h| h == "change" || h.contains("chg"));
-//         let vol_idx = headers.iter().position(|h| h.contains("volume") || h.contains("vol"));
-//         let deals_idx = headers.iter().position(|h| h.contains("deal"));
-
-//         let Ok(tr_sel) = Selector::parse("tbody tr") else { continue };
-//         let Ok(td_sel) = Selector::parse("td") else { continue };
-
-//         let mut rows = Vec::new();
-//         for tr in table.select(&tr_sel) {
-//             let cells: Vec<String> = tr
-//                 .select(&td_sel)
-//                 .map(|td| td.text().collect::<String>().trim().to_string())
-//                 .collect();
-
-//             if cells.is_empty() || cells.iter().all(|c| c.is_empty()) {
-//                 continue;
-//             }
-
-//             rows.push(RawHistoricalRow {
-//                 date: cells.get(date_idx).cloned(),
-//                 // kwayisi NGX ticker pages have: Date | Close | Change | Change% | Volume | Deals
-//                 // No open/high/low on free pages
-//                 open: None,
-//                 high: None,
-//                 low: None,
-//                 close: cells.get(close_idx).cloned(),
-//                 change: change_idx.and_then(|i| cells.get(i)).cloned(),
-//                 volume: vol_idx.and_then(|i| cells.get(i)).cloned(),
-//             });
-//         }
-
-//         if !rows.is_empty() {
-//             return Some(rows);
-//         }
-//     }
-
-//     // Last resort: if there are no headers, just try columns positionally
-//     // kwayisi fallback layout: Date | Close | Change | Change% | Volume | Deals
-//     let Ok(sel) = Selector::parse("table tbody tr") else { return None };
-//     let Ok(td_sel) = Selector::parse("td") else { return None };
-//     let mut rows = Vec::new();
-
-//     for tr in doc.select(&sel) {
-//         let cells: Vec<String> = tr
-//             .select(&td_sel)
-//             .map(|td| td.text().collect::<String>().trim().to_string())
-//             .collect();
-
-//         if cells.len() < 2 { continue; }
-
-//         // Heuristic: first cell looks like a date if it contains a digit and a separator
-//         let first = cells[0].as_str();
-//         let looks_like_date = first.contains('-') || first.contains('/') || first.len() >= 8;
-//         if !looks_like_date { continue; }
-
-//         rows.push(RawHistoricalRow {
-//             date: cells.first().cloned(),
-//             open: cells.get(1).cloned(), 
-//             high: cells.get(2).cloned(),
-//             low: cells.get(3).cloned(),
-//             close: cells.get(4).cloned(),
-//             change: cells.get(5).cloned(),
-//             volume: cells.get(6).cloned(),
-//             ..Default::default()
-//         });
-//     }
-
-//     if rows.is_empty() { None } else { Some(rows) }
-// }
-
-// // ── Ticker meta (from the detail page header) ─────────────────────────────────
-
-// #[derive(Debug, Default)]
-// pub struct TickerMeta {
-//     pub name: Option<String>,
-//     pub sector: Option<String>,
-//     pub isin: Option<String>,
-//     pub board: Option<String>,
-// }
-
-// pub fn parse_ticker_meta(html: &str) -> TickerMeta {
-//     let doc = Html::parse_document(html);
-//     let mut meta = TickerMeta::default();
-
-//     for sel_str in &["h1", "h2", ".company-name", "title"] {
-//         if let Ok(sel) = Selector::parse(sel_str) {
-//             if let Some(el) = doc.select(&sel).next() {
-//                 let text = el.text().collect::<String>().trim().to_string();
-//                 if !text.is_empty() && !text.to_lowercase().contains("kwayisi") {
-//                     meta.name = Some(text);
-//                     break;
-//                 }
-//             }
-//         }
-//     }
-
-//     let Ok(dt_sel) = Selector::parse("dt") else { return meta };
-//     let Ok(dd_sel) = Selector::parse("dd") else { return meta };
-
-//     let dts: Vec<String> = doc
-//         .select(&dt_sel)
-//         .map(|el| el.text().collect::<String>().to_lowercase())
-//         .collect();
-//     let dds: Vec<String> = doc
-//         .select(&dd_sel)
-//         .map(|el| el.text().collect::<String>().trim().to_string())
-//         .collect();
-
-//     for (dt, dd) in dts.iter().zip(dds.iter()) {
-//         if dt.contains("isin") {
-//             meta.isin = Some(dd.clone());
-//         } else if dt.contains("sector") || dt.contains("industry") {
-//             meta.sector = Some(dd.clone());
-//         } else if dt.contains("board") || dt.contains("segment") {
-//             meta.board = Some(dd.clone());
-//         }
-//     }
-
-//     meta
-// }
\ No newline at end of file
+use crate::models::{RawEquityRow, RawHistoricalRow, Ticker, TickerStatus};
+use crate::scraper::cleaner::normalise_symbol;
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use scraper::{Html, Selector};
+use tracing::warn;
+
+// ── Listing page ──────────────────────────────────────────────────────────────
+
+/// Where each listing field sits in a row; `None` for a column the page lacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ListingColumns {
+    symbol: usize,
+    name: Option<usize>,
+    price: Option<usize>,
+    change: Option<usize>,
+    change_pct: Option<usize>,
+    volume: Option<usize>,
+    deals: Option<usize>,
+}
+
+/// kwayisi's layout: Ticker | Name | Price | Change | Change% | Volume | Deals
+const POSITIONAL_LISTING_COLUMNS: ListingColumns = ListingColumns {
+    symbol: 0,
+    name: Some(1),
+    price: Some(2),
+    change: Some(3),
+    change_pct: Some(4),
+    volume: Some(5),
+    deals: Some(6),
+};
+
+/// "Change %" → "change%": lowercase, keeping only letters, digits and '%'.
+fn label_key(label: &str) -> String {
+    label
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '%')
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Map `thead` labels onto listing fields, so an added rank column or a
+/// reordered table still parses. `None` without a recognisable symbol
+/// column, in which case the caller falls back to positions.
+fn listing_columns(headers: &[String]) -> Option<ListingColumns> {
+    let keys: Vec<String> = headers.iter().map(|h| label_key(h)).collect();
+    let find = |labels: &[&str]| keys.iter().position(|k| labels.contains(&k.as_str()));
+    Some(ListingColumns {
+        symbol: find(&["ticker", "symbol", "code"])?,
+        name: find(&["name", "company"]),
+        price: find(&["price", "close", "last"]),
+        change: find(&["change", "chg"]),
+        change_pct: find(&["change%", "chg%", "%change"]),
+        volume: find(&["volume", "vol"]),
+        deals: find(&["deals", "trades"]),
+    })
+}
+
+pub fn parse_listing_page(html: &str) -> Result<(Vec<RawEquityRow>, Vec<String>)> {
+    let doc = Html::parse_document(html);
+
+    let row_sel = Selector::parse("table tbody tr")
+        .map_err(|e| anyhow::anyhow!("row selector: {:?}", e))?;
+    let th_sel = Selector::parse("table thead th")
+        .map_err(|e| anyhow::anyhow!("th selector: {:?}", e))?;
+    let td_sel = Selector::parse("td")
+        .map_err(|e| anyhow::anyhow!("td selector: {:?}", e))?;
+    let a_sel = Selector::parse("a")
+        .map_err(|e| anyhow::anyhow!("a selector: {:?}", e))?;
+
+    let headers: Vec<String> = doc
+        .select(&th_sel)
+        .map(|th| th.text().collect::<String>().trim().to_string())
+        .collect();
+    let columns = listing_columns(&headers).unwrap_or_else(|| {
+        if !headers.is_empty() {
+            warn!("Listing header {:?} not recognised, reading columns by position", headers);
+        }
+        POSITIONAL_LISTING_COLUMNS
+    });
+
+    let mut rows = Vec::new();
+    let mut hrefs = Vec::new();
+
+    for tr in doc.select(&row_sel) {
+        let tds: Vec<_> = tr.select(&td_sel).collect();
+        let cells: Vec<String> = tds
+            .iter()
+            .map(|td| td.text().collect::<String>().trim().to_string())
+            .collect();
+
+        let Some(symbol) = cells.get(columns.symbol).filter(|s| !s.is_empty()) else {
+            continue;
+        };
+        if cells.len() < 2 {
+            continue;
+        }
+
+        let href = tds[columns.symbol]
+            .select(&a_sel)
+            .next()
+            .and_then(|a| a.value().attr("href"))
+            .map(|h| h.to_string());
+        if let Some(href) = href {
+            hrefs.push(href);
+        }
+
+        let cell = |column: Option<usize>| column.and_then(|i| cells.get(i)).cloned();
+        rows.push(RawEquityRow {
+            symbol: Some(symbol.to_uppercase()),
+            name: cell(columns.name),
+            price: cell(columns.price),
+            change: cell(columns.change),
+            change_pct: cell(columns.change_pct),
+            volume: cell(columns.volume),
+            deals: cell(columns.deals),
+        });
+    }
+
+    Ok((rows, hrefs))
+}
+
+pub fn has_next_page(html: &str) -> bool {
+    (html.contains("?page=") && html.contains("Next"))
+        || html.contains("next")
+        || html.contains("›")
+}
+
+// ── Per-ticker page ───────────────────────────────────────────────────────────
+
+pub fn parse_ticker_page(html: &str, symbol: &str) -> Result<Vec<RawHistoricalRow>> {
+    let doc = Html::parse_document(html);
+
+    // Find the price history table — kwayisi uses id="t" consistently
+    let row_sel = find_history_rows(&doc);
+
+    let Some(rows_html) = row_sel else {
+        warn!("No price history table found for {}", symbol);
+        return Ok(vec![]);
+    };
+
+    Ok(rows_html)
+}
+
+/// Find and extract raw history rows from the price table.
+fn find_history_rows(doc: &Html) -> Option<Vec<RawHistoricalRow>> {
+    // Try id="t" first (kwayisi convention)
+    let table_candidates = ["table#t", "table.prices", "table"];
+
+    for selector_str in &table_candidates {
+        let Ok(sel) = Selector::parse(selector_str) else { continue };
+        let Some(table) = doc.select(&sel).next() else { continue };
+
+        // Check if this table has a date-like header
+        let Ok(th_sel) = Selector::parse("thead th") else { continue };
+        let headers: Vec<String> = table
+            .select(&th_sel)
+            .map(|th| th.text().collect::<String>().to_lowercase())
+            .collect();
+
+        let has_date = headers.iter().any(|h| h.contains("date"));
+        let has_price = headers.iter().any(|h| {
+            h.contains("price") || h.contains("close") || h.contains("last")
+        });
+
+        if !has_date && !has_price && *selector_str == "table" {
+            // Generic table without recognisable headers — skip
+            continue;
+        }
+
+        // Determine column positions from headers
+        let date_idx = headers.iter().position(|h| h.contains("date")).unwrap_or(0);
+        let close_idx = headers
+            .iter()
+            .position(|h| h.contains("close") || h.contains("price") || h.contains("last"))
+            .unwrap_or(1);
+        let change_idx = headers.iter().position(|h| h == "change" || h.contains("chg"));
+        let vol_idx = headers.iter().position(|h| h.contains("volume") || h.contains("vol"));
+
+        let Ok(tr_sel) = Selector::parse("tbody tr") else { continue };
+        let Ok(td_sel) = Selector::parse("td") else { continue };
+
+        let mut rows = Vec::new();
+        for tr in table.select(&tr_sel) {
+            let cells: Vec<String> = tr
+                .select(&td_sel)
+                .map(|td| td.text().collect::<String>().trim().to_string())
+                .collect();
+
+            if cells.is_empty() || cells.iter().all(|c| c.is_empty()) {
+                continue;
+            }
+
+            rows.push(RawHistoricalRow {
+                date: cells.get(date_idx).cloned(),
+                // kwayisi NGX ticker pages have: Date | Close | Change | Change% | Volume | Deals
+                // No open/high/low on free pages
+                open: None,
+                high: None,
+                low: None,
+                close: cells.get(close_idx).cloned(),
+                change: change_idx.and_then(|i| cells.get(i)).cloned(),
+                volume: vol_idx.and_then(|i| cells.get(i)).cloned(),
+            });
+        }
+
+        if !rows.is_empty() {
+            return Some(rows);
+        }
+    }
+
+    // Last resort: if there are no headers, just try columns positionally
+    // kwayisi fallback layout: Date | Close | Change | Change% | Volume | Deals
+    let Ok(sel) = Selector::parse("table tbody tr") else { return None };
+    let Ok(td_sel) = Selector::parse("td") else { return None };
+    let mut rows = Vec::new();
+
+    for tr in doc.select(&sel) {
+        let cells: Vec<String> = tr
+            .select(&td_sel)
+            .map(|td| td.text().collect::<String>().trim().to_string())
+            .collect();
+
+        if cells.len() < 2 {
+            continue;
+        }
+
+        // Heuristic: first cell looks like a date if it contains a digit and a separator
+        let first = cells[0].as_str();
+        let looks_like_date = first.contains('-') || first.contains('/') || first.len() >= 8;
+        if !looks_like_date { continue; }
+
+        rows.push(RawHistoricalRow {
+            date: cells.first().cloned(),
+            open: cells.get(1).cloned(),
+            high: cells.get(2).cloned(),
+            low: cells.get(3).cloned(),
+            close: cells.get(4).cloned(),
+            change: cells.get(5).cloned(),
+            volume: cells.get(6).cloned(),
+        });
+    }
+
+    if rows.is_empty() { None } else { Some(rows) }
+}
+
+// ── Ticker meta (from the detail page header) ─────────────────────────────────
+
+#[derive(Debug, Default)]
+pub struct TickerMeta {
+    pub name: Option<String>,
+    pub sector: Option<String>,
+    pub industry: Option<String>,
+    pub isin: Option<String>,
+    pub board: Option<String>,
+    pub status: Option<String>,    // "Suspended", "Delisted", … when shown
+}
+
+impl TickerMeta {
+    /// The page's metadata as a `Ticker` for `symbol` on `exchange`, ready to
+    /// upsert: fields the page didn't show stay `None`, so the upsert keeps
+    /// whatever the listing or a CSV load already stored.
+    pub fn into_ticker(self, symbol: &str, exchange: &str, now: NaiveDateTime) -> Ticker {
+        Ticker {
+            symbol: normalise_symbol(symbol),
+            name: self.name,
+            sector: self.sector,
+            industry: self.industry,
+            exchange: Some(normalise_symbol(exchange)),
+            isin: self.isin.map(|s| s.to_uppercase()),
+            board: self.board,
+            status: self.status.as_deref().and_then(TickerStatus::from_label),
+            scraped_at: now,
+        }
+    }
+}
+
+pub fn parse_ticker_meta(html: &str) -> TickerMeta {
+    let doc = Html::parse_document(html);
+    let mut meta = TickerMeta::default();
+
+    for sel_str in &["h1", "h2", ".company-name", "title"] {
+        if let Ok(sel) = Selector::parse(sel_str)
+            && let Some(el) = doc.select(&sel).next()
+        {
+            let text = el.text().collect::<String>().trim().to_string();
+            if !text.is_empty() && !text.to_lowercase().contains("kwayisi") {
+                meta.name = Some(text);
+                break;
+            }
+        }
+    }
+
+    let Ok(dt_sel) = Selector::parse("dt") else { return meta };
+    let Ok(dd_sel) = Selector::parse("dd") else { return meta };
+
+    let dts: Vec<String> = doc
+        .select(&dt_sel)
+        .map(|el| el.text().collect::<String>().to_lowercase())
+        .collect();
+    let dds: Vec<String> = doc
+        .select(&dd_sel)
+        .map(|el| el.text().collect::<String>().trim().to_string())
+        .collect();
+
+    for (dt, dd) in dts.iter().zip(dds.iter()) {
+        if dt.contains("isin") {
+            meta.isin = Some(dd.clone());
+        } else if dt.contains("industry") {
+            meta.industry = Some(dd.clone());
+        } else if dt.contains("sector") {
+            meta.sector = Some(dd.clone());
+        } else if dt.contains("board") || dt.contains("segment") {
+            meta.board = Some(dd.clone());
+        } else if dt.contains("status") {
+            meta.status = Some(dd.clone());
+        }
+    }
+
+    meta
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    const LISTING: &str = include_str!("../../tests/fixtures/kwayisi_listing.html");
+    const LISTING_RANKED: &str = include_str!("../../tests/fixtures/kwayisi_listing_ranked.html");
+    const LISTING_NO_HEADER: &str =
+        include_str!("../../tests/fixtures/kwayisi_listing_no_header.html");
+    const TICKER: &str = include_str!("../../tests/fixtures/kwayisi_ticker.html");
+
+    #[test]
+    fn test_listing_page_rows_and_links() {
+        let (rows, hrefs) = parse_listing_page(LISTING).unwrap();
+        assert_eq!(rows.len(), 5);
+        assert_eq!(hrefs.len(), 5);
+        assert_eq!(rows[1].symbol.as_deref(), Some("DANGCEM"));
+        assert_eq!(rows[1].price.as_deref(), Some("610.00"));
+        assert_eq!(hrefs[1], "/ngx/dangcem.html");
+        assert!(has_next_page(LISTING));
+    }
+
+    #[test]
+    fn test_listing_columns_follow_the_header() {
+        // Leading rank column and Name/Price swapped relative to kwayisi's layout
+        let (rows, hrefs) = parse_listing_page(LISTING_RANKED).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(hrefs, ["/ngx/accesscorp.html", "/ngx/dangcem.html", "/ngx/gtco.html"]);
+        assert_eq!(rows[1].symbol.as_deref(), Some("DANGCEM"));
+        assert_eq!(rows[1].name.as_deref(), Some("Dangote Cement Plc"));
+        assert_eq!(rows[1].price.as_deref(), Some("610.00"));
+        assert_eq!(rows[1].change_pct.as_deref(), Some("0.00%"));
+        assert_eq!(rows[1].volume.as_deref(), Some("402,331"));
+        assert_eq!(rows[1].deals, None);
+    }
+
+    #[test]
+    fn test_listing_without_header_reads_by_position() {
+        let (rows, hrefs) = parse_listing_page(LISTING_NO_HEADER).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(hrefs, ["/ngx/gtco.html", "/ngx/mtnn.html"]);
+        assert_eq!(rows[0].symbol.as_deref(), Some("GTCO"));
+        assert_eq!(rows[0].name.as_deref(), Some("Guaranty Trust Holding Company Plc"));
+        assert_eq!(rows[1].price.as_deref(), Some("230.00"));
+        assert_eq!(rows[1].deals.as_deref(), Some("143"));
+    }
+
+    #[test]
+    fn test_ticker_page_extracts_date_and_close() {
+        let rows = parse_ticker_page(TICKER, "DANGCEM").unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].date.as_deref(), Some("2024-01-05"));
+        assert_eq!(rows[2].close.as_deref(), Some("600.00"));
+        assert_eq!(rows[1].change.as_deref(), Some("+10.00"));
+        assert_eq!(rows[1].volume.as_deref(), Some("1,118,020"));
+    }
+
+    #[test]
+    fn test_ticker_meta_fills_isin_and_board() {
+        let now = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap().and_hms_opt(18, 0, 0).unwrap();
+        let ticker = parse_ticker_meta(TICKER).into_ticker("dangcem", "ngx", now);
+        assert_eq!(ticker.symbol, "DANGCEM");
+        assert_eq!(ticker.name.as_deref(), Some("Dangote Cement Plc"));
+        assert_eq!(ticker.exchange.as_deref(), Some("NGX"));
+        assert_eq!(ticker.isin.as_deref(), Some("NG0000DANGCEM7"));
+        assert_eq!(ticker.board.as_deref(), Some("Premium"));
+        assert_eq!(ticker.sector.as_deref(), Some("Industrial Goods"));
+        assert_eq!(ticker.status, Some(TickerStatus::Active));
+    }
+}
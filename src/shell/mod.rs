@@ -0,0 +1,97 @@
+//! Interactive exploration prompt over one open repository.
+//!
+//! ```text
+//! ngx> DANGCEM                        last stored bar for a symbol
+//! ngx> sql SELECT COUNT(*) FROM fx_rates
+//! ngx> help
+//! ```
+
+use crate::output::OutputFormat;
+use crate::scraper::cleaner::normalise_symbol;
+use crate::storage::Repository;
+use anyhow::Result;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::path::PathBuf;
+
+const HELP: &str = "\
+Commands:
+  <SYMBOL>        show the latest stored bar for SYMBOL
+  sql <query>     run a read-only SQL query (SELECT/WITH)
+  help            show this help
+  exit | quit     leave the shell (Ctrl-D also works)";
+
+/// `~/.ngx_history`, when a home directory is known.
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".ngx_history"))
+}
+
+pub fn run(repo: &Repository, format: OutputFormat) -> Result<()> {
+    let mut rl = DefaultEditor::new()?;
+    let history = history_path();
+    if let Some(path) = &history {
+        // Missing on first run — that's fine
+        let _ = rl.load_history(path);
+    }
+
+    println!("NGX shell — type `help` for commands, Ctrl-D to exit.");
+
+    loop {
+        let line = match rl.readline("ngx> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        rl.add_history_entry(line)?;
+
+        let (cmd, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let result = match cmd.to_lowercase().as_str() {
+            "exit" | "quit" => break,
+            "help" | "?" => {
+                println!("{}", HELP);
+                Ok(())
+            }
+            "sql" => run_sql(repo, rest.trim(), format),
+            _ if rest.is_empty() => show_last_bar(repo, cmd, format),
+            _ => {
+                println!("Unknown command {:?} — type `help`.", cmd);
+                Ok(())
+            }
+        };
+        // Errors are per-line; keep the session alive
+        if let Err(e) = result {
+            println!("error: {:#}", e);
+        }
+    }
+
+    if let Some(path) = &history {
+        rl.save_history(path)?;
+    }
+    Ok(())
+}
+
+fn run_sql(repo: &Repository, sql: &str, format: OutputFormat) -> Result<()> {
+    if sql.is_empty() {
+        println!("usage: sql <query>");
+        return Ok(());
+    }
+    let (headers, rows) = repo.query_sql(sql)?;
+    let headers: Vec<&str> = headers.iter().map(|s| s.as_str()).collect();
+    format.writer().write_table(&headers, &rows)
+}
+
+fn show_last_bar(repo: &Repository, symbol: &str, format: OutputFormat) -> Result<()> {
+    let symbol = normalise_symbol(symbol);
+    match repo.last_bar(&symbol)? {
+        Some(bar) => format.writer().write_record(&bar),
+        None => {
+            format.notice(format!("No bars for {}.", symbol));
+            Ok(())
+        }
+    }
+}
@@ -1,14 +1,23 @@
-use crate::models::{DailyBar, FxRate, Ticker};
+use crate::analytics::{self, AlignedSeries, PriceSeries};
+use crate::config::LoaderConfig;
+use crate::models::{
+    AuditEntry, Breadth, CorporateAction, CoverageRow, DailyBar, DateRange, FxRate, HealthSnapshot,
+    RealReturn, RenameReport, Resample, ScrapeRun, Ticker, TickerStatus, VerifyIssue,
+};
+use crate::scraper::cleaner::{normalise_symbol, DateOrder};
 use anyhow::{Context, Result};
-use chrono::Utc;
-use duckdb::{params, Connection};
+use chrono::{NaiveDateTime, Utc};
+use duckdb::{params, AccessMode, Config, Connection, Row};
 use std::path::Path;
 use std::sync::Mutex;
-use tracing::info;
+use std::time::Duration;
+use tracing::{debug, info, warn};
 
 // ── Schema ────────────────────────────────────────────────────────────────────
 
-const DDL: &str = r#"
+/// The schema as first shipped (migration 1). Frozen: every later change is
+/// its own migration below, so old and new databases converge on one schema.
+const BASELINE_DDL: &str = r#"
 CREATE TABLE IF NOT EXISTS tickers (
     symbol      VARCHAR PRIMARY KEY,
     name        VARCHAR NOT NULL DEFAULT '',
@@ -53,95 +62,927 @@ CREATE TABLE IF NOT EXISTS scrape_runs (
     bars_inserted       INTEGER DEFAULT 0,
     error_msg           VARCHAR
 );
+"#;
 
+const SCHEMA_VERSION_TABLE: &str = r#"
 CREATE TABLE IF NOT EXISTS schema_version (
     version     INTEGER PRIMARY KEY,
     applied_at  TIMESTAMP NOT NULL
 );
 "#;
 
+/// Columns added after a table's first release; `CREATE TABLE IF NOT EXISTS`
+/// won't add them to databases created earlier.
+const ADD_COLUMNS: &str = r#"
+ALTER TABLE daily_bars ADD COLUMN IF NOT EXISTS exchange VARCHAR DEFAULT 'NGX';
+ALTER TABLE tickers ADD COLUMN IF NOT EXISTS isin VARCHAR;
+ALTER TABLE tickers ADD COLUMN IF NOT EXISTS board VARCHAR;
+ALTER TABLE tickers ADD COLUMN IF NOT EXISTS status VARCHAR;
+"#;
+
+const AUDIT_LOG: &str = r#"
+CREATE SEQUENCE IF NOT EXISTS audit_log_id;
+
+CREATE TABLE IF NOT EXISTS audit_log (
+    id          BIGINT PRIMARY KEY DEFAULT nextval('audit_log_id'),
+    at          TIMESTAMP NOT NULL,
+    operation   VARCHAR NOT NULL,
+    table_name  VARCHAR NOT NULL,
+    row_key     VARCHAR,
+    row_count   BIGINT NOT NULL,
+    run_id      INTEGER
+);
+"#;
+
+const CORPORATE_ACTIONS: &str = r#"
+CREATE TABLE IF NOT EXISTS corporate_actions (
+    symbol           VARCHAR NOT NULL,
+    ex_date          DATE    NOT NULL,
+    action_type      VARCHAR NOT NULL,   -- 'split' | 'dividend'
+    ratio_or_amount  DOUBLE  NOT NULL,   -- new shares per old, or naira per share
+    PRIMARY KEY (symbol, ex_date, action_type)
+);
+"#;
+
+/// Re-key `fx_rates` by (pair, date, source) so several sources can quote the
+/// same day. A primary key can't be altered in place, so the table is rebuilt;
+/// unattributed rates become `'unknown'`. Safe on tables already keyed this way.
+const FX_RATES_SOURCE_KEY: &str = r#"
+CREATE TEMP TABLE fx_rates_old AS SELECT * FROM fx_rates;
+DROP TABLE fx_rates;
+
+CREATE TABLE fx_rates (
+    pair        VARCHAR  NOT NULL,
+    date        DATE     NOT NULL,
+    open        DOUBLE,
+    high        DOUBLE,
+    low         DOUBLE,
+    close       DOUBLE   NOT NULL,
+    change_pct  DOUBLE,
+    source      VARCHAR  NOT NULL DEFAULT 'unknown',
+    scraped_at  TIMESTAMP NOT NULL,
+    PRIMARY KEY (pair, date, source)
+);
+
+INSERT INTO fx_rates (pair, date, open, high, low, close, change_pct, source, scraped_at)
+SELECT pair, date, open, high, low, close, change_pct, COALESCE(source, 'unknown'), scraped_at
+FROM fx_rates_old;
+
+DROP TABLE fx_rates_old;
+"#;
+
+/// Re-key `daily_bars` by (symbol, exchange, date) so one database can hold
+/// the same symbol on several exchanges. Rebuilt like `FX_RATES_SOURCE_KEY`;
+/// bars without an exchange become `'NGX'`.
+const DAILY_BARS_EXCHANGE_KEY: &str = r#"
+CREATE TEMP TABLE daily_bars_old AS SELECT * FROM daily_bars;
+DROP TABLE daily_bars;
+
+CREATE TABLE daily_bars (
+    symbol      VARCHAR  NOT NULL,
+    exchange    VARCHAR  NOT NULL DEFAULT 'NGX',
+    date        DATE     NOT NULL,
+    open        DOUBLE,
+    high        DOUBLE,
+    low         DOUBLE,
+    close       DOUBLE   NOT NULL,
+    change_pct  DOUBLE,
+    volume      BIGINT,
+    scraped_at  TIMESTAMP NOT NULL,
+    PRIMARY KEY (symbol, exchange, date)
+);
+
+INSERT INTO daily_bars
+    (symbol, exchange, date, open, high, low, close, change_pct, volume, scraped_at)
+SELECT symbol, COALESCE(exchange, 'NGX'), date, open, high, low, close, change_pct, volume,
+       scraped_at
+FROM daily_bars_old;
+
+DROP TABLE daily_bars_old;
+"#;
+
+/// Schema changes in order, each recorded in `schema_version` once applied.
+/// Append new entries (never edit shipped ones); every script must also be
+/// idempotent, since databases created before versioning only recorded v1.
+const MIGRATIONS: &[(u32, &str)] = &[
+    (1, BASELINE_DDL),
+    (2, ADD_COLUMNS),
+    (3, AUDIT_LOG),
+    (4, CORPORATE_ACTIONS),
+    (5, FX_RATES_SOURCE_KEY),
+    (6, DAILY_BARS_EXCHANGE_KEY),
+];
+
+/// Re-created after every migration run; list new ones in `DROP_DERIVED` too.
 const INDEXES: &str = r#"
 CREATE INDEX IF NOT EXISTS idx_bars_date   ON daily_bars (date);
 CREATE INDEX IF NOT EXISTS idx_bars_symbol ON daily_bars (symbol);
+CREATE INDEX IF NOT EXISTS idx_bars_exchange ON daily_bars (exchange);
 CREATE INDEX IF NOT EXISTS idx_fx_date     ON fx_rates (date);
 CREATE INDEX IF NOT EXISTS idx_fx_pair     ON fx_rates (pair);
 "#;
 
+/// Everything `INDEXES` and `VIEWS` create, dropped before pending migrations
+/// run: DuckDB refuses to ALTER or DROP a table that an index or view depends
+/// on. Both are re-created once the migrations are done.
+const DROP_DERIVED: &str = r#"
+DROP VIEW IF EXISTS bars_usd;
+DROP INDEX IF EXISTS idx_bars_date;
+DROP INDEX IF EXISTS idx_bars_symbol;
+DROP INDEX IF EXISTS idx_bars_exchange;
+DROP INDEX IF EXISTS idx_fx_date;
+DROP INDEX IF EXISTS idx_fx_pair;
+"#;
+
+/// Equity closes converted at the as-of `USDNGN` rate: each bar takes the
+/// latest rate dated on or before it (sources averaged per date). Depends on
+/// `USDNGN` rows in `fx_rates`; without one, `fx` and `close_usd` are NULL.
+const VIEWS: &str = r#"
+CREATE OR REPLACE VIEW bars_usd AS
+WITH usdngn AS (
+    SELECT date, AVG(close) AS fx
+    FROM fx_rates
+    WHERE pair = 'USDNGN'
+    GROUP BY date
+)
+SELECT b.symbol, b.exchange, b.date,
+       b.close AS close_ngn,
+       u.fx,
+       b.close / u.fx AS close_usd
+FROM daily_bars b
+ASOF LEFT JOIN usdngn u ON b.date >= u.date;
+"#;
+
+/// Stored in `fx_rates.source` when a rate carries no attribution, so the
+/// (pair, date, source) key stays NOT NULL.
+const UNKNOWN_FX_SOURCE: &str = "unknown";
+
+/// Invariant checks run by `Repository::verify`: `(check, table, query)`,
+/// each query returning one `(key, detail)` row per violation.
+const VERIFY_CHECKS: &[(&str, &str, &str)] = &[
+    (
+        "unknown_symbol",
+        "daily_bars",
+        r#"SELECT symbol, concat(COUNT(*), ' bars, no tickers row') FROM daily_bars
+           WHERE symbol NOT IN (SELECT symbol FROM tickers)
+           GROUP BY symbol ORDER BY symbol"#,
+    ),
+    (
+        "duplicate_key",
+        "daily_bars",
+        r#"SELECT concat_ws(' ', symbol, exchange, date), concat(COUNT(*), ' rows')
+           FROM daily_bars
+           GROUP BY symbol, exchange, date HAVING COUNT(*) > 1 ORDER BY 1"#,
+    ),
+    (
+        "duplicate_key",
+        "tickers",
+        r#"SELECT symbol, concat(COUNT(*), ' rows') FROM tickers
+           GROUP BY symbol HAVING COUNT(*) > 1 ORDER BY 1"#,
+    ),
+    (
+        "duplicate_key",
+        "fx_rates",
+        r#"SELECT concat_ws(' ', pair, date, source), concat(COUNT(*), ' rows') FROM fx_rates
+           GROUP BY pair, date, source HAVING COUNT(*) > 1 ORDER BY 1"#,
+    ),
+    (
+        "high_below_low",
+        "daily_bars",
+        r#"SELECT concat_ws(' ', symbol, date), concat('high ', high, ' < low ', low)
+           FROM daily_bars
+           WHERE high < low ORDER BY symbol, date"#,
+    ),
+    (
+        "negative_volume",
+        "daily_bars",
+        r#"SELECT concat_ws(' ', symbol, date), concat('volume ', volume) FROM daily_bars
+           WHERE volume < 0 ORDER BY symbol, date"#,
+    ),
+    (
+        "non_positive_rate",
+        "fx_rates",
+        r#"SELECT concat_ws(' ', pair, date, source), concat('close ', close) FROM fx_rates
+           WHERE close <= 0 ORDER BY pair, date"#,
+    ),
+];
+
+/// Batches larger than this are worth `bulk_insert_bars`' staging overhead.
+pub const BULK_INSERT_THRESHOLD: usize = 500;
+
+/// Tables `export_parquet` may copy; the name is spliced into SQL, so it must
+/// come from this list.
+const EXPORTABLE_TABLES: &[&str] =
+    &["tickers", "daily_bars", "fx_rates", "corporate_actions", "scrape_runs"];
+
+/// Symbols flagged suspended or delisted; a NULL status counts as active.
+const INACTIVE_SYMBOLS: &str =
+    "SELECT symbol FROM tickers WHERE status IN ('suspended', 'delisted')";
+
+/// Calendar days a symbol's last bar may trail the newest bar in the DB
+/// before the health snapshot counts it as stale.
+const STALE_AFTER_DAYS: i64 = 7;
+
+const BAR_COLUMNS: &str =
+    "symbol, exchange, date, open, high, low, close, change_pct, volume, scraped_at";
+
+/// Map a row selected with [`BAR_COLUMNS`] to a `DailyBar`.
+fn bar_from_row(r: &Row<'_>) -> duckdb::Result<DailyBar> {
+    Ok(DailyBar {
+        symbol: r.get(0)?,
+        exchange: r.get(1)?,
+        date: r.get(2)?,
+        open: r.get(3)?,
+        high: r.get(4)?,
+        low: r.get(5)?,
+        close: r.get(6)?,
+        change_pct: r.get(7)?,
+        volume: r.get(8)?,
+        scraped_at: r.get(9)?,
+    })
+}
+
+const SCRAPE_RUN_COLUMNS: &str = r#"id, started_at, finished_at,
+    date_diff('second', started_at, finished_at),
+    status, COALESCE(tickers_processed, 0), COALESCE(bars_inserted, 0), error_msg"#;
+
+/// Map a row selected with [`SCRAPE_RUN_COLUMNS`] to a `ScrapeRun`.
+fn scrape_run_from_row(r: &Row<'_>) -> duckdb::Result<ScrapeRun> {
+    Ok(ScrapeRun {
+        id: r.get(0)?,
+        started_at: r.get(1)?,
+        finished_at: r.get(2)?,
+        duration_secs: r.get(3)?,
+        status: r.get(4)?,
+        tickers_processed: r.get(5)?,
+        bars_inserted: r.get(6)?,
+        error_msg: r.get(7)?,
+    })
+}
+
+/// `audit_log.row_key` for a batch: the key when every row shares it,
+/// otherwise how many distinct keys were touched.
+fn batch_key<'a>(keys: impl Iterator<Item = &'a str>) -> Option<String> {
+    let distinct: std::collections::BTreeSet<&str> = keys.collect();
+    match distinct.len() {
+        0 => None,
+        1 => distinct.into_iter().next().map(str::to_string),
+        n => Some(format!("{} keys", n)),
+    }
+}
+
+/// First wait between attempts to open a locked database; doubles each retry.
+const OPEN_BACKOFF: Duration = Duration::from_millis(250);
+
+/// DuckDB's message when another process holds the file lock.
+fn is_lock_error(msg: &str) -> bool {
+    let msg = msg.to_lowercase();
+    msg.contains("could not set lock") || msg.contains("conflicting lock")
+}
+
+/// First pause before retrying a write that lost a transaction conflict.
+const WRITE_BACKOFF: Duration = Duration::from_millis(50);
+
+/// DuckDB's optimistic-concurrency failures: the transaction lost a race with
+/// another writer and can simply be run again.
+fn is_conflict_error(msg: &str) -> bool {
+    let msg = msg.to_lowercase();
+    msg.contains("write-write conflict")
+        || msg.contains("transaction conflict")
+        || msg.contains("conflict on tuple")
+        || msg.contains("conflict on update")
+}
+
+/// Run `open` until it succeeds, retrying up to `retries` times with doubling
+/// backoff while the failure is a lock conflict. Other errors return at once.
+fn open_with_retry<T, E: std::fmt::Display>(
+    path: &Path,
+    retries: u32,
+    backoff: Duration,
+    mut open: impl FnMut() -> std::result::Result<T, E>,
+) -> Result<T> {
+    let mut delay = backoff;
+    for attempt in 0..=retries {
+        match open() {
+            Ok(v) => return Ok(v),
+            Err(e) if is_lock_error(&e.to_string()) => {
+                if attempt == retries {
+                    anyhow::bail!(
+                        "{:?} is locked by another process (an update still running?); \
+                         gave up after {} retries: {}",
+                        path,
+                        retries,
+                        e
+                    );
+                }
+                warn!("{:?} is locked, retrying in {:?}", path, delay);
+                std::thread::sleep(delay);
+                delay *= 2;
+            }
+            Err(e) => anyhow::bail!("Failed to open DuckDB at {:?}: {}", path, e),
+        }
+    }
+    unreachable!("loop returns on the last attempt")
+}
+
 // ── Repository ────────────────────────────────────────────────────────────────
 
 pub struct Repository {
     conn: Mutex<Connection>,
+    audit: bool,
+    read_only: bool,
+    /// `--dry-run`: upserts and run bookkeeping are logged and skipped.
+    dry_run: bool,
+    /// Extra attempts for a write that hits a transaction conflict.
+    write_retries: u32,
+    /// Scrape run in progress, stamped on audit rows.
+    current_run: Mutex<Option<i64>>,
 }
 
 impl Repository {
-    pub fn open(path: &Path) -> Result<Self> {
+    /// Open (creating if needed) the database at `path`, retrying up to
+    /// `retries` times while another process holds its lock.
+    pub fn open(path: &Path, retries: u32) -> Result<Self> {
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)
                 .with_context(|| format!("Could not create dir {:?}", parent))?;
         }
-        let conn = Connection::open(path)
-            .with_context(|| format!("Failed to open DuckDB at {:?}", path))?;
+        let conn = open_with_retry(path, retries, OPEN_BACKOFF, || Connection::open(path))?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            audit: false,
+            read_only: false,
+            dry_run: false,
+            write_retries: 0,
+            current_run: Mutex::new(None),
+        })
+    }
+
+    /// Open an existing database read-only, so queries never contend for the
+    /// write lock with a running load. Any write through it fails.
+    pub fn open_readonly(path: &Path, retries: u32) -> Result<Self> {
+        anyhow::ensure!(path.exists(), "{:?} does not exist (nothing to read yet)", path);
+        let conn = open_with_retry(path, retries, OPEN_BACKOFF, || {
+            let config = Config::default().access_mode(AccessMode::ReadOnly)?;
+            Connection::open_with_flags(path, config)
+        })?;
         Ok(Self {
             conn: Mutex::new(conn),
+            audit: false,
+            read_only: true,
+            dry_run: false,
+            write_retries: 0,
+            current_run: Mutex::new(None),
         })
     }
 
+    /// Repository for `--dry-run`: the existing database opened read-only so
+    /// lookups (latest dates, known symbols) see real data, or an empty
+    /// in-memory schema when there is no database yet. Upserts are skipped.
+    pub fn open_dry_run(path: &Path, retries: u32) -> Result<Self> {
+        let repo = if path.exists() {
+            Self::open_readonly(path, retries)?
+        } else {
+            let repo = Self::open_in_memory()?;
+            repo.run_migrations()?;
+            repo
+        };
+        Ok(repo.with_dry_run(true))
+    }
+
     pub fn open_in_memory() -> Result<Self> {
         Ok(Self {
             conn: Mutex::new(Connection::open_in_memory()?),
+            audit: false,
+            read_only: false,
+            dry_run: false,
+            write_retries: 0,
+            current_run: Mutex::new(None),
         })
     }
 
+    /// Turn `audit_log` recording on or off (see `storage.audit`).
+    pub fn with_audit(mut self, enabled: bool) -> Self {
+        self.audit = enabled;
+        self
+    }
+
+    /// Retry conflicting writes up to `retries` times (see `storage.write_retries`).
+    pub fn with_write_retries(mut self, retries: u32) -> Self {
+        self.write_retries = retries;
+        self
+    }
+
+    /// Skip every upsert (returning the rows it would have written) and the
+    /// scrape-run bookkeeping, so loads can be rehearsed. Writes that have no
+    /// dry-run path still go through `with_retry`; pair this with
+    /// `open_readonly` to make those fail instead of landing.
+    pub fn with_dry_run(mut self, enabled: bool) -> Self {
+        self.dry_run = enabled;
+        self
+    }
+
+    /// Fail early, with a clearer message than DuckDB's, on writes through a
+    /// read-only repository.
+    fn ensure_writable(&self, what: &str) -> Result<()> {
+        anyhow::ensure!(
+            !self.read_only,
+            "{} needs write access, but the database was opened read-only ({})",
+            what,
+            if self.dry_run { "--dry-run" } else { "--readonly" }
+        );
+        Ok(())
+    }
+
+    /// True (after logging) when a write of `rows` rows via `op` should be
+    /// skipped because this is a dry run.
+    fn skip_write(&self, op: &str, rows: usize) -> bool {
+        if self.dry_run {
+            debug!("Dry run: skipping {} ({} rows)", op, rows);
+        }
+        self.dry_run
+    }
+
     // Helper to reduce boilerplate
     fn conn(&self) -> std::sync::MutexGuard<Connection> {
         self.conn.lock().unwrap()
     }
 
+    /// Run `f` on the connection, re-running it with doubling backoff while it
+    /// fails on a transaction conflict. `f` should open and commit its own
+    /// transaction so a failed attempt rolls back cleanly. Other errors, and
+    /// the last conflict once retries run out, propagate unchanged.
+    pub fn with_retry<T>(&self, f: impl Fn(&Connection) -> Result<T>) -> Result<T> {
+        self.ensure_writable("this write")?;
+        let conn = self.conn();
+        let mut delay = WRITE_BACKOFF;
+        let mut attempt = 0;
+        loop {
+            match f(&conn) {
+                Err(e)
+                    if attempt < self.write_retries && is_conflict_error(&format!("{:#}", e)) =>
+                {
+                    attempt += 1;
+                    warn!(
+                        "Write conflict, retry {}/{} in {:?}: {:#}",
+                        attempt, self.write_retries, delay, e
+                    );
+                    std::thread::sleep(delay);
+                    delay *= 2;
+                }
+                result => return result,
+            }
+        }
+    }
+
     pub fn run_migrations(&self) -> Result<()> {
+        if self.skip_write("run_migrations", 0) {
+            return Ok(());
+        }
+        self.ensure_writable("Migration")?;
         info!("Running migrations…");
         let conn = self.conn();
-        conn.execute_batch(DDL).context("DDL failed")?;
+        conn.execute_batch(SCHEMA_VERSION_TABLE)
+            .context("schema_version creation failed")?;
+        let current: u32 =
+            conn.query_row("SELECT COALESCE(MAX(version), 0) FROM schema_version", [], |r| {
+                r.get(0)
+            })?;
+
+        if MIGRATIONS.iter().any(|(v, _)| *v > current) {
+            conn.execute_batch(DROP_DERIVED)
+                .context("Dropping indexes and views before migrating failed")?;
+        }
+        for &(version, sql) in MIGRATIONS.iter().filter(|(v, _)| *v > current) {
+            let tx = conn.unchecked_transaction()?;
+            tx.execute_batch(sql)
+                .with_context(|| format!("Migration {} failed", version))?;
+            tx.execute(
+                "INSERT INTO schema_version (version, applied_at) VALUES (?, ?)",
+                params![version, Utc::now().naive_utc()],
+            )?;
+            tx.commit()?;
+            info!("Applied migration {}", version);
+        }
+
         conn.execute_batch(INDEXES)
             .context("Index creation failed")?;
-        conn.execute(
-            "INSERT OR IGNORE INTO schema_version (version, applied_at) VALUES (1, ?)",
-            params![Utc::now().naive_utc()],
-        )?;
+        conn.execute_batch(VIEWS).context("View creation failed")?;
         info!("Migrations done.");
         Ok(())
     }
 
+    /// Highest applied migration (call after `run_migrations`).
+    pub fn schema_version(&self) -> Result<u32> {
+        let conn = self.conn();
+        Ok(conn.query_row("SELECT MAX(version) FROM schema_version", [], |r| r.get(0))?)
+    }
+
+    /// Recreate the derived views (`bars_usd`).
+    pub fn refresh_views(&self) -> Result<()> {
+        if self.skip_write("refresh_views", 0) {
+            return Ok(());
+        }
+        self.with_retry(|conn| {
+            conn.execute_batch(VIEWS).context("View creation failed")?;
+            Ok(())
+        })
+    }
+
+    // ── Audit log ─────────────────────────────────────────────────────────────
+
+    /// Append one summary row for a mutating batch. Takes the caller's
+    /// connection (or transaction) so it commits together with the batch.
+    fn audit(
+        &self,
+        conn: &Connection,
+        operation: &str,
+        table: &str,
+        row_key: Option<String>,
+        rows: usize,
+    ) -> Result<()> {
+        if !self.audit {
+            return Ok(());
+        }
+        let run_id = *self.current_run.lock().unwrap();
+        conn.execute(
+            r#"INSERT INTO audit_log (at, operation, table_name, row_key, row_count, run_id)
+               VALUES (?, ?, ?, ?, ?, ?)"#,
+            params![Utc::now().naive_utc(), operation, table, row_key, rows as i64, run_id],
+        )
+        .context("write audit_log")?;
+        Ok(())
+    }
+
+    /// Most recent audit entries, newest first.
+    pub fn recent_audit(&self, limit: usize) -> Result<Vec<AuditEntry>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            r#"SELECT at, operation, table_name, row_key, row_count, run_id
+               FROM audit_log ORDER BY id DESC LIMIT ?"#,
+        )?;
+        let rows = stmt
+            .query_map(params![limit as i64], |r| {
+                Ok(AuditEntry {
+                    at: r.get(0)?,
+                    operation: r.get(1)?,
+                    table_name: r.get(2)?,
+                    row_key: r.get(3)?,
+                    row_count: r.get(4)?,
+                    run_id: r.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
     // ── Tickers ───────────────────────────────────────────────────────────────
 
+    /// Inserts or updates tickers. Fields a ticker leaves `None` keep the stored
+    /// value; `name` is stored as '' (the column default) when never reported.
     pub fn upsert_tickers(&self, tickers: &[Ticker]) -> Result<usize> {
+        if self.skip_write("upsert_tickers", tickers.len()) {
+            return Ok(tickers.len());
+        }
+        self.with_retry(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            for t in tickers {
+                tx.execute(
+                    r#"INSERT INTO tickers
+                           (symbol, name, sector, industry, exchange, isin, board, status, scraped_at)
+                       VALUES (?, COALESCE(?, ''), ?, ?, ?, ?, ?, ?, ?)
+                       ON CONFLICT (symbol) DO UPDATE SET
+                           name      = COALESCE(NULLIF(excluded.name, ''), tickers.name),
+                           sector    = COALESCE(excluded.sector, tickers.sector),
+                           industry  = COALESCE(excluded.industry, tickers.industry),
+                           exchange  = COALESCE(excluded.exchange, tickers.exchange),
+                           isin      = COALESCE(excluded.isin, tickers.isin),
+                           board     = COALESCE(excluded.board, tickers.board),
+                           status    = COALESCE(excluded.status, tickers.status),
+                           scraped_at = excluded.scraped_at"#,
+                    params![
+                        t.symbol,
+                        t.name,
+                        t.sector,
+                        t.industry,
+                        t.exchange,
+                        t.isin,
+                        t.board,
+                        t.status.map(TickerStatus::as_str),
+                        t.scraped_at
+                    ],
+                )
+                .with_context(|| format!("upsert ticker {}", t.symbol))?;
+            }
+            self.audit(
+                &tx,
+                "upsert_tickers",
+                "tickers",
+                batch_key(tickers.iter().map(|t| t.symbol.as_str())),
+                tickers.len(),
+            )?;
+            tx.commit()?;
+            Ok(tickers.len())
+        })
+    }
+
+    /// Full metadata for one ticker, if stored.
+    pub fn get_ticker(&self, symbol: &str) -> Result<Option<Ticker>> {
         let conn = self.conn();
-        let tx = conn.unchecked_transaction()?;
-        for t in tickers {
-            tx.execute(
-                r#"INSERT INTO tickers (symbol, name, sector, industry, exchange, scraped_at)
-                   VALUES (?, ?, ?, ?, ?, ?)
+        let mut stmt = conn.prepare(
+            r#"SELECT symbol, NULLIF(name, ''), sector, industry, exchange, isin, board, status,
+                      scraped_at
+               FROM tickers WHERE symbol = ?"#,
+        )?;
+        let mut rows = stmt.query(params![symbol])?;
+        let Some(r) = rows.next()? else {
+            return Ok(None);
+        };
+        Ok(Some(Ticker {
+            symbol: r.get(0)?,
+            name: r.get(1)?,
+            sector: r.get(2)?,
+            industry: r.get(3)?,
+            exchange: r.get(4)?,
+            isin: r.get(5)?,
+            board: r.get(6)?,
+            status: r
+                .get::<_, Option<String>>(7)?
+                .as_deref()
+                .and_then(TickerStatus::from_label),
+            scraped_at: r.get(8)?,
+        }))
+    }
+
+    /// Symbols whose ticker is flagged suspended or delisted.
+    pub fn inactive_symbols(&self) -> Result<Vec<String>> {
+        let conn = self.conn();
+        let syms = conn
+            .prepare(INACTIVE_SYMBOLS)?
+            .query_map([], |r| r.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(syms)
+    }
+
+    /// Ticker symbols, optionally restricted to one exchange and/or to symbols
+    /// not flagged suspended or delisted.
+    pub fn list_symbols(&self, exchange: Option<&str>, active_only: bool) -> Result<Vec<String>> {
+        let conn = self.conn();
+        let syms: Vec<String> = conn
+            .prepare(
+                r#"SELECT symbol FROM tickers
+                   WHERE (?::VARCHAR IS NULL OR UPPER(exchange) = ?)
+                     AND (NOT ? OR COALESCE(status, 'active') = 'active')
+                   ORDER BY symbol"#,
+            )?
+            .query_map(params![exchange, exchange, active_only], |r| r.get(0))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(syms)
+    }
+
+    /// Flag as suspended every active (or unreported) ticker whose last
+    /// `min_days` closes on its listed exchange (any exchange, if the ticker
+    /// names none) are all identical — the stale-price pattern a halted stock
+    /// leaves. Symbols with fewer bars than that are left alone, as are
+    /// delisted ones. Returns the symbols newly flagged (on a dry run, those
+    /// that would be).
+    pub fn infer_suspended(&self, min_days: usize) -> Result<Vec<String>> {
+        if self.skip_write("infer_suspended", 0) {
+            return Self::suspension_candidates(&self.conn(), min_days);
+        }
+        self.with_retry(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let flagged = Self::suspension_candidates(&tx, min_days)?;
+            for symbol in &flagged {
+                tx.execute(
+                    "UPDATE tickers SET status = ? WHERE symbol = ?",
+                    params![TickerStatus::Suspended.as_str(), symbol],
+                )?;
+            }
+            if !flagged.is_empty() {
+                self.audit(
+                    &tx,
+                    "infer_suspended",
+                    "tickers",
+                    batch_key(flagged.iter().map(String::as_str)),
+                    flagged.len(),
+                )?;
+            }
+            tx.commit()?;
+            Ok(flagged)
+        })
+    }
+
+    /// The tickers `infer_suspended` would flag, read through `conn`.
+    fn suspension_candidates(conn: &Connection, min_days: usize) -> Result<Vec<String>> {
+        let flagged = conn
+            .prepare(
+                r#"WITH recent AS (
+                       SELECT symbol, exchange, close,
+                              row_number() OVER (
+                                  PARTITION BY symbol, exchange ORDER BY date DESC
+                              ) AS rn
+                       FROM daily_bars
+                   )
+                   SELECT DISTINCT r.symbol
+                   FROM recent r
+                   JOIN tickers t
+                     ON t.symbol = r.symbol AND r.exchange = COALESCE(t.exchange, r.exchange)
+                   WHERE r.rn <= ? AND COALESCE(t.status, 'active') = 'active'
+                   GROUP BY r.symbol, r.exchange
+                   HAVING COUNT(*) = ? AND MIN(r.close) = MAX(r.close)
+                   ORDER BY r.symbol"#,
+            )?
+            .query_map(params![min_days as i64, min_days as i64], |r| r.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(flagged)
+    }
+
+    /// Move all of `old`'s bars and its ticker row to `new` in one transaction.
+    ///
+    /// If `new` already has data the two are merged with upsert semantics:
+    /// `old`'s rows are applied as if freshly loaded onto `new`, so on shared
+    /// dates its values win but NULLs keep what `new` had. On a dry run this
+    /// is `rename_preview`.
+    pub fn rename_symbol(&self, old: &str, new: &str) -> Result<RenameReport> {
+        anyhow::ensure!(old != new, "old and new symbol are the same ({})", old);
+        if self.skip_write("rename_symbol", 0) {
+            return self.rename_preview(old, new);
+        }
+
+        self.with_retry(|conn| {
+            let tx = conn.unchecked_transaction()?;
+
+            let bars_merged: i64 = tx.query_row(
+                r#"SELECT COUNT(*) FROM daily_bars o
+                   JOIN daily_bars n
+                     ON n.symbol = ? AND n.exchange = o.exchange AND n.date = o.date
+                   WHERE o.symbol = ?"#,
+                params![new, old],
+                |r| r.get(0),
+            )?;
+
+            let bars_moved = tx.execute(
+                r#"INSERT INTO daily_bars
+                       (symbol, exchange, date, open, high, low, close, change_pct, volume,
+                        scraped_at)
+                   SELECT ?, exchange, date, open, high, low, close, change_pct, volume,
+                          scraped_at
+                   FROM daily_bars WHERE symbol = ?
+                   ON CONFLICT (symbol, exchange, date) DO UPDATE SET
+                       open       = COALESCE(excluded.open, daily_bars.open),
+                       high       = COALESCE(excluded.high, daily_bars.high),
+                       low        = COALESCE(excluded.low, daily_bars.low),
+                       close      = excluded.close,
+                       change_pct = COALESCE(excluded.change_pct, daily_bars.change_pct),
+                       volume     = COALESCE(excluded.volume, daily_bars.volume),
+                       scraped_at = excluded.scraped_at"#,
+                params![new, old],
+            )?;
+            tx.execute("DELETE FROM daily_bars WHERE symbol = ?", params![old])?;
+
+            let ticker_moved = tx.execute(
+                r#"INSERT INTO tickers
+                       (symbol, name, sector, industry, exchange, isin, board, status, scraped_at)
+                   SELECT ?, name, sector, industry, exchange, isin, board, status, scraped_at
+                   FROM tickers WHERE symbol = ?
                    ON CONFLICT (symbol) DO UPDATE SET
-                       name      = excluded.name,
+                       name      = COALESCE(NULLIF(excluded.name, ''), tickers.name),
                        sector    = COALESCE(excluded.sector, tickers.sector),
                        industry  = COALESCE(excluded.industry, tickers.industry),
                        exchange  = COALESCE(excluded.exchange, tickers.exchange),
+                       isin      = COALESCE(excluded.isin, tickers.isin),
+                       board     = COALESCE(excluded.board, tickers.board),
+                       status    = COALESCE(excluded.status, tickers.status),
                        scraped_at = excluded.scraped_at"#,
-                params![t.symbol, t.name, t.sector, t.industry, t.exchange, t.scraped_at],
-            )
-            .with_context(|| format!("upsert ticker {}", t.symbol))?;
-        }
-        tx.commit()?;
-        Ok(tickers.len())
+                params![new, old],
+            )? > 0;
+            tx.execute("DELETE FROM tickers WHERE symbol = ?", params![old])?;
+
+            // Actions follow the bars they adjust; an existing one for `new` wins.
+            tx.execute(
+                r#"INSERT INTO corporate_actions (symbol, ex_date, action_type, ratio_or_amount)
+                   SELECT ?, ex_date, action_type, ratio_or_amount
+                   FROM corporate_actions WHERE symbol = ?
+                   ON CONFLICT DO NOTHING"#,
+                params![new, old],
+            )?;
+            tx.execute("DELETE FROM corporate_actions WHERE symbol = ?", params![old])?;
+
+            anyhow::ensure!(
+                bars_moved > 0 || ticker_moved,
+                "no bars or ticker stored for {}",
+                old
+            );
+
+            self.audit(
+                &tx,
+                "rename_symbol",
+                "daily_bars",
+                Some(format!("{} -> {}", old, new)),
+                bars_moved,
+            )?;
+            tx.commit()?;
+
+            Ok(RenameReport {
+                old: old.to_string(),
+                new: new.to_string(),
+                bars_moved,
+                bars_merged: bars_merged as usize,
+                ticker_moved,
+            })
+        })
     }
 
-    pub fn list_symbols(&self) -> Result<Vec<String>> {
+    /// What `rename_symbol(old, new)` would do, without writing anything.
+    pub fn rename_preview(&self, old: &str, new: &str) -> Result<RenameReport> {
         let conn = self.conn();
-        let mut stmt = conn.prepare("SELECT symbol FROM tickers ORDER BY symbol")?;
-        let syms: Vec<String> = stmt
+        let (bars_moved, bars_merged): (i64, i64) = conn.query_row(
+            r#"SELECT COUNT(*), COUNT(n.date) FROM daily_bars o
+               LEFT JOIN daily_bars n
+                 ON n.symbol = ? AND n.exchange = o.exchange AND n.date = o.date
+               WHERE o.symbol = ?"#,
+            params![new, old],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )?;
+        let tickers: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM tickers WHERE symbol = ?",
+            params![old],
+            |r| r.get(0),
+        )?;
+        Ok(RenameReport {
+            old: old.to_string(),
+            new: new.to_string(),
+            bars_moved: bars_moved as usize,
+            bars_merged: bars_merged as usize,
+            ticker_moved: tickers > 0,
+        })
+    }
+
+    /// Purge a symbol's bars, ticker row and corporate actions in one
+    /// transaction. Returns the total number of rows removed.
+    pub fn delete_symbol(&self, symbol: &str) -> Result<usize> {
+        self.with_retry(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let bars = tx.execute("DELETE FROM daily_bars WHERE symbol = ?", params![symbol])?;
+            let ticker = tx.execute("DELETE FROM tickers WHERE symbol = ?", params![symbol])?;
+            let actions =
+                tx.execute("DELETE FROM corporate_actions WHERE symbol = ?", params![symbol])?;
+            let removed = bars + ticker + actions;
+            self.audit(&tx, "delete_symbol", "daily_bars", Some(symbol.to_string()), removed)?;
+            tx.commit()?;
+            Ok(removed)
+        })
+    }
+
+    /// Drop every bar and FX rate dated before `cutoff` in one transaction.
+    /// Returns the total number of rows removed.
+    pub fn prune_before(&self, cutoff: chrono::NaiveDate) -> Result<usize> {
+        self.with_retry(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let bars = tx.execute("DELETE FROM daily_bars WHERE date < ?", params![cutoff])?;
+            let rates = tx.execute("DELETE FROM fx_rates WHERE date < ?", params![cutoff])?;
+            let key = Some(format!("before {}", cutoff));
+            self.audit(&tx, "prune_before", "daily_bars", key.clone(), bars)?;
+            self.audit(&tx, "prune_before", "fx_rates", key, rates)?;
+            tx.commit()?;
+            Ok(bars + rates)
+        })
+    }
+
+    /// Flush the WAL into the database file, reclaiming space freed by deletes.
+    pub fn checkpoint(&self) -> Result<()> {
+        self.ensure_writable("CHECKPOINT")?;
+        self.conn().execute_batch("CHECKPOINT").context("CHECKPOINT failed")?;
+        Ok(())
+    }
+
+    /// Re-apply the current `normalise_symbol` to every symbol stored in
+    /// `tickers` or `daily_bars`, renaming (and merging on collision) those
+    /// that change. On a dry-run repository nothing is written; the reports
+    /// say what would move.
+    pub fn normalize_symbols(&self) -> Result<Vec<RenameReport>> {
+        let stored: Vec<String> = self
+            .conn()
+            .prepare(
+                r#"SELECT symbol FROM tickers
+                   UNION
+                   SELECT DISTINCT symbol FROM daily_bars
+                   ORDER BY symbol"#,
+            )?
             .query_map([], |r| r.get(0))?
-            .filter_map(|r| r.ok())
-            .collect();
-        Ok(syms)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut reports = Vec::new();
+        for old in stored {
+            let new = normalise_symbol(&old);
+            if new == old {
+                continue;
+            }
+            reports.push(self.rename_symbol(&old, &new)?);
+        }
+        Ok(reports)
     }
 
     // ── Daily bars ────────────────────────────────────────────────────────────
@@ -150,14 +991,184 @@ impl Repository {
         if bars.is_empty() {
             return Ok(0);
         }
+        if self.skip_write("upsert_daily_bars", bars.len()) {
+            return Ok(bars.len());
+        }
 
-        let conn = self.conn();
-        let tx = conn.unchecked_transaction()?;
+        self.with_retry(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            Self::write_bars(&tx, bars)?;
+            self.audit(
+                &tx,
+                "upsert_daily_bars",
+                "daily_bars",
+                batch_key(bars.iter().map(|b| b.symbol.as_str())),
+                bars.len(),
+            )?;
+            tx.commit()?;
+            Ok(bars.len())
+        })
+    }
+
+    /// Fill NULL `change_pct` from the previous close on the same exchange;
+    /// values the source supplied are left alone. A series' first bar stays
+    /// NULL.
+    /// Returns the number of bars filled.
+    pub fn recompute_change_pct(&self) -> Result<usize> {
+        if self.skip_write("recompute_change_pct", 0) {
+            return Ok(0);
+        }
+        self.with_retry(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let n = tx.execute(
+                r#"UPDATE daily_bars SET change_pct = p.pct
+                   FROM (
+                       SELECT symbol, exchange, date,
+                              (close / LAG(close) OVER (
+                                  PARTITION BY symbol, exchange ORDER BY date
+                              ) - 1) * 100.0 AS pct
+                       FROM daily_bars
+                   ) p
+                   WHERE daily_bars.symbol = p.symbol
+                     AND daily_bars.exchange = p.exchange
+                     AND daily_bars.date = p.date
+                     AND daily_bars.change_pct IS NULL
+                     AND p.pct IS NOT NULL"#,
+                [],
+            )?;
+            self.audit(&tx, "recompute_change_pct", "daily_bars", None, n)?;
+            tx.commit()?;
+            Ok(n)
+        })
+    }
+
+    /// `upsert_daily_bars` for large batches: the bars go through DuckDB's
+    /// Appender into a temp table and merge into `daily_bars` with a single
+    /// `INSERT … SELECT`. Same merge rules; a (symbol, exchange, date)
+    /// repeated within `bars` keeps its last occurrence.
+    pub fn bulk_insert_bars(&self, bars: &[DailyBar]) -> Result<usize> {
+        if bars.is_empty() {
+            return Ok(0);
+        }
+        if self.skip_write("bulk_insert_bars", bars.len()) {
+            return Ok(bars.len());
+        }
+
+        self.with_retry(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            tx.execute_batch(
+                r#"CREATE OR REPLACE TEMP TABLE bars_staging AS
+                   SELECT 0::BIGINT AS seq, symbol, exchange, date, open, high, low, close,
+                          change_pct, volume, scraped_at
+                   FROM daily_bars LIMIT 0"#,
+            )?;
+            {
+                let mut appender = tx.appender("bars_staging")?;
+                for (seq, bar) in bars.iter().enumerate() {
+                    appender
+                        .append_row(params![
+                            seq as i64,
+                            bar.symbol,
+                            bar.exchange,
+                            bar.date,
+                            bar.open,
+                            bar.high,
+                            bar.low,
+                            bar.close,
+                            bar.change_pct,
+                            bar.volume,
+                            bar.scraped_at,
+                        ])
+                        .with_context(|| format!("append bar {} {}", bar.symbol, bar.date))?;
+                }
+                appender.flush()?;
+            }
+            tx.execute(
+                r#"INSERT INTO daily_bars
+                       (symbol, exchange, date, open, high, low, close, change_pct, volume, scraped_at)
+                   SELECT symbol, exchange, date, open, high, low, close, change_pct, volume, scraped_at
+                   FROM bars_staging
+                   QUALIFY row_number() OVER (
+                       PARTITION BY symbol, exchange, date ORDER BY seq DESC
+                   ) = 1
+                   ON CONFLICT (symbol, exchange, date) DO UPDATE SET
+                       open       = COALESCE(excluded.open, daily_bars.open),
+                       high       = COALESCE(excluded.high, daily_bars.high),
+                       low        = COALESCE(excluded.low, daily_bars.low),
+                       close      = excluded.close,
+                       change_pct = COALESCE(excluded.change_pct, daily_bars.change_pct),
+                       volume     = COALESCE(excluded.volume, daily_bars.volume),
+                       scraped_at = excluded.scraped_at"#,
+                [],
+            )
+            .context("merge staged bars")?;
+            tx.execute_batch("DROP TABLE bars_staging")?;
+            self.audit(
+                &tx,
+                "bulk_insert_bars",
+                "daily_bars",
+                batch_key(bars.iter().map(|b| b.symbol.as_str())),
+                bars.len(),
+            )?;
+            tx.commit()?;
+            Ok(bars.len())
+        })
+    }
+
+    /// Replace rather than merge: for each symbol and exchange in `bars`,
+    /// delete its stored rows within the batch's date span, then insert, all in
+    /// one transaction. Values the new data leaves NULL stay NULL (no COALESCE
+    /// with old rows), and stored dates the new data omits inside that span
+    /// are dropped.
+    pub fn replace_daily_bars(&self, bars: &[DailyBar]) -> Result<usize> {
+        if bars.is_empty() {
+            return Ok(0);
+        }
+        if self.skip_write("replace_daily_bars", bars.len()) {
+            return Ok(bars.len());
+        }
+
+        let mut spans: std::collections::BTreeMap<
+            (&str, &str),
+            (chrono::NaiveDate, chrono::NaiveDate),
+        > = std::collections::BTreeMap::new();
+        for b in bars {
+            let key = (b.symbol.as_str(), b.exchange.as_str());
+            let span = spans.entry(key).or_insert((b.date, b.date));
+            span.0 = span.0.min(b.date);
+            span.1 = span.1.max(b.date);
+        }
+
+        self.with_retry(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            for ((symbol, exchange), (from, to)) in &spans {
+                tx.execute(
+                    r#"DELETE FROM daily_bars
+                       WHERE symbol = ? AND exchange = ? AND date BETWEEN ? AND ?"#,
+                    params![symbol, exchange, from, to],
+                )
+                .with_context(|| format!("clear {} {}..{}", symbol, from, to))?;
+            }
+            Self::write_bars(&tx, bars)?;
+            self.audit(
+                &tx,
+                "replace_daily_bars",
+                "daily_bars",
+                batch_key(bars.iter().map(|b| b.symbol.as_str())),
+                bars.len(),
+            )?;
+            tx.commit()?;
+            Ok(bars.len())
+        })
+    }
+
+    /// Upsert `bars` on the caller's connection/transaction.
+    fn write_bars(conn: &Connection, bars: &[DailyBar]) -> Result<()> {
         let sql = r#"
             INSERT INTO daily_bars
-                (symbol, date, open, high, low, close, change_pct, volume, scraped_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
-            ON CONFLICT (symbol, date) DO UPDATE SET
+                (symbol, exchange, date, open, high, low, close, change_pct, volume, scraped_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (symbol, exchange, date) DO UPDATE SET
                 open       = COALESCE(excluded.open, daily_bars.open),
                 high       = COALESCE(excluded.high, daily_bars.high),
                 low        = COALESCE(excluded.low, daily_bars.low),
@@ -168,10 +1179,11 @@ impl Repository {
         "#;
 
         for bar in bars {
-            tx.execute(
+            conn.execute(
                 sql,
                 params![
                     bar.symbol,
+                    bar.exchange,
                     bar.date,
                     bar.open,
                     bar.high,
@@ -184,21 +1196,197 @@ impl Repository {
             )
             .with_context(|| format!("insert bar {} {}", bar.symbol, bar.date))?;
         }
+        Ok(())
+    }
+
+    /// Speed path for trusted investing.com equity CSVs: DuckDB reads and
+    /// cleans the file itself (`read_csv`), then upserts in one statement.
+    ///
+    /// Columns are read by position — Date, Price, Open, High, Low, Vol.,
+    /// Change % — and prices only with ',' as a thousands separator, so call
+    /// `loader::fast_load_date_order` first: it refuses files the Rust loader
+    /// would read differently and settles the slash-date order (`None` falls
+    /// back to the loader's day-first-then-month-first guess). Like the Rust
+    /// loader, rows without a date or positive close, dated after today, or
+    /// failing the OHLC check are dropped, though not logged or counted;
+    /// other unparseable cells become NULL. Returns rows written.
+    pub fn bulk_load_equity_csv(
+        &self,
+        path: &Path,
+        symbol: &str,
+        exchange: &str,
+        opts: &LoaderConfig,
+        date_order: Option<DateOrder>,
+    ) -> Result<usize> {
+        let file = path.to_string_lossy().replace('\'', "''");
+        let delim = opts.delimiter.to_string().replace('\'', "''");
+        let quote = opts.quote.to_string().replace('\'', "''");
+        let header = opts.has_headers;
+        let comment = match opts.comment {
+            Some(c) => format!(", comment = '{}'", c.to_string().replace('\'', "''")),
+            None => String::new(),
+        };
+        // Same formats, in the same order, as `cleaner::parse_date_with_hint`.
+        let slash_formats: &[&str] = match date_order {
+            Some(DateOrder::DayFirst) => &["%d/%m/%Y"],
+            Some(DateOrder::MonthFirst) => &["%m/%d/%Y"],
+            None => &["%d/%m/%Y", "%m/%d/%Y"],
+        };
+        let date_expr = ["%b %d, %Y", "%Y-%m-%d"]
+            .iter()
+            .chain(slash_formats)
+            .chain(&["%d %b %Y"])
+            .map(|f| format!("try_strptime(trim(date), '{}')", f))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let rows = format!(
+            r#"
+            WITH raw AS (
+                SELECT * FROM read_csv('{file}', header = {header},
+                    delim = '{delim}', quote = '{quote}'{comment}, columns = {{
+                    'date': 'VARCHAR', 'price': 'VARCHAR', 'open': 'VARCHAR',
+                    'high': 'VARCHAR', 'low': 'VARCHAR', 'volume': 'VARCHAR',
+                    'change_pct': 'VARCHAR'
+                }})
+            ),
+            cleaned AS (
+                SELECT
+                    COALESCE({date_expr})::DATE                             AS date,
+                    TRY_CAST(replace(open,  ',', '') AS DOUBLE)              AS open,
+                    TRY_CAST(replace(high,  ',', '') AS DOUBLE)              AS high,
+                    TRY_CAST(replace(low,   ',', '') AS DOUBLE)              AS low,
+                    TRY_CAST(replace(price, ',', '') AS DOUBLE)              AS close,
+                    TRY_CAST(replace(replace(change_pct, '%', ''), ',', '') AS DOUBLE)
+                                                                             AS change_pct,
+                    upper(trim(replace(volume, ',', '')))                    AS vol
+                FROM raw
+            )
+            SELECT
+                ?, ?, date, open, high, low, close, change_pct,
+                -- Truncated like `parse_volume`: "1.2345K" is 1234 shares
+                trunc(CASE
+                    WHEN vol LIKE '%B' THEN TRY_CAST(rtrim(vol, 'B') AS DOUBLE) * 1e9
+                    WHEN vol LIKE '%M' THEN TRY_CAST(rtrim(vol, 'M') AS DOUBLE) * 1e6
+                    WHEN vol LIKE '%K' THEN TRY_CAST(rtrim(vol, 'K') AS DOUBLE) * 1e3
+                    ELSE TRY_CAST(vol AS DOUBLE)
+                END)::BIGINT,
+                ?
+            FROM cleaned
+            WHERE date IS NOT NULL AND close > 0 AND date <= ?
+              -- `cleaner::check_ohlc`
+              AND (high IS NULL OR low IS NULL OR (high >= low AND (open IS NULL
+                   OR (open BETWEEN low AND high AND close BETWEEN low AND high))))
+            "#
+        );
+        let now = Utc::now().naive_utc();
+        let args = params![symbol, exchange, now, now.date()];
+        if self.skip_write("bulk_load_equity_csv", 0) {
+            let n: i64 = self
+                .conn()
+                .query_row(&format!("SELECT COUNT(*) FROM ({rows})"), args, |r| r.get(0))
+                .with_context(|| format!("bulk load {:?}", path))?;
+            return Ok(n as usize);
+        }
+        let sql = format!(
+            r#"
+            INSERT INTO daily_bars
+                (symbol, exchange, date, open, high, low, close, change_pct, volume, scraped_at)
+            {rows}
+            ON CONFLICT (symbol, exchange, date) DO UPDATE SET
+                open       = COALESCE(excluded.open, daily_bars.open),
+                high       = COALESCE(excluded.high, daily_bars.high),
+                low        = COALESCE(excluded.low, daily_bars.low),
+                close      = excluded.close,
+                change_pct = COALESCE(excluded.change_pct, daily_bars.change_pct),
+                volume     = COALESCE(excluded.volume, daily_bars.volume),
+                scraped_at = excluded.scraped_at
+            "#
+        );
 
-        tx.commit()?;
-        Ok(bars.len())
+        self.with_retry(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let n = tx
+                .execute(&sql, args)
+                .with_context(|| format!("bulk load {:?}", path))?;
+            self.audit(&tx, "bulk_load_equity_csv", "daily_bars", Some(symbol.to_string()), n)?;
+            tx.commit()?;
+            Ok(n)
+        })
     }
 
-    pub fn latest_date_for_symbol(&self, symbol: &str) -> Result<Option<chrono::NaiveDate>> {
+    /// Newest stored date for `symbol` (on `exchange`, if given).
+    pub fn latest_date_for_symbol(
+        &self,
+        symbol: &str,
+        exchange: Option<&str>,
+    ) -> Result<Option<chrono::NaiveDate>> {
         let conn = self.conn();
-        let mut stmt = conn.prepare("SELECT MAX(date) FROM daily_bars WHERE symbol = ?")?;
+        // MAX always yields one row (NULL without bars), so any error here is
+        // real and must not read as "no data, fetch everything".
+        let mut stmt = conn.prepare(
+            r#"SELECT MAX(date) FROM daily_bars
+               WHERE symbol = ? AND (?::VARCHAR IS NULL OR exchange = ?)"#,
+        )?;
         let date: Option<chrono::NaiveDate> = stmt
-            .query_row(params![symbol], |r| r.get(0))
-            .ok()
-            .flatten();
+            .query_row(params![symbol, exchange, exchange], |r| r.get(0))
+            .with_context(|| format!("latest date for {}", symbol))?;
         Ok(date)
     }
 
+    /// Most recent bar stored for `symbol`.
+    pub fn last_bar(&self, symbol: &str) -> Result<Option<DailyBar>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM daily_bars WHERE symbol = ? ORDER BY date DESC LIMIT 1",
+            BAR_COLUMNS
+        ))?;
+        let mut rows = stmt.query(params![symbol])?;
+        match rows.next()? {
+            Some(r) => Ok(Some(bar_from_row(r)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The `n` most recently written bars (optionally for one symbol), oldest
+    /// write first.
+    pub fn recent_bars(&self, symbol: Option<&str>, n: usize) -> Result<Vec<DailyBar>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            r#"SELECT * FROM (
+                   SELECT {} FROM daily_bars
+                   WHERE (?::VARCHAR IS NULL OR symbol = ?)
+                   ORDER BY scraped_at DESC, symbol DESC, date DESC
+                   LIMIT ?
+               ) ORDER BY scraped_at, symbol, date"#,
+            BAR_COLUMNS
+        ))?;
+        let bars = stmt
+            .query_map(params![symbol, symbol, n as i64], bar_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(bars)
+    }
+
+    /// Bars whose `scraped_at` is newer than `watermark`, in write order —
+    /// the poll step of `tail --follow`.
+    pub fn bars_scraped_after(
+        &self,
+        symbol: Option<&str>,
+        watermark: NaiveDateTime,
+    ) -> Result<Vec<DailyBar>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            r#"SELECT {} FROM daily_bars
+               WHERE scraped_at > ?
+                 AND (?::VARCHAR IS NULL OR symbol = ?)
+               ORDER BY scraped_at, symbol, date"#,
+            BAR_COLUMNS
+        ))?;
+        let bars = stmt
+            .query_map(params![watermark, symbol, symbol], bar_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(bars)
+    }
+
     pub fn bar_count(&self) -> Result<i64> {
         let conn = self.conn();
         let mut s = conn.prepare("SELECT COUNT(*) FROM daily_bars")?;
@@ -217,49 +1405,619 @@ impl Repository {
         Ok(s.query_row([], |r| Ok((r.get(0)?, r.get(1)?)))?)
     }
 
-    // ── FX rates ──────────────────────────────────────────────────────────────
+    /// Advancers/decliners/unchanged on `date` (on `exchange`, if given,
+    /// and leaving out suspended or delisted symbols with `active_only`); all
+    /// zero when nothing is stored for that day.
+    pub fn market_breadth(
+        &self,
+        date: chrono::NaiveDate,
+        exchange: Option<&str>,
+        active_only: bool,
+    ) -> Result<Breadth> {
+        let conn = self.conn();
+        let (advancers, decliners, unchanged) = conn.query_row(
+            &format!(
+                r#"SELECT COUNT(*) FILTER (WHERE change_pct > 0),
+                          COUNT(*) FILTER (WHERE change_pct < 0),
+                          COUNT(*) FILTER (WHERE change_pct = 0)
+                   FROM daily_bars
+                   WHERE date = ? AND (?::VARCHAR IS NULL OR exchange = ?)
+                     AND (NOT ? OR symbol NOT IN ({INACTIVE_SYMBOLS}))"#
+            ),
+            params![date, exchange, exchange, active_only],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )?;
+        Ok(Breadth { date, advancers, decliners, unchanged })
+    }
 
-    pub fn upsert_fx_rates(&self, rates: &[FxRate]) -> Result<usize> {
-        if rates.is_empty() {
-            return Ok(0);
-        }
+    /// Every date on which at least one symbol has a bar, ascending.
+    pub fn distinct_trading_dates(&self) -> Result<Vec<chrono::NaiveDate>> {
+        let conn = self.conn();
+        let dates = conn
+            .prepare("SELECT DISTINCT date FROM daily_bars ORDER BY date")?
+            .query_map([], |r| r.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(dates)
+    }
 
+    /// Stream bars (one symbol, or all) through `f` in (symbol, date) order
+    /// without collecting them, so whole-table scans stay in constant memory.
+    /// Returns the number of bars visited; an error from `f` stops the scan.
+    pub fn for_each_bar(
+        &self,
+        symbol: Option<&str>,
+        mut f: impl FnMut(DailyBar) -> Result<()>,
+    ) -> Result<usize> {
         let conn = self.conn();
-        let tx = conn.unchecked_transaction()?;
-        let sql = r#"
-            INSERT INTO fx_rates
-                (pair, date, open, high, low, close, change_pct, source, scraped_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
-            ON CONFLICT (pair, date) DO UPDATE SET
-                open       = COALESCE(excluded.open, fx_rates.open),
-                high       = COALESCE(excluded.high, fx_rates.high),
-                low        = COALESCE(excluded.low, fx_rates.low),
-                close      = excluded.close,
-                change_pct = COALESCE(excluded.change_pct, fx_rates.change_pct),
-                source     = COALESCE(excluded.source, fx_rates.source),
-                scraped_at = excluded.scraped_at
-        "#;
+        let where_clause = if symbol.is_some() { "WHERE symbol = ?" } else { "" };
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM daily_bars {} ORDER BY symbol, date",
+            BAR_COLUMNS, where_clause
+        ))?;
+        let mut rows = match symbol {
+            Some(sym) => stmt.query(params![sym])?,
+            None => stmt.query([])?,
+        };
 
-        for rate in rates {
-            tx.execute(
-                sql,
+        let mut n = 0usize;
+        while let Some(row) = rows.next()? {
+            f(bar_from_row(row)?)?;
+            n += 1;
+        }
+        Ok(n)
+    }
+
+    /// `(present, expected)` trading days for `symbol` (on `exchange`, if
+    /// given) in `year`, where expected is weekdays minus `holidays`. A date
+    /// counts once however many exchanges have it. The current year is
+    /// counted up to today.
+    pub fn trading_day_coverage(
+        &self,
+        symbol: &str,
+        exchange: Option<&str>,
+        year: i32,
+        holidays: &[chrono::NaiveDate],
+    ) -> Result<(i64, i64)> {
+        let start = chrono::NaiveDate::from_ymd_opt(year, 1, 1)
+            .with_context(|| format!("invalid year {}", year))?;
+        let end = chrono::NaiveDate::from_ymd_opt(year, 12, 31)
+            .with_context(|| format!("invalid year {}", year))?
+            .min(Utc::now().date_naive());
+
+        let conn = self.conn();
+        let present: i64 = conn.query_row(
+            r#"SELECT COUNT(DISTINCT date) FROM daily_bars
+               WHERE symbol = ? AND (?::VARCHAR IS NULL OR exchange = ?)
+                 AND date BETWEEN ? AND ?"#,
+            params![symbol, exchange, exchange, start, end],
+            |r| r.get(0),
+        )?;
+        let expected = crate::utils::expected_trading_days(start, end, holidays);
+        Ok((present, expected))
+    }
+
+    /// Symbols whose newest `scraped_at` is more than `older_than_hours` ago,
+    /// with that timestamp, oldest first. Unlike date gaps this tracks when we
+    /// last wrote the symbol, so tickers the pipeline keeps skipping or
+    /// failing on show up before any trading day goes missing.
+    pub fn stale_symbols(&self, older_than_hours: i64) -> Result<Vec<(String, NaiveDateTime)>> {
+        let cutoff = Utc::now().naive_utc() - chrono::Duration::hours(older_than_hours);
+        let conn = self.conn();
+        let stale = conn
+            .prepare(
+                r#"SELECT symbol, MAX(scraped_at) AS last_scraped
+                   FROM daily_bars
+                   GROUP BY symbol
+                   HAVING MAX(scraped_at) < ?
+                   ORDER BY last_scraped, symbol"#,
+            )?
+            .query_map(params![cutoff], |r| Ok((r.get(0)?, r.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(stale)
+    }
+
+    /// Consecutive stored dates for `symbol` (on `exchange`, if given) within
+    /// `range` more than `max_gap_days` apart, as `(last_before, first_after)`.
+    /// Weekends alone make 3-day gaps and holidays longer ones, so the
+    /// threshold is the caller's call.
+    pub fn find_date_gaps(
+        &self,
+        symbol: &str,
+        exchange: Option<&str>,
+        range: DateRange,
+        max_gap_days: i64,
+    ) -> Result<Vec<(chrono::NaiveDate, chrono::NaiveDate)>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            r#"SELECT prev_date, date FROM (
+                   SELECT date, LAG(date) OVER (ORDER BY date) AS prev_date
+                   FROM (
+                       SELECT DISTINCT date FROM daily_bars
+                       WHERE symbol = ? AND (?::VARCHAR IS NULL OR exchange = ?)
+                         AND (?::DATE IS NULL OR date >= ?)
+                         AND (?::DATE IS NULL OR date <= ?)
+                   )
+               )
+               WHERE prev_date IS NOT NULL AND date_diff('day', prev_date, date) > ?
+               ORDER BY date"#,
+        )?;
+        let gaps = stmt
+            .query_map(
                 params![
-                    rate.pair,
-                    rate.date,
-                    rate.open,
-                    rate.high,
-                    rate.low,
-                    rate.close,
-                    rate.change_pct,
-                    rate.source,
-                    rate.scraped_at,
+                    symbol,
+                    exchange,
+                    exchange,
+                    range.from,
+                    range.from,
+                    range.to,
+                    range.to,
+                    max_gap_days
                 ],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(gaps)
+    }
+
+    /// Bar count and date span per symbol, optionally restricted to one
+    /// exchange. Symbols known from `tickers` but without any bars are
+    /// included with a count of 0.
+    ///
+    /// Rows come most bars first, ties by symbol; every `--format` of the
+    /// `coverage` command keeps this order.
+    pub fn symbol_coverage(
+        &self,
+        exchange: Option<&str>,
+        active_only: bool,
+    ) -> Result<Vec<CoverageRow>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            r#"WITH counts AS (
+                   SELECT symbol, COUNT(*) AS bars, MIN(date) AS first_date,
+                          MAX(date) AS last_date
+                   FROM daily_bars
+                   WHERE (?::VARCHAR IS NULL OR exchange = ?)
+                   GROUP BY symbol
+               ),
+               known AS (
+                   SELECT symbol FROM tickers WHERE (?::VARCHAR IS NULL OR exchange = ?)
+               )
+               SELECT COALESCE(c.symbol, k.symbol) AS sym, COALESCE(c.bars, 0) AS bars,
+                      c.first_date, c.last_date
+               FROM counts c
+               FULL OUTER JOIN known k ON k.symbol = c.symbol
+               WHERE NOT ? OR COALESCE(c.symbol, k.symbol) NOT IN ({INACTIVE_SYMBOLS})
+               ORDER BY bars DESC, sym"#
+        ))?;
+        let rows = stmt
+            .query_map(params![exchange, exchange, exchange, exchange, active_only], |r| {
+                Ok(CoverageRow {
+                    symbol: r.get(0)?,
+                    bars: r.get(1)?,
+                    first_date: r.get(2)?,
+                    last_date: r.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Date-ordered bars for one symbol within `range`, optionally only those
+    /// from one exchange.
+    ///
+    /// With `max_points`, a range holding more bars than that is downsampled
+    /// in DuckDB into equal calendar-day buckets counted from the first bar,
+    /// sized so at most `max_points` come back. Each bucket becomes one bar
+    /// dated at its first day: open of its first bar, close of its last, max
+    /// high, min low, summed volume, and no `change_pct`. Smaller ranges come
+    /// back raw.
+    pub fn bars_in_range(
+        &self,
+        symbol: &str,
+        exchange: Option<&str>,
+        range: DateRange,
+        max_points: Option<usize>,
+    ) -> Result<Vec<DailyBar>> {
+        let conn = self.conn();
+        if let Some(max_points) = max_points {
+            anyhow::ensure!(max_points >= 1, "max_points must be at least 1");
+            let (count, first, last): (i64, Option<chrono::NaiveDate>, Option<chrono::NaiveDate>) =
+                conn.query_row(
+                    r#"SELECT COUNT(*), MIN(date), MAX(date) FROM daily_bars
+                       WHERE symbol = ?
+                         AND (?::VARCHAR IS NULL OR exchange = ?)
+                         AND (?::DATE IS NULL OR date >= ?)
+                         AND (?::DATE IS NULL OR date <= ?)"#,
+                    params![symbol, exchange, exchange, range.from, range.from, range.to, range.to],
+                    |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+                )?;
+            if let (Some(first), Some(last)) = (first, last)
+                && count as usize > max_points
+            {
+                let span_days = (last - first).num_days() + 1;
+                let bucket_days = (span_days as u64).div_ceil(max_points as u64) as i64;
+                let mut stmt = conn.prepare(
+                    r#"SELECT ANY_VALUE(symbol), ANY_VALUE(exchange), MIN(date),
+                              ARG_MIN(open, date), MAX(high), MIN(low), ARG_MAX(close, date),
+                              NULL::DOUBLE, SUM(volume)::BIGINT, MAX(scraped_at)
+                       FROM daily_bars
+                       WHERE symbol = ? AND (?::VARCHAR IS NULL OR exchange = ?)
+                         AND date BETWEEN ? AND ?
+                       GROUP BY date_diff('day', ?::DATE, date) // ?
+                       ORDER BY 3"#,
+                )?;
+                let bars = stmt
+                    .query_map(
+                        params![symbol, exchange, exchange, first, last, first, bucket_days],
+                        bar_from_row,
+                    )?
+                    .collect::<Result<Vec<_>, _>>()?;
+                return Ok(bars);
+            }
+        }
+
+        let mut stmt = conn.prepare(&format!(
+            r#"SELECT {BAR_COLUMNS} FROM daily_bars
+               WHERE symbol = ?
+                 AND (?::VARCHAR IS NULL OR exchange = ?)
+                 AND (?::DATE IS NULL OR date >= ?)
+                 AND (?::DATE IS NULL OR date <= ?)
+               ORDER BY date"#
+        ))?;
+        let bars = stmt
+            .query_map(
+                params![symbol, exchange, exchange, range.from, range.from, range.to, range.to],
+                bar_from_row,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(bars)
+    }
+
+    /// One bar per `freq` period within `range`, dated at the period start:
+    /// first open, max high, min low, last close, summed volume, and no
+    /// `change_pct`. Days missing open/high/low stand in with their close, so
+    /// close-only history still yields complete bars.
+    pub fn resample(
+        &self,
+        symbol: &str,
+        exchange: Option<&str>,
+        range: DateRange,
+        freq: Resample,
+    ) -> Result<Vec<DailyBar>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            r#"SELECT ANY_VALUE(symbol), ANY_VALUE(exchange), date_trunc(?, date)::DATE AS period,
+                      ARG_MIN(COALESCE(open, close), date),
+                      MAX(COALESCE(high, close)), MIN(COALESCE(low, close)),
+                      ARG_MAX(close, date),
+                      NULL::DOUBLE, SUM(volume)::BIGINT, MAX(scraped_at)
+               FROM daily_bars
+               WHERE symbol = ?
+                 AND (?::VARCHAR IS NULL OR exchange = ?)
+                 AND (?::DATE IS NULL OR date >= ?)
+                 AND (?::DATE IS NULL OR date <= ?)
+               GROUP BY period
+               ORDER BY period"#,
+        )?;
+        let bars = stmt
+            .query_map(
+                params![
+                    freq.date_part(),
+                    symbol,
+                    exchange,
+                    exchange,
+                    range.from,
+                    range.from,
+                    range.to,
+                    range.to
+                ],
+                bar_from_row,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(bars)
+    }
+
+    /// Date-ordered closes for one symbol within `range`.
+    pub fn close_series(
+        &self,
+        symbol: &str,
+        exchange: Option<&str>,
+        range: DateRange,
+    ) -> Result<PriceSeries> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            r#"SELECT date, close FROM daily_bars
+               WHERE symbol = ?
+                 AND (?::VARCHAR IS NULL OR exchange = ?)
+                 AND (?::DATE IS NULL OR date >= ?)
+                 AND (?::DATE IS NULL OR date <= ?)
+               ORDER BY date"#,
+        )?;
+        let points = stmt
+            .query_map(
+                params![symbol, exchange, exchange, range.from, range.from, range.to, range.to],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(PriceSeries::new(points))
+    }
+
+    /// `symbols`' closes (on `exchange`, if given) on their common dates, each
+    /// rebased to 100 at `base` (or the earliest common date). Errors if they
+    /// share no date from there.
+    pub fn rebased_series(
+        &self,
+        symbols: &[String],
+        exchange: Option<&str>,
+        base: Option<chrono::NaiveDate>,
+    ) -> Result<AlignedSeries> {
+        let series = symbols
+            .iter()
+            .map(|s| self.close_series(s, exchange, DateRange::default()))
+            .collect::<Result<Vec<_>>>()?;
+        analytics::align(&series).rebased(base).with_context(|| {
+            format!(
+                "{} share no trading dates{}",
+                symbols.join(", "),
+                base.map(|b| format!(" on or after {}", b)).unwrap_or_default()
             )
-            .with_context(|| format!("insert fx {} {}", rate.pair, rate.date))?;
+        })
+    }
+
+    /// Day-over-day returns for `symbol` (on `exchange`, if given) in naira and
+    /// in dollars, read from the `bars_usd` view. Each exchange's first bar in
+    /// `range` has no returns.
+    pub fn real_returns(
+        &self,
+        symbol: &str,
+        exchange: Option<&str>,
+        range: DateRange,
+    ) -> Result<Vec<RealReturn>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            r#"SELECT date, close_ngn, fx, close_usd,
+                      (close_ngn / LAG(close_ngn) OVER w - 1) * 100.0,
+                      (close_usd / LAG(close_usd) OVER w - 1) * 100.0
+               FROM bars_usd
+               WHERE symbol = ?
+                 AND (?::VARCHAR IS NULL OR exchange = ?)
+                 AND (?::DATE IS NULL OR date >= ?)
+                 AND (?::DATE IS NULL OR date <= ?)
+               WINDOW w AS (PARTITION BY exchange ORDER BY date)
+               ORDER BY date, exchange"#,
+        )?;
+        let rows = stmt
+            .query_map(
+                params![symbol, exchange, exchange, range.from, range.from, range.to, range.to],
+                |r| {
+                    Ok(RealReturn {
+                        date: r.get(0)?,
+                        close_ngn: r.get(1)?,
+                        fx: r.get(2)?,
+                        close_usd: r.get(3)?,
+                        return_ngn_pct: r.get(4)?,
+                        return_usd_pct: r.get(5)?,
+                    })
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// A symbol's closes in dollars, each divided by the latest `USDNGN` rate
+    /// on or before its date (so NGX sessions without an FX row reuse the last
+    /// one). Bars older than the first rate are left out; no `USDNGN` rows at
+    /// all is an error rather than an empty series.
+    pub fn bars_in_usd(
+        &self,
+        symbol: &str,
+        exchange: Option<&str>,
+    ) -> Result<Vec<(chrono::NaiveDate, f64)>> {
+        let conn = self.conn();
+        let has_rates: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM fx_rates WHERE pair = 'USDNGN'",
+            [],
+            |r| r.get(0),
+        )?;
+        anyhow::ensure!(has_rates, "no USDNGN rates stored; load or fetch FX first");
+
+        let mut stmt = conn.prepare(
+            r#"SELECT date, close_usd FROM bars_usd
+               WHERE symbol = ? AND (?::VARCHAR IS NULL OR exchange = ?)
+                 AND close_usd IS NOT NULL
+               ORDER BY date"#,
+        )?;
+        let rows = stmt
+            .query_map(params![symbol, exchange, exchange], |r| Ok((r.get(0)?, r.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Heuristic unadjusted-split finder: day-over-day closes whose ratio is
+    /// within `tolerance_pct` of a common split ratio (see
+    /// [`analytics::split_ratio_match`]), unless the bar's own `change_pct`
+    /// reports a move of that size — then the source treats it as a real
+    /// price change. Expect false positives around genuine crashes or
+    /// consecutive limit moves; review before adjusting anything.
+    /// Moves are compared within one exchange's series (only `exchange`'s, if
+    /// given); `active_only` skips suspended or delisted symbols. Only moves
+    /// into a day within `range` are reported, though the day before may lie
+    /// outside it. Returns `(symbol, date, prev_close / close)`.
+    pub fn detect_possible_splits(
+        &self,
+        exchange: Option<&str>,
+        active_only: bool,
+        range: DateRange,
+        tolerance_pct: f64,
+    ) -> Result<Vec<(String, chrono::NaiveDate, f64)>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            r#"SELECT symbol, date, prev_close, close, change_pct FROM (
+                   SELECT symbol, exchange, date, close, change_pct,
+                          LAG(close) OVER (
+                              PARTITION BY symbol, exchange ORDER BY date
+                          ) AS prev_close
+                   FROM daily_bars
+                   WHERE (?::VARCHAR IS NULL OR exchange = ?)
+                     AND (NOT ? OR symbol NOT IN ({INACTIVE_SYMBOLS}))
+               )
+               WHERE prev_close IS NOT NULL
+                 AND (?::DATE IS NULL OR date >= ?)
+                 AND (?::DATE IS NULL OR date <= ?)
+               ORDER BY symbol, date, exchange"#
+        ))?;
+        let mut rows = stmt.query(params![
+            exchange,
+            exchange,
+            active_only,
+            range.from,
+            range.from,
+            range.to,
+            range.to
+        ])?;
+        let mut found = Vec::new();
+        while let Some(r) = rows.next()? {
+            let (prev, close): (f64, f64) = (r.get(2)?, r.get(3)?);
+            let Some(ratio) = analytics::split_ratio_match(prev, close, tolerance_pct) else {
+                continue;
+            };
+            let reported: Option<f64> = r.get(4)?;
+            let actual_pct = (close / prev - 1.0) * 100.0;
+            if reported.is_some_and(|p| (p - actual_pct).abs() <= tolerance_pct) {
+                continue;
+            }
+            found.push((r.get(0)?, r.get(1)?, ratio));
+        }
+        Ok(found)
+    }
+
+    /// Worst peak-to-trough decline in a symbol's closes within `range`:
+    /// `(peak_date, trough_date, fraction)`, or `None` when it has no bars.
+    pub fn max_drawdown(
+        &self,
+        symbol: &str,
+        exchange: Option<&str>,
+        range: DateRange,
+    ) -> Result<Option<(chrono::NaiveDate, chrono::NaiveDate, f64)>> {
+        Ok(self.close_series(symbol, exchange, range)?.max_drawdown())
+    }
+
+    // ── Corporate actions ─────────────────────────────────────────────────────
+
+    pub fn upsert_corporate_actions(&self, actions: &[CorporateAction]) -> Result<usize> {
+        if actions.is_empty() {
+            return Ok(0);
+        }
+        if self.skip_write("upsert_corporate_actions", actions.len()) {
+            return Ok(actions.len());
         }
 
-        tx.commit()?;
-        Ok(rates.len())
+        self.with_retry(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            for a in actions {
+                tx.execute(
+                    r#"INSERT INTO corporate_actions (symbol, ex_date, action_type, ratio_or_amount)
+                       VALUES (?, ?, ?, ?)
+                       ON CONFLICT (symbol, ex_date, action_type) DO UPDATE SET
+                           ratio_or_amount = excluded.ratio_or_amount"#,
+                    params![a.symbol, a.ex_date, a.action_type.as_str(), a.ratio_or_amount],
+                )
+                .with_context(|| format!("upsert action {} {}", a.symbol, a.ex_date))?;
+            }
+            self.audit(
+                &tx,
+                "upsert_corporate_actions",
+                "corporate_actions",
+                batch_key(actions.iter().map(|a| a.symbol.as_str())),
+                actions.len(),
+            )?;
+            tx.commit()?;
+            Ok(actions.len())
+        })
+    }
+
+    /// A symbol's closes, oldest first, back-adjusted for splits: each close
+    /// is divided by the product of the ratios of every split with a later
+    /// ex-date, so prices on either side of a split are comparable.
+    /// Dividends are not adjusted for.
+    pub fn adjusted_close_series(
+        &self,
+        symbol: &str,
+        exchange: Option<&str>,
+    ) -> Result<Vec<(chrono::NaiveDate, f64)>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            r#"SELECT b.date,
+                      b.close / COALESCE((
+                          SELECT product(a.ratio_or_amount)
+                          FROM corporate_actions a
+                          WHERE a.symbol = b.symbol
+                            AND a.action_type = 'split'
+                            AND a.ex_date > b.date
+                      ), 1.0)
+               FROM daily_bars b
+               WHERE b.symbol = ? AND (?::VARCHAR IS NULL OR b.exchange = ?)
+               ORDER BY b.date"#,
+        )?;
+        let points = stmt
+            .query_map(params![symbol, exchange, exchange], |r| Ok((r.get(0)?, r.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(points)
+    }
+
+    // ── FX rates ──────────────────────────────────────────────────────────────
+
+    pub fn upsert_fx_rates(&self, rates: &[FxRate]) -> Result<usize> {
+        if rates.is_empty() {
+            return Ok(0);
+        }
+        if self.skip_write("upsert_fx_rates", rates.len()) {
+            return Ok(rates.len());
+        }
+
+        self.with_retry(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let sql = r#"
+                INSERT INTO fx_rates
+                    (pair, date, open, high, low, close, change_pct, source, scraped_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT (pair, date, source) DO UPDATE SET
+                    open       = COALESCE(excluded.open, fx_rates.open),
+                    high       = COALESCE(excluded.high, fx_rates.high),
+                    low        = COALESCE(excluded.low, fx_rates.low),
+                    close      = excluded.close,
+                    change_pct = COALESCE(excluded.change_pct, fx_rates.change_pct),
+                    scraped_at = excluded.scraped_at
+            "#;
+
+            for rate in rates {
+                tx.execute(
+                    sql,
+                    params![
+                        rate.pair,
+                        rate.date,
+                        rate.open,
+                        rate.high,
+                        rate.low,
+                        rate.close,
+                        rate.change_pct,
+                        rate.source.as_deref().unwrap_or(UNKNOWN_FX_SOURCE),
+                        rate.scraped_at,
+                    ],
+                )
+                .with_context(|| format!("insert fx {} {}", rate.pair, rate.date))?;
+            }
+
+            self.audit(
+                &tx,
+                "upsert_fx_rates",
+                "fx_rates",
+                batch_key(rates.iter().map(|r| r.pair.as_str())),
+                rates.len(),
+            )?;
+            tx.commit()?;
+            Ok(rates.len())
+        })
     }
 
     pub fn fx_count(&self) -> Result<i64> {
@@ -274,15 +2032,225 @@ impl Repository {
         Ok(s.query_row([], |r| Ok((r.get(0)?, r.get(1)?)))?)
     }
 
-    // ── Scrape runs ───────────────────────────────────────────────────────────
+    /// Latest `pair` rate dated on or before `date`, averaged across sources
+    /// quoting that day, with the date it was quoted.
+    pub fn fx_rate_as_of(
+        &self,
+        pair: &str,
+        date: chrono::NaiveDate,
+    ) -> Result<Option<(chrono::NaiveDate, f64)>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            r#"SELECT date, AVG(close) FROM fx_rates
+               WHERE pair = ? AND date <= ?
+               GROUP BY date
+               ORDER BY date DESC
+               LIMIT 1"#,
+        )?;
+        let mut rows = stmt.query(params![pair, date])?;
+        match rows.next()? {
+            Some(r) => Ok(Some((r.get(0)?, r.get(1)?))),
+            None => Ok(None),
+        }
+    }
 
-    pub fn begin_scrape_run(&self) -> Result<i64> {
+    /// Annualised change of `pair` between the as-of rates at `from` and `to`
+    /// (see [`analytics::annualised_change`]), over the requested calendar
+    /// span. `None` when either endpoint has no rate on or before it, a rate
+    /// isn't positive, or `to` isn't after `from`.
+    pub fn fx_annualised_change(
+        &self,
+        pair: &str,
+        from: chrono::NaiveDate,
+        to: chrono::NaiveDate,
+    ) -> Result<Option<f64>> {
+        let days = (to - from).num_days();
+        if days <= 0 {
+            return Ok(None);
+        }
+        let (Some((_, start)), Some((_, end))) =
+            (self.fx_rate_as_of(pair, from)?, self.fx_rate_as_of(pair, to)?)
+        else {
+            return Ok(None);
+        };
+        Ok(analytics::annualised_change(start, end, days))
+    }
+
+    /// Percentage spread of `source_b` over `source_a` for each date both
+    /// sources quoted `pair`: `(b - a) / a * 100`. Dates held by only one
+    /// source are dropped.
+    pub fn fx_source_spread(
+        &self,
+        pair: &str,
+        source_a: &str,
+        source_b: &str,
+    ) -> Result<Vec<(chrono::NaiveDate, f64)>> {
         let conn = self.conn();
-        conn.execute(
-            "INSERT INTO scrape_runs (started_at, status) VALUES (?, 'running')",
-            params![Utc::now().naive_utc()],
+        let mut stmt = conn.prepare(
+            r#"SELECT a.date, (b.close - a.close) / a.close * 100.0
+               FROM fx_rates a
+               JOIN fx_rates b ON b.pair = a.pair AND b.date = a.date
+               WHERE a.pair = ? AND a.source = ? AND b.source = ?
+               ORDER BY a.date"#,
+        )?;
+        let rows = stmt
+            .query_map(params![pair, source_a, source_b], |r| Ok((r.get(0)?, r.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    // ── Export ────────────────────────────────────────────────────────────────
+
+    /// Write a whole table to a Parquet file with DuckDB's native `COPY`,
+    /// overwriting `out` and creating its directory if needed. Returns the
+    /// number of rows written.
+    pub fn export_parquet(&self, table: &str, out: &Path) -> Result<usize> {
+        anyhow::ensure!(
+            EXPORTABLE_TABLES.contains(&table),
+            "Cannot export {:?}; choose one of {}",
+            table,
+            EXPORTABLE_TABLES.join(", ")
+        );
+        if let Some(parent) = out.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Could not create dir {:?}", parent))?;
+        }
+        let path = out
+            .to_str()
+            .with_context(|| format!("{:?} is not valid UTF-8", out))?
+            .replace('\'', "''");
+        let n = self
+            .conn()
+            .execute(
+                &format!("COPY (SELECT * FROM {table}) TO '{path}' (FORMAT PARQUET)"),
+                [],
+            )
+            .with_context(|| format!("export {} to {:?}", table, out))?;
+        Ok(n)
+    }
+
+    // ── Ad-hoc SQL ────────────────────────────────────────────────────────────
+
+    /// Run a user-supplied query and return column names plus every value
+    /// rendered as text (NULL → "—").
+    ///
+    /// Only a single SELECT or WITH statement is accepted: anything else, or a
+    /// `;` that would start a second statement, is refused before DuckDB sees
+    /// it. The query then runs as a subquery, so a WITH that doesn't end in a
+    /// SELECT fails to parse rather than writing.
+    pub fn query_sql(&self, sql: &str) -> Result<(Vec<String>, Vec<Vec<String>>)> {
+        let sql = sql.trim().trim_end_matches(';').trim_end();
+        let keyword = sql.split(|c: char| !c.is_ascii_alphabetic()).next().unwrap_or_default();
+        let read_only = ["SELECT", "WITH"].iter().any(|k| keyword.eq_ignore_ascii_case(k));
+        anyhow::ensure!(
+            read_only && !sql.contains(';'),
+            "Only a single read-only query (SELECT/WITH) is allowed"
+        );
+        let conn = self.conn();
+        let mut stmt = conn
+            .prepare(&format!("SELECT COLUMNS(*)::VARCHAR FROM ({}) AS q", sql))
+            .context("Only read-only queries (SELECT/WITH) are allowed")?;
+        let mut rows = stmt.query([])?;
+        let headers = rows.as_ref().map(|s| s.column_names()).unwrap_or_default();
+
+        let mut out = Vec::new();
+        while let Some(r) = rows.next()? {
+            let mut cells = Vec::with_capacity(headers.len());
+            for i in 0..headers.len() {
+                let v: Option<String> = r.get(i)?;
+                cells.push(v.unwrap_or_else(|| "—".to_string()));
+            }
+            out.push(cells);
+        }
+        Ok((headers, out))
+    }
+
+    // ── Health ────────────────────────────────────────────────────────────────
+
+    /// One-call summary for monitoring/alerting.
+    pub fn health_snapshot(&self) -> Result<HealthSnapshot> {
+        let (bar_min_date, bar_max_date) = self.date_range()?;
+        let (fx_min_date, fx_max_date) = self.fx_date_range()?;
+        let (tickers, equity_bars, fx_rates) =
+            (self.ticker_count()?, self.bar_count()?, self.fx_count()?);
+
+        let conn = self.conn();
+        let stale_symbols: i64 = conn.query_row(
+            r#"SELECT COUNT(*) FROM (
+                   SELECT symbol FROM daily_bars
+                   GROUP BY symbol
+                   HAVING MAX(date) < (SELECT MAX(date) FROM daily_bars) - ?::INTEGER
+               )"#,
+            params![STALE_AFTER_DAYS],
+            |r| r.get(0),
         )?;
-        let id: i64 = conn.query_row("SELECT last_insert_rowid()", [], |r| r.get(0))?;
+        let fx_pairs: Vec<String> = conn
+            .prepare("SELECT DISTINCT pair FROM fx_rates ORDER BY pair")?
+            .query_map([], |r| r.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        let last_run: Option<(String, chrono::NaiveDateTime, Option<i64>)> = conn
+            .prepare(
+                r#"SELECT status, started_at, date_diff('second', started_at, finished_at)
+                   FROM scrape_runs ORDER BY id DESC LIMIT 1"#,
+            )?
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?
+            .next()
+            .transpose()?;
+
+        Ok(HealthSnapshot {
+            tickers,
+            equity_bars,
+            bar_min_date,
+            bar_max_date,
+            stale_symbols,
+            fx_rates,
+            fx_min_date,
+            fx_max_date,
+            fx_pairs,
+            last_run_status: last_run.as_ref().map(|r| r.0.clone()),
+            last_run_started_at: last_run.as_ref().map(|r| r.1),
+            last_run_duration_secs: last_run.and_then(|r| r.2),
+        })
+    }
+
+    // ── Verification ──────────────────────────────────────────────────────────
+
+    /// Every violation of the invariants in `VERIFY_CHECKS`; empty when the
+    /// database is consistent.
+    pub fn verify(&self) -> Result<Vec<VerifyIssue>> {
+        let conn = self.conn();
+        let mut issues = Vec::new();
+        for &(check, table, sql) in VERIFY_CHECKS {
+            let rows = conn
+                .prepare(sql)?
+                .query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?)))?
+                .collect::<Result<Vec<_>, _>>()
+                .with_context(|| format!("verify check {} on {}", check, table))?;
+            issues.extend(rows.into_iter().map(|(key, detail)| VerifyIssue {
+                check: check.to_string(),
+                table_name: table.to_string(),
+                key,
+                detail,
+            }));
+        }
+        Ok(issues)
+    }
+
+    // ── Scrape runs ───────────────────────────────────────────────────────────
+
+    /// Record a run as started. Dry runs are not recorded and get id 0.
+    pub fn begin_scrape_run(&self) -> Result<i64> {
+        if self.skip_write("begin_scrape_run", 1) {
+            return Ok(0);
+        }
+        let id: i64 = self.with_retry(|conn| {
+            conn.execute(
+                "INSERT INTO scrape_runs (started_at, status) VALUES (?, 'running')",
+                params![Utc::now().naive_utc()],
+            )?;
+            Ok(conn.query_row("SELECT last_insert_rowid()", [], |r| r.get(0))?)
+        })?;
+        *self.current_run.lock().unwrap() = Some(id);
         Ok(id)
     }
 
@@ -293,21 +2261,1320 @@ impl Repository {
         bars: usize,
         error: Option<&str>,
     ) -> Result<()> {
-        let conn = self.conn();
-        conn.execute(
-            r#"UPDATE scrape_runs SET
-               finished_at = ?, status = ?,
-               tickers_processed = ?, bars_inserted = ?, error_msg = ?
-               WHERE id = ?"#,
-            params![
-                Utc::now().naive_utc(),
-                if error.is_none() { "success" } else { "error" },
-                tickers as i64,
-                bars as i64,
-                error,
-                run_id,
-            ],
-        )?;
+        if self.skip_write("finish_scrape_run", 1) {
+            return Ok(());
+        }
+        self.with_retry(|conn| {
+            conn.execute(
+                r#"UPDATE scrape_runs SET
+                   finished_at = ?, status = ?,
+                   tickers_processed = ?, bars_inserted = ?, error_msg = ?
+                   WHERE id = ?"#,
+                params![
+                    Utc::now().naive_utc(),
+                    if error.is_none() { "success" } else { "error" },
+                    tickers as i64,
+                    bars as i64,
+                    error,
+                    run_id,
+                ],
+            )?;
+            Ok(())
+        })?;
+        *self.current_run.lock().unwrap() = None;
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// The `limit` most recent scrape runs, newest first.
+    pub fn recent_runs(&self, limit: usize) -> Result<Vec<ScrapeRun>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM scrape_runs ORDER BY id DESC LIMIT ?",
+            SCRAPE_RUN_COLUMNS
+        ))?;
+        let rows = stmt
+            .query_map(params![limit as i64], scrape_run_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// The most recent run that finished with status `success`, if any.
+    pub fn last_successful_run(&self) -> Result<Option<ScrapeRun>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM scrape_runs WHERE status = 'success' ORDER BY id DESC LIMIT 1",
+            SCRAPE_RUN_COLUMNS
+        ))?;
+        let mut rows = stmt.query([])?;
+        match rows.next()? {
+            Some(r) => Ok(Some(scrape_run_from_row(r)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scraper::parsers::TickerMeta;
+    use chrono::{NaiveDate, Timelike};
+
+    fn repo() -> Repository {
+        let repo = Repository::open_in_memory().unwrap();
+        repo.run_migrations().unwrap();
+        repo
+    }
+
+    fn bar(symbol: &str, date: &str, close: f64) -> DailyBar {
+        DailyBar {
+            symbol: symbol.to_string(),
+            exchange: "NGX".to_string(),
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            open: None,
+            high: None,
+            low: None,
+            close,
+            change_pct: None,
+            volume: None,
+            scraped_at: Utc::now().naive_utc(),
+        }
+    }
+
+    #[test]
+    fn test_ticker_round_trip_keeps_isin_and_board() {
+        let repo = repo();
+        let ticker = Ticker {
+            symbol: "DANGCEM".to_string(),
+            name: Some("Dangote Cement Plc".to_string()),
+            sector: Some("Industrial Goods".to_string()),
+            industry: None,
+            exchange: Some("NGX".to_string()),
+            isin: Some("NGDANGCEM008".to_string()),
+            board: Some("Premium".to_string()),
+            status: Some(TickerStatus::Active),
+            scraped_at: Utc::now().naive_utc().with_nanosecond(0).unwrap(),
+        };
+        repo.upsert_tickers(std::slice::from_ref(&ticker)).unwrap();
+
+        assert_eq!(repo.get_ticker("DANGCEM").unwrap(), Some(ticker));
+        assert_eq!(repo.get_ticker("NOPE").unwrap(), None);
+    }
+
+    #[test]
+    fn test_ticker_without_a_name_keeps_the_stored_name() {
+        let repo = repo();
+        let now = Utc::now().naive_utc();
+        let page = TickerMeta { board: Some("Premium".to_string()), ..Default::default() };
+        repo.upsert_tickers(&[page.into_ticker("GTCO", "NGX", now)]).unwrap();
+        assert_eq!(repo.get_ticker("GTCO").unwrap().unwrap().name, None);
+
+        let listed = TickerMeta {
+            name: Some("Guaranty Trust Holding Company Plc".to_string()),
+            ..Default::default()
+        };
+        repo.upsert_tickers(&[listed.into_ticker("GTCO", "NGX", now)]).unwrap();
+        let page =
+            TickerMeta { sector: Some("Financial Services".to_string()), ..Default::default() };
+        repo.upsert_tickers(&[page.into_ticker("GTCO", "NGX", now)]).unwrap();
+
+        let stored = repo.get_ticker("GTCO").unwrap().unwrap();
+        assert_eq!(stored.name.as_deref(), Some("Guaranty Trust Holding Company Plc"));
+        assert_eq!(stored.sector.as_deref(), Some("Financial Services"));
+        assert_eq!(stored.board.as_deref(), Some("Premium"));
+    }
+
+    #[test]
+    fn test_latest_date_for_symbol() {
+        let repo = repo();
+        assert_eq!(repo.latest_date_for_symbol("GTCO", None).unwrap(), None);
+
+        repo.upsert_daily_bars(&[bar("GTCO", "2024-01-02", 40.0), bar("GTCO", "2024-01-03", 41.0)])
+            .unwrap();
+        assert_eq!(
+            repo.latest_date_for_symbol("GTCO", None).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 1, 3)
+        );
+
+        // No schema: a query error, not "no data".
+        let bare = Repository::open_in_memory().unwrap();
+        assert!(bare.latest_date_for_symbol("GTCO", None).is_err());
+    }
+
+    #[test]
+    fn test_symbol_coverage_includes_tickers_without_bars() {
+        let repo = repo();
+        repo.upsert_daily_bars(&[
+            bar("GTCO", "2024-01-02", 40.0),
+            bar("GTCO", "2024-01-03", 41.0),
+            bar("DANGCEM", "2024-01-03", 300.0),
+            bar("ACCESSCORP", "2024-01-02", 20.0),
+        ])
+        .unwrap();
+        repo.upsert_tickers(&[Ticker {
+            symbol: "ZENITHBANK".to_string(),
+            name: Some("Zenith Bank Plc".to_string()),
+            sector: None,
+            industry: None,
+            exchange: Some("NGX".to_string()),
+            isin: None,
+            board: None,
+            status: None,
+            scraped_at: Utc::now().naive_utc(),
+        }])
+        .unwrap();
+
+        let rows = repo.symbol_coverage(None, false).unwrap();
+        let summary: Vec<(&str, i64)> = rows.iter().map(|r| (r.symbol.as_str(), r.bars)).collect();
+        // Most bars first; equal counts fall back to the symbol.
+        assert_eq!(
+            summary,
+            [("GTCO", 2), ("ACCESSCORP", 1), ("DANGCEM", 1), ("ZENITHBANK", 0)]
+        );
+        assert_eq!(rows[3].first_date, None);
+    }
+
+    #[test]
+    fn test_market_breadth_counts_change_pct_signs() {
+        let repo = repo();
+        let with_change = |symbol: &str, change_pct: Option<f64>| DailyBar {
+            change_pct,
+            ..bar(symbol, "2024-01-03", 10.0)
+        };
+        repo.upsert_daily_bars(&[
+            with_change("GTCO", Some(1.5)),
+            with_change("MTNN", Some(-0.4)),
+            with_change("ZENITHBANK", Some(2.0)),
+            with_change("UBA", Some(0.0)),
+            with_change("FBNH", None),
+        ])
+        .unwrap();
+
+        let breadth = repo.market_breadth("2024-01-03".parse().unwrap(), None, false).unwrap();
+        assert_eq!((breadth.advancers, breadth.decliners, breadth.unchanged), (2, 1, 1));
+        let empty = repo.market_breadth("2024-01-04".parse().unwrap(), None, false).unwrap();
+        assert_eq!((empty.advancers, empty.decliners, empty.unchanged), (0, 0, 0));
+
+        repo.upsert_tickers(&[Ticker {
+            symbol: "ZENITHBANK".to_string(),
+            name: None,
+            sector: None,
+            industry: None,
+            exchange: Some("NGX".to_string()),
+            isin: None,
+            board: None,
+            status: Some(TickerStatus::Suspended),
+            scraped_at: Utc::now().naive_utc(),
+        }])
+        .unwrap();
+        let active = repo.market_breadth("2024-01-03".parse().unwrap(), None, true).unwrap();
+        assert_eq!((active.advancers, active.decliners, active.unchanged), (1, 1, 1));
+        assert_eq!(repo.inactive_symbols().unwrap(), ["ZENITHBANK"]);
+    }
+
+    #[test]
+    fn test_stale_symbols_by_last_scrape() {
+        let repo = repo();
+        let scraped = |symbol: &str, date: &str, hours_ago: i64| DailyBar {
+            scraped_at: Utc::now().naive_utc() - chrono::Duration::hours(hours_ago),
+            ..bar(symbol, date, 10.0)
+        };
+        repo.upsert_daily_bars(&[
+            scraped("GTCO", "2024-01-02", 100),
+            scraped("GTCO", "2024-01-03", 2),
+            scraped("MTNN", "2024-01-02", 90),
+            scraped("UBA", "2024-01-02", 200),
+        ])
+        .unwrap();
+
+        let stale = repo.stale_symbols(48).unwrap();
+        let symbols: Vec<&str> = stale.iter().map(|(s, _)| s.as_str()).collect();
+        assert_eq!(symbols, ["UBA", "MTNN"]);
+        assert!(repo.stale_symbols(1_000).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_for_each_bar_visits_every_bar() {
+        let repo = repo();
+        repo.upsert_daily_bars(&[
+            bar("DANGCEM", "2024-01-02", 300.0),
+            bar("DANGCEM", "2024-01-03", 305.0),
+            bar("GTCO", "2024-01-02", 40.0),
+        ])
+        .unwrap();
+
+        let mut calls = 0i64;
+        let visited = repo
+            .for_each_bar(None, |_| {
+                calls += 1;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(calls, repo.bar_count().unwrap());
+        assert_eq!(visited as i64, calls);
+
+        let mut gtco = Vec::new();
+        repo.for_each_bar(Some("GTCO"), |b| {
+            gtco.push(b);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(gtco.len(), 1);
+        assert_eq!(gtco[0].close, 40.0);
+    }
+
+    #[test]
+    fn test_bulk_load_equity_csv_cleans_investing_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("DANGCEM_historical.csv");
+        std::fs::write(
+            &path,
+            "\"Date\",\"Price\",\"Open\",\"High\",\"Low\",\"Vol.\",\"Change %\"\n\
+             \"01/03/2024\",\"1,305.00\",\"1,300.00\",\
+             \"1,310.00\",\"1,295.00\",\"1.2345K\",\"0.38%\"\n\
+             \"12/29/2023\",\"1,300.00\",\"1,290.00\",\
+             \"1,300.00\",\"1,290.00\",\"850K\",\"-0.10%\"\n\
+             \"12/28/2023\",\"1,295.00\",\"1,290.00\",\"1,280.00\",\"1,300.00\",\"1K\",\"0%\"\n\
+             \"01/02/2099\",\"1,310.00\",\"\",\"\",\"\",\"\",\"\"\n\
+             \"bad\",\"x\",\"\",\"\",\"\",\"\",\"\"\n",
+        )
+        .unwrap();
+
+        // "12/29/2023" settles the column as month-first, as in the Rust loader
+        let opts = LoaderConfig::default();
+        let order = crate::loader::fast_load_date_order(&path, &opts).unwrap();
+        assert_eq!(order, Some(DateOrder::MonthFirst));
+        let repo = repo();
+        let n = repo.bulk_load_equity_csv(&path, "DANGCEM", "NGX", &opts, order).unwrap();
+        // The OHLC-inconsistent and future-dated rows are dropped, as in the Rust loader
+        assert_eq!(n, 2);
+
+        let last = repo.last_bar("DANGCEM").unwrap().unwrap();
+        assert_eq!(last.date, NaiveDate::from_ymd_opt(2024, 1, 3).unwrap());
+        assert_eq!(last.close, 1305.0);
+        assert_eq!(last.volume, Some(1_234));
+        assert_eq!(last.change_pct, Some(0.38));
+    }
+
+    #[test]
+    fn test_bulk_load_day_first_dates_match_the_rust_loader() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("GTCO_historical.csv");
+        std::fs::write(
+            &path,
+            "Date,Price,Open,High,Low,Vol.,Change %\n\
+             25/03/2024,41.00,40.00,41.50,39.50,1.1M,2.50%\n\
+             01/03/2024,40.00,39.00,40.50,38.50,900K,0.00%\n",
+        )
+        .unwrap();
+
+        let opts = LoaderConfig::default();
+        let order = crate::loader::fast_load_date_order(&path, &opts).unwrap();
+        let repo = repo();
+        repo.bulk_load_equity_csv(&path, "GTCO", "NGX", &opts, order).unwrap();
+        let rust_path = crate::loader::load_equity_csv(&path, "NGX", &opts).unwrap();
+
+        let stored: Vec<NaiveDate> = repo
+            .bars_in_range("GTCO", None, DateRange::default(), None)
+            .unwrap()
+            .iter()
+            .map(|b| b.date)
+            .collect();
+        let mut loaded: Vec<NaiveDate> = rust_path.bars.iter().map(|b| b.date).collect();
+        loaded.sort();
+        assert_eq!(stored, loaded);
+        assert_eq!(stored[0], NaiveDate::from_ymd_opt(2024, 3, 1).unwrap());
+    }
+
+    #[test]
+    fn test_infer_suspended_flags_unchanged_closes() {
+        let repo = repo();
+        let tickers: Vec<Ticker> = ["FROZEN", "MOVING", "SHORT"]
+            .iter()
+            .map(|s| Ticker {
+                symbol: s.to_string(),
+                name: None,
+                sector: None,
+                industry: None,
+                exchange: Some("NGX".to_string()),
+                isin: None,
+                board: None,
+                status: None,
+                scraped_at: Utc::now().naive_utc(),
+            })
+            .collect();
+        repo.upsert_tickers(&tickers).unwrap();
+
+        let mut bars = vec![bar("FROZEN", "2024-01-01", 9.0)];
+        for day in 2..=6 {
+            let date = format!("2024-01-{:02}", day);
+            bars.push(bar("FROZEN", &date, 10.0));
+            bars.push(bar("MOVING", &date, 10.0 + day as f64));
+        }
+        bars.push(bar("SHORT", "2024-01-05", 5.0));
+        bars.push(bar("SHORT", "2024-01-06", 5.0));
+        repo.upsert_daily_bars(&bars).unwrap();
+
+        // A dry run reports the candidate but leaves it active.
+        let repo = repo.with_dry_run(true);
+        assert_eq!(repo.infer_suspended(5).unwrap(), vec!["FROZEN".to_string()]);
+        assert_eq!(repo.get_ticker("FROZEN").unwrap().unwrap().status, None);
+
+        let repo = repo.with_dry_run(false);
+        assert_eq!(repo.infer_suspended(5).unwrap(), vec!["FROZEN".to_string()]);
+        let frozen = repo.get_ticker("FROZEN").unwrap().unwrap();
+        assert_eq!(frozen.status, Some(TickerStatus::Suspended));
+
+        // Already flagged: not reported again; excluded from active-only listings.
+        assert!(repo.infer_suspended(5).unwrap().is_empty());
+        assert_eq!(repo.list_symbols(None, true).unwrap(), vec!["MOVING", "SHORT"]);
+        assert_eq!(repo.list_symbols(None, false).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_verify_reports_broken_invariants() {
+        let repo = repo();
+        assert!(repo.verify().unwrap().is_empty());
+
+        let mut inverted = bar("GTCO", "2024-01-02", 40.0);
+        (inverted.high, inverted.low) = (Some(39.0), Some(41.0));
+        let mut negative = bar("GTCO", "2024-01-03", 40.0);
+        negative.volume = Some(-5);
+        repo.upsert_daily_bars(&[inverted, negative]).unwrap();
+
+        let issues = repo.verify().unwrap();
+        let checks: Vec<&str> = issues.iter().map(|i| i.check.as_str()).collect();
+        assert_eq!(checks, ["unknown_symbol", "high_below_low", "negative_volume"]);
+        assert_eq!(issues[0].key, "GTCO");
+        assert_eq!(issues[1].key, "GTCO 2024-01-02");
+    }
+
+    #[test]
+    fn test_recent_runs_newest_first_with_duration() {
+        let repo = repo();
+        assert!(repo.last_successful_run().unwrap().is_none());
+        let first = repo.begin_scrape_run().unwrap();
+        repo.finish_scrape_run(first, 10, 250, None).unwrap();
+        let second = repo.begin_scrape_run().unwrap();
+        repo.finish_scrape_run(second, 3, 0, Some("listing timed out")).unwrap();
+        repo.begin_scrape_run().unwrap();
+
+        let runs = repo.recent_runs(2).unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].status, "running");
+        assert_eq!(runs[0].finished_at, None);
+        assert_eq!(runs[0].duration_secs, None);
+        assert_eq!(runs[1].id, second);
+        assert_eq!(runs[1].status, "error");
+        assert_eq!(runs[1].error_msg.as_deref(), Some("listing timed out"));
+        assert!(runs[1].duration_secs.is_some_and(|d| d >= 0));
+        assert_eq!(repo.recent_runs(10).unwrap()[2].bars_inserted, 250);
+        assert_eq!(repo.last_successful_run().unwrap().map(|r| r.id), Some(first));
+    }
+
+    #[test]
+    fn test_migrations_upgrade_a_v1_database() {
+        // A database as first shipped, holding data, with only version 1
+        // recorded, and the indexes and a view that block ALTER/DROP on its
+        // tables until they are dropped.
+        let repo = Repository::open_in_memory().unwrap();
+        {
+            let conn = repo.conn();
+            conn.execute_batch(BASELINE_DDL).unwrap();
+            conn.execute_batch(SCHEMA_VERSION_TABLE).unwrap();
+            conn.execute_batch(
+                r#"CREATE INDEX idx_bars_date   ON daily_bars (date);
+                   CREATE INDEX idx_bars_symbol ON daily_bars (symbol);
+                   CREATE INDEX idx_fx_date     ON fx_rates (date);
+                   CREATE INDEX idx_fx_pair     ON fx_rates (pair);
+                   CREATE VIEW bars_usd AS
+                   SELECT b.symbol, b.date, b.close AS close_ngn, f.close AS fx,
+                          b.close / f.close AS close_usd
+                   FROM daily_bars b
+                   LEFT JOIN fx_rates f ON f.pair = 'USDNGN' AND f.date = b.date;
+                   INSERT INTO daily_bars (symbol, date, close, scraped_at)
+                       VALUES ('GTCO', '2024-03-04', 40.0, now());
+                   INSERT INTO fx_rates (pair, date, close, scraped_at)
+                       VALUES ('USDNGN', '2024-03-04', 1600.0, now());
+                   INSERT INTO schema_version VALUES (1, now());"#,
+            )
+            .unwrap();
+        }
+
+        repo.run_migrations().unwrap();
+        assert_eq!(repo.schema_version().unwrap(), MIGRATIONS.last().unwrap().0);
+        repo.conn().execute("SELECT isin, board, status FROM tickers", []).unwrap();
+        repo.conn().execute("SELECT * FROM corporate_actions", []).unwrap();
+        let bars = repo.bars_in_range("GTCO", None, DateRange::default(), None).unwrap();
+        assert_eq!(bars[0].exchange, "NGX");
+        let close_usd: f64 = repo
+            .conn()
+            .query_row("SELECT close_usd FROM bars_usd WHERE exchange = 'NGX'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(close_usd, 40.0 / 1600.0);
+
+        // Old rates are kept under 'unknown' and a second source can join them.
+        repo.upsert_fx_rates(&[FxRate {
+            pair: "USDNGN".to_string(),
+            date: "2024-03-04".parse().unwrap(),
+            open: None,
+            high: None,
+            low: None,
+            close: 1590.0,
+            change_pct: None,
+            source: Some("cbn".to_string()),
+            scraped_at: Utc::now().naive_utc(),
+        }])
+        .unwrap();
+        assert_eq!(repo.fx_count().unwrap(), 2);
+
+        // Re-running is a no-op.
+        repo.run_migrations().unwrap();
+        let applied: i64 = repo
+            .conn()
+            .query_row("SELECT COUNT(*) FROM schema_version", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(applied, MIGRATIONS.len() as i64);
+    }
+
+    #[test]
+    fn test_same_symbol_on_two_exchanges_keeps_both() {
+        let repo = repo();
+        let gse = |close| DailyBar {
+            exchange: "GSE".to_string(),
+            ..bar("MTNN", "2024-03-04", close)
+        };
+        repo.upsert_daily_bars(&[bar("MTNN", "2024-03-04", 210.0), gse(2.5)]).unwrap();
+        // Re-scraping one exchange updates its row and leaves the other alone
+        repo.upsert_daily_bars(&[gse(2.6)]).unwrap();
+        assert_eq!(repo.bar_count().unwrap(), 2);
+
+        let all = DateRange::default();
+        let closes = |exchange| -> Vec<f64> {
+            let bars = repo.bars_in_range("MTNN", exchange, all, None).unwrap();
+            bars.iter().map(|b| b.close).collect()
+        };
+        assert_eq!(closes(Some("NGX")), [210.0]);
+        assert_eq!(closes(Some("GSE")), [2.6]);
+        assert_eq!(closes(None).len(), 2);
+        assert!(repo.verify().unwrap().iter().all(|i| i.check != "duplicate_key"));
+    }
+
+    #[test]
+    fn test_fx_source_migration_rekeys_existing_rates() {
+        // fx_rates as it was before sources were part of the key
+        let repo = repo();
+        repo.conn()
+            .execute_batch(
+                r#"DROP TABLE fx_rates;
+                   CREATE TABLE fx_rates (
+                       pair VARCHAR NOT NULL, date DATE NOT NULL, open DOUBLE, high DOUBLE,
+                       low DOUBLE, close DOUBLE NOT NULL, change_pct DOUBLE, source VARCHAR,
+                       scraped_at TIMESTAMP NOT NULL, PRIMARY KEY (pair, date)
+                   );
+                   INSERT INTO fx_rates VALUES
+                       ('USDNGN', '2024-03-04', NULL, NULL, NULL, 1600.0, NULL, NULL, now());
+                   DELETE FROM schema_version WHERE version >= 5;"#,
+            )
+            .unwrap();
+
+        repo.run_migrations().unwrap();
+        let source: String = repo
+            .conn()
+            .query_row("SELECT source FROM fx_rates WHERE pair = 'USDNGN'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(source, UNKNOWN_FX_SOURCE);
+
+        // A second source for the same day now lands beside the first.
+        repo.upsert_fx_rates(&[FxRate {
+            pair: "USDNGN".to_string(),
+            date: "2024-03-04".parse().unwrap(),
+            open: None,
+            high: None,
+            low: None,
+            close: 1590.0,
+            change_pct: None,
+            source: Some("cbn".to_string()),
+            scraped_at: Utc::now().naive_utc(),
+        }])
+        .unwrap();
+        assert_eq!(repo.fx_count().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_upserts_write_one_audit_row_per_batch() {
+        let audited = Repository::open_in_memory().unwrap().with_audit(true);
+        audited.run_migrations().unwrap();
+        audited
+            .upsert_daily_bars(&[
+                bar("DANGCEM", "2024-01-02", 300.0),
+                bar("DANGCEM", "2024-01-03", 305.0),
+            ])
+            .unwrap();
+        audited
+            .upsert_daily_bars(&[bar("GTCO", "2024-01-02", 40.0), bar("MTNN", "2024-01-02", 200.0)])
+            .unwrap();
+
+        let log = audited.recent_audit(10).unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].row_key.as_deref(), Some("2 keys"));
+        assert_eq!(log[1].operation, "upsert_daily_bars");
+        assert_eq!(log[1].row_key.as_deref(), Some("DANGCEM"));
+        assert_eq!(log[1].row_count, 2);
+
+        // Off by default.
+        let quiet = repo();
+        quiet.upsert_daily_bars(&[bar("GTCO", "2024-01-02", 40.0)]).unwrap();
+        assert!(quiet.recent_audit(10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rename_symbol_moves_bars() {
+        let repo = repo();
+        repo.upsert_daily_bars(&[
+            bar("DANGCME", "2024-01-02", 300.0),
+            bar("DANGCME", "2024-01-03", 305.0),
+        ])
+        .unwrap();
+
+        let report = repo.rename_symbol("DANGCME", "DANGCEM").unwrap();
+        assert_eq!(report.bars_moved, 2);
+        assert_eq!(report.bars_merged, 0);
+        assert!(!report.ticker_moved);
+        assert!(repo.last_bar("DANGCME").unwrap().is_none());
+        assert_eq!(repo.last_bar("DANGCEM").unwrap().unwrap().close, 305.0);
+
+        assert!(repo.rename_symbol("DANGCME", "DANGCEM").is_err());
+    }
+
+    #[test]
+    fn test_delete_symbol_removes_bars_and_ticker() {
+        let repo = repo();
+        repo.upsert_daily_bars(&[
+            bar("OANDO", "2024-01-02", 10.0),
+            bar("OANDO", "2024-01-03", 11.0),
+            bar("GTCO", "2024-01-02", 40.0),
+        ])
+        .unwrap();
+        repo.upsert_tickers(&[Ticker {
+            symbol: "OANDO".to_string(),
+            name: Some("Oando Plc".to_string()),
+            sector: None,
+            industry: None,
+            exchange: Some("NGX".to_string()),
+            isin: None,
+            board: None,
+            status: Some(TickerStatus::Delisted),
+            scraped_at: Utc::now().naive_utc(),
+        }])
+        .unwrap();
+
+        assert_eq!(repo.delete_symbol("OANDO").unwrap(), 3);
+        assert!(repo.last_bar("OANDO").unwrap().is_none());
+        assert_eq!(repo.get_ticker("OANDO").unwrap(), None);
+        assert!(repo.last_bar("GTCO").unwrap().is_some());
+        assert_eq!(repo.delete_symbol("OANDO").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_prune_before_drops_older_bars_and_rates() {
+        let repo = repo();
+        repo.upsert_daily_bars(&[
+            bar("GTCO", "2019-12-31", 30.0),
+            bar("GTCO", "2020-01-02", 31.0),
+            bar("MTNN", "2018-06-01", 90.0),
+        ])
+        .unwrap();
+        let rate = |date: &str| FxRate {
+            pair: "USDNGN".to_string(),
+            date: date.parse().unwrap(),
+            open: None,
+            high: None,
+            low: None,
+            close: 360.0,
+            change_pct: None,
+            source: None,
+            scraped_at: Utc::now().naive_utc(),
+        };
+        repo.upsert_fx_rates(&[rate("2019-05-01"), rate("2020-05-01")]).unwrap();
+
+        let cutoff = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+        assert_eq!(repo.prune_before(cutoff).unwrap(), 3);
+        assert_eq!(repo.bar_count().unwrap(), 1);
+        assert_eq!(repo.fx_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_rename_symbol_merges_into_existing() {
+        let repo = repo();
+        let mut existing = bar("GTCO", "2024-01-02", 40.0);
+        existing.volume = Some(1_000);
+        let mut typo = bar("GTC0", "2024-01-02", 41.0);
+        typo.volume = None;
+        repo.upsert_daily_bars(&[existing, typo, bar("GTC0", "2024-01-03", 42.0)])
+            .unwrap();
+
+        let report = repo.rename_symbol("GTC0", "GTCO").unwrap();
+        assert_eq!(report.bars_moved, 2);
+        assert_eq!(report.bars_merged, 1);
+
+        let mut bars = Vec::new();
+        repo.for_each_bar(Some("GTCO"), |b| {
+            bars.push(b);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0].close, 41.0);
+        assert_eq!(bars[0].volume, Some(1_000));
+        assert_eq!(repo.bar_count().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_rename_preview_matches_rename_across_exchanges() {
+        let repo = repo();
+        let gse = DailyBar { exchange: "GSE".to_string(), ..bar("GTC0", "2024-01-02", 2.0) };
+        // Only the NGX bar lands on a stored (symbol, exchange, date)
+        repo.upsert_daily_bars(&[
+            bar("GTCO", "2024-01-02", 40.0),
+            bar("GTC0", "2024-01-02", 41.0),
+            gse,
+        ])
+        .unwrap();
+
+        let preview = repo.rename_preview("GTC0", "GTCO").unwrap();
+        assert_eq!((preview.bars_moved, preview.bars_merged), (2, 1));
+        let report = repo.rename_symbol("GTC0", "GTCO").unwrap();
+        assert_eq!((report.bars_moved, report.bars_merged), (2, 1));
+        assert_eq!(repo.bar_count().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_health_snapshot_counts_stale_symbols() {
+        let repo = repo();
+        repo.upsert_daily_bars(&[
+            bar("DANGCEM", "2024-03-01", 300.0),
+            bar("GTCO", "2024-03-01", 40.0),
+            bar("OANDO", "2024-01-15", 10.0),
+        ])
+        .unwrap();
+
+        let health = repo.health_snapshot().unwrap();
+        assert_eq!(health.equity_bars, 3);
+        assert_eq!(health.stale_symbols, 1);
+        assert_eq!(health.bar_max_date, NaiveDate::from_ymd_opt(2024, 3, 1));
+        assert!(health.fx_pairs.is_empty());
+        assert_eq!(health.last_run_status, None);
+    }
+
+    #[test]
+    fn test_rebased_series_needs_common_dates() {
+        let repo = repo();
+        repo.upsert_daily_bars(&[
+            bar("DANGCEM", "2024-01-02", 300.0),
+            bar("DANGCEM", "2024-01-03", 330.0),
+            bar("GTCO", "2024-01-03", 40.0),
+            bar("MTNN", "2024-02-01", 200.0),
+        ])
+        .unwrap();
+
+        let both = ["DANGCEM".to_string(), "GTCO".to_string()];
+        let rebased = repo.rebased_series(&both, None, None).unwrap();
+        assert_eq!(rebased.dates.len(), 1);
+        assert_eq!(rebased.closes, vec![vec![100.0], vec![100.0]]);
+
+        let disjoint = ["DANGCEM".to_string(), "MTNN".to_string()];
+        assert!(repo.rebased_series(&disjoint, None, None).is_err());
+    }
+
+    #[test]
+    fn test_detect_possible_splits_skips_reported_moves() {
+        let repo = repo();
+        let mut crash = bar("OANDO", "2024-01-03", 5.0);
+        crash.change_pct = Some(-50.0);
+        repo.upsert_daily_bars(&[
+            bar("DANGCEM", "2024-01-02", 600.0),
+            bar("DANGCEM", "2024-01-03", 301.0),
+            bar("DANGCEM", "2024-01-04", 305.0),
+            bar("OANDO", "2024-01-02", 10.0),
+            crash,
+        ])
+        .unwrap();
+
+        let found = repo.detect_possible_splits(None, false, DateRange::default(), 3.0).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, "DANGCEM");
+        assert_eq!(found[0].1, NaiveDate::from_ymd_opt(2024, 1, 3).unwrap());
+        assert_eq!(found[0].2, 2.0);
+    }
+
+    #[test]
+    fn test_replace_overwrites_instead_of_coalescing() {
+        let repo = repo();
+        let mut old = bar("GTCO", "2024-01-02", 39.5);
+        old.volume = Some(1_000);
+        repo.upsert_daily_bars(&[
+            bar("GTCO", "2024-01-01", 39.0),
+            old,
+            bar("GTCO", "2024-01-03", 40.0),
+            bar("GTCO", "2024-01-04", 40.5),
+            bar("GTCO", "2024-01-05", 42.0),
+        ])
+        .unwrap();
+
+        // Corrected history for Jan 2–4: Jan 2 fixed with no volume, Jan 3 gone.
+        let mut fixed = bar("GTCO", "2024-01-02", 41.0);
+        fixed.volume = None;
+        repo.replace_daily_bars(&[fixed, bar("GTCO", "2024-01-04", 41.5)])
+            .unwrap();
+
+        let mut bars = Vec::new();
+        repo.for_each_bar(Some("GTCO"), |b| {
+            bars.push(b);
+            Ok(())
+        })
+        .unwrap();
+        let dates: Vec<String> = bars.iter().map(|b| b.date.to_string()).collect();
+        assert_eq!(dates, ["2024-01-01", "2024-01-02", "2024-01-04", "2024-01-05"]);
+        assert_eq!(bars[1].close, 41.0);
+        assert_eq!(bars[1].volume, None);
+    }
+
+    #[test]
+    fn test_open_retries_only_on_lock_conflicts() {
+        let path = Path::new("data/test.duckdb");
+        let locked = "IO Error: Could not set lock on file \"data/test.duckdb\": \
+                      Conflicting lock is held in ngx-etl (PID 4242)";
+
+        let mut attempts = 0;
+        let opened = open_with_retry(path, 3, Duration::ZERO, || {
+            attempts += 1;
+            if attempts < 3 { Err(locked) } else { Ok("conn") }
+        });
+        assert_eq!(opened.unwrap(), "conn");
+        assert_eq!(attempts, 3);
+
+        attempts = 0;
+        let err = open_with_retry(path, 2, Duration::ZERO, || {
+            attempts += 1;
+            Err::<(), _>(locked)
+        })
+        .unwrap_err();
+        assert_eq!(attempts, 3);
+        assert!(err.to_string().contains("locked by another process"));
+
+        attempts = 0;
+        let _ = open_with_retry(path, 5, Duration::ZERO, || {
+            attempts += 1;
+            Err::<(), _>("IO Error: Permission denied")
+        });
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn test_normalize_symbols_fixes_case_and_whitespace() {
+        let repo = repo();
+        repo.upsert_daily_bars(&[
+            bar("dangcem", "2024-01-02", 300.0),
+            bar("GTCO ", "2024-01-02", 40.5),
+            bar("GTCO", "2024-01-02", 40.0),
+            bar("GTCO", "2024-01-03", 41.0),
+        ])
+        .unwrap();
+
+        let repo = repo.with_dry_run(true);
+        let preview = repo.normalize_symbols().unwrap();
+        assert_eq!(preview.len(), 2);
+        assert_eq!(repo.bar_count().unwrap(), 4);
+        let gtco = preview.iter().find(|r| r.new == "GTCO").unwrap();
+        assert_eq!((gtco.bars_moved, gtco.bars_merged), (1, 1));
+
+        let repo = repo.with_dry_run(false);
+        repo.normalize_symbols().unwrap();
+        assert_eq!(repo.bar_count().unwrap(), 3);
+        assert_eq!(repo.last_bar("DANGCEM").unwrap().unwrap().close, 300.0);
+        assert!(repo.last_bar("dangcem").unwrap().is_none());
+        assert!(repo.last_bar("GTCO ").unwrap().is_none());
+        assert!(repo.normalize_symbols().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_bars_in_usd_forward_fills_rate() {
+        let repo = repo();
+        repo.upsert_daily_bars(&[
+            bar("MTNN", "2024-01-01", 200.0),
+            bar("MTNN", "2024-01-03", 220.0),
+            bar("MTNN", "2024-01-05", 242.0),
+        ])
+        .unwrap();
+        assert!(repo.bars_in_usd("MTNN", None).is_err());
+
+        let usdngn = |date: &str, close: f64| FxRate {
+            pair: "USDNGN".to_string(),
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            open: None,
+            high: None,
+            low: None,
+            close,
+            change_pct: None,
+            source: Some("cbn".to_string()),
+            scraped_at: Utc::now().naive_utc(),
+        };
+        repo.upsert_fx_rates(&[usdngn("2024-01-02", 1000.0), usdngn("2024-01-05", 1100.0)])
+            .unwrap();
+
+        // The 1st predates any rate; the 3rd reuses the 2nd's rate.
+        let usd = repo.bars_in_usd("MTNN", None).unwrap();
+        let d = |s| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        assert_eq!(usd, vec![(d("2024-01-03"), 0.22), (d("2024-01-05"), 0.22)]);
+    }
+
+    #[test]
+    fn test_real_returns_use_as_of_usdngn() {
+        let repo = repo();
+        repo.upsert_daily_bars(&[
+            bar("DANGCEM", "2024-01-02", 300.0),
+            bar("DANGCEM", "2024-01-03", 330.0),
+            bar("DANGCEM", "2024-01-04", 330.0),
+        ])
+        .unwrap();
+        assert!(repo.real_returns("DANGCEM", None, DateRange::default()).unwrap()[0]
+            .close_usd
+            .is_none());
+
+        let usdngn = |date: &str, close: f64| FxRate {
+            pair: "USDNGN".to_string(),
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            open: None,
+            high: None,
+            low: None,
+            close,
+            change_pct: None,
+            source: None,
+            scraped_at: Utc::now().naive_utc(),
+        };
+        // No rate on the 2nd or 4th: they take the latest earlier rate.
+        repo.upsert_fx_rates(&[usdngn("2024-01-01", 1000.0), usdngn("2024-01-03", 1100.0)])
+            .unwrap();
+
+        let rows = repo.real_returns("DANGCEM", None, DateRange::default()).unwrap();
+        let fx: Vec<_> = rows.iter().map(|r| r.fx).collect();
+        assert_eq!(fx, vec![Some(1000.0), Some(1100.0), Some(1100.0)]);
+        assert_eq!(rows[0].return_ngn_pct, None);
+        assert!((rows[1].return_ngn_pct.unwrap() - 10.0).abs() < 1e-9);
+        // +10% in naira, fully eaten by a 10% weaker naira.
+        assert!(rows[1].return_usd_pct.unwrap().abs() < 1e-9);
+        assert!(rows[2].return_usd_pct.unwrap().abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_bars_scraped_after_watermark() {
+        let repo = repo();
+        let mut old = bar("DANGCEM", "2024-01-02", 300.0);
+        old.scraped_at = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap().and_hms_opt(18, 0, 0).unwrap();
+        let mut new = bar("GTCO", "2024-01-03", 41.0);
+        new.scraped_at = old.scraped_at + chrono::Duration::days(1);
+        repo.upsert_daily_bars(&[old.clone(), new.clone()]).unwrap();
+
+        let tail = repo.recent_bars(None, 1).unwrap();
+        assert_eq!(tail.len(), 1);
+        assert_eq!(tail[0].symbol, "GTCO");
+
+        let fresh = repo.bars_scraped_after(None, old.scraped_at).unwrap();
+        assert_eq!(fresh.len(), 1);
+        assert_eq!(fresh[0].symbol, "GTCO");
+        assert!(repo.bars_scraped_after(None, new.scraped_at).unwrap().is_empty());
+        assert!(repo.bars_scraped_after(Some("DANGCEM"), old.scraped_at).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_with_retry_reruns_only_conflicts() {
+        let repo = repo().with_write_retries(2);
+        let attempts = std::cell::Cell::new(0);
+        let n = repo
+            .with_retry(|conn| {
+                attempts.set(attempts.get() + 1);
+                if attempts.get() < 3 {
+                    anyhow::bail!("TransactionContext Error: Failed to commit: write-write conflict");
+                }
+                Ok(conn.query_row("SELECT 42", [], |r| r.get::<_, i64>(0))?)
+            })
+            .unwrap();
+        assert_eq!((n, attempts.get()), (42, 3));
+
+        attempts.set(0);
+        let err = repo.with_retry(|_| -> Result<()> {
+            attempts.set(attempts.get() + 1);
+            anyhow::bail!("Constraint Error: NOT NULL constraint failed: daily_bars.close")
+        });
+        assert!(err.is_err());
+        assert_eq!(attempts.get(), 1);
+
+        attempts.set(0);
+        let err = repo.with_retry(|_| -> Result<()> {
+            attempts.set(attempts.get() + 1);
+            anyhow::bail!("TransactionContext Error: Conflict on tuple deletion!")
+        });
+        assert!(err.is_err());
+        assert_eq!(attempts.get(), 3);
+    }
+
+    #[test]
+    fn test_dry_run_counts_without_writing() {
+        let repo = repo().with_dry_run(true);
+        let n = repo
+            .upsert_daily_bars(&[bar("GTCO", "2024-01-02", 40.0), bar("GTCO", "2024-01-03", 42.0)])
+            .unwrap();
+        assert_eq!(n, 2);
+        let run = repo.begin_scrape_run().unwrap();
+        repo.finish_scrape_run(run, 1, n, None).unwrap();
+
+        assert_eq!(repo.bar_count().unwrap(), 0);
+        assert!(repo.recent_runs(10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_recompute_change_pct_fills_only_missing() {
+        let repo = repo();
+        let mut reported = bar("GTCO", "2024-01-04", 44.0);
+        reported.change_pct = Some(5.0);
+        repo.upsert_daily_bars(&[
+            bar("GTCO", "2024-01-02", 40.0),
+            bar("GTCO", "2024-01-03", 42.0),
+            reported,
+        ])
+        .unwrap();
+
+        assert_eq!(repo.recompute_change_pct().unwrap(), 1);
+        let bars = repo.bars_in_range("GTCO", None, DateRange::default(), None).unwrap();
+        assert_eq!(bars[0].change_pct, None);
+        assert!((bars[1].change_pct.unwrap() - 5.0).abs() < 1e-9);
+        assert_eq!(bars[2].change_pct, Some(5.0));
+    }
+
+    #[test]
+    fn test_recompute_change_pct_stays_within_an_exchange() {
+        let repo = repo();
+        let gse = |date, close| DailyBar {
+            exchange: "GSE".to_string(),
+            ..bar("MTNN", date, close)
+        };
+        repo.upsert_daily_bars(&[
+            bar("MTNN", "2024-01-02", 200.0),
+            gse("2024-01-02", 2.0),
+            bar("MTNN", "2024-01-03", 210.0),
+            gse("2024-01-03", 2.2),
+        ])
+        .unwrap();
+
+        assert_eq!(repo.recompute_change_pct().unwrap(), 2);
+        let pct = |exchange| {
+            let bars = repo.bars_in_range("MTNN", Some(exchange), DateRange::default(), None);
+            bars.unwrap()[1].change_pct.unwrap()
+        };
+        assert!((pct("NGX") - 5.0).abs() < 1e-9);
+        assert!((pct("GSE") - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_readonly_repo_reads_but_refuses_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ngx.duckdb");
+        {
+            let rw = Repository::open(&path, 0).unwrap();
+            rw.run_migrations().unwrap();
+            rw.upsert_daily_bars(&[bar("DANGCEM", "2024-01-02", 300.0)]).unwrap();
+        }
+
+        let ro = Repository::open_readonly(&path, 0).unwrap();
+        assert_eq!(ro.bar_count().unwrap(), 1);
+        let err = ro.upsert_daily_bars(&[bar("GTCO", "2024-01-02", 40.0)]).unwrap_err();
+        assert!(err.to_string().contains("read-only"), "{}", err);
+        for err in [
+            ro.rename_symbol("DANGCEM", "DANGOTE").unwrap_err(),
+            ro.infer_suspended(5).unwrap_err(),
+        ] {
+            assert!(err.to_string().contains("read-only"), "{}", err);
+        }
+        assert!(ro.run_migrations().is_err());
+    }
+
+    #[test]
+    fn test_fx_annualised_change_uses_as_of_endpoints() {
+        let repo = repo();
+        let rate = |date: &str, close: f64| FxRate {
+            pair: "USDNGN".to_string(),
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            open: None,
+            high: None,
+            low: None,
+            close,
+            change_pct: None,
+            source: None,
+            scraped_at: Utc::now().naive_utc(),
+        };
+        repo.upsert_fx_rates(&[rate("2023-01-02", 1000.0), rate("2023-12-29", 1500.0)])
+            .unwrap();
+
+        let carry = |pair: &str, from: &str, to: &str| {
+            let d = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+            repo.fx_annualised_change(pair, d(from), d(to)).unwrap()
+        };
+        // Both endpoints fall on non-quoting days and take the prior rate.
+        let change = carry("USDNGN", "2023-01-03", "2024-01-03").unwrap();
+        assert!((change - 0.5).abs() < 1e-9);
+        assert_eq!(carry("USDNGN", "2023-06-01", "2023-06-01"), None);
+        assert_eq!(carry("USDNGN", "2022-06-01", "2023-06-01"), None);
+        assert_eq!(carry("EURNGN", "2023-01-03", "2024-01-02"), None);
+    }
+
+    #[test]
+    fn test_fx_source_spread_pairs_sources_by_date() {
+        let repo = repo();
+        let rate = |date: &str, close: f64, source: &str| FxRate {
+            pair: "USDNGN".to_string(),
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            open: None,
+            high: None,
+            low: None,
+            close,
+            change_pct: None,
+            source: Some(source.to_string()),
+            scraped_at: Utc::now().naive_utc(),
+        };
+        repo.upsert_fx_rates(&[
+            rate("2024-01-02", 1000.0, "cbn"),
+            rate("2024-01-02", 1050.0, "investing.com"),
+            rate("2024-01-03", 1010.0, "cbn"),
+            rate("2024-01-04", 1080.0, "investing.com"),
+        ])
+        .unwrap();
+
+        // Only 2024-01-02 is quoted by both sources.
+        let spread = repo.fx_source_spread("USDNGN", "cbn", "investing.com").unwrap();
+        assert_eq!(spread.len(), 1);
+        assert_eq!(spread[0].0, NaiveDate::from_ymd_opt(2024, 1, 2).unwrap());
+        assert!((spread[0].1 - 5.0).abs() < 1e-9);
+        let reversed = repo.fx_source_spread("USDNGN", "investing.com", "cbn").unwrap();
+        assert!((reversed[0].1 + 50.0 / 1050.0 * 100.0).abs() < 1e-9);
+        assert!(repo.fx_source_spread("EURNGN", "cbn", "investing.com").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_bars_in_range_downsamples_to_max_points() {
+        let repo = repo();
+        let start = NaiveDate::from_ymd_opt(2015, 1, 1).unwrap();
+        let bars: Vec<DailyBar> = (0..3000)
+            .map(|i| {
+                let mut b = bar("DANGCEM", "2015-01-01", 100.0 + i as f64);
+                b.date = start + chrono::Days::new(i);
+                b.high = Some(b.close + 1.0);
+                b.low = Some(b.close - 1.0);
+                b
+            })
+            .collect();
+        repo.upsert_daily_bars(&bars).unwrap();
+
+        let raw = repo.bars_in_range("DANGCEM", None, DateRange::default(), Some(5000)).unwrap();
+        assert_eq!(raw.len(), 3000);
+
+        let sampled = repo.bars_in_range("DANGCEM", None, DateRange::default(), Some(300)).unwrap();
+        assert!(sampled.len() <= 300 && sampled.len() >= 250, "{}", sampled.len());
+        let (first, last) = (&sampled[0], sampled.last().unwrap());
+        assert_eq!(first.date, start);
+        assert_eq!(last.close, 3099.0);
+        assert!((bars[2999].date - last.date).num_days() < 10);
+        // Each bucket keeps the extremes of the bars it covers.
+        assert_eq!(first.low, Some(99.0));
+        assert_eq!(first.high, Some(first.close + 1.0));
+    }
+
+    #[test]
+    fn test_query_sql_returns_headers_and_rows() {
+        let repo = repo();
+        repo.upsert_daily_bars(&[
+            bar("DANGCEM", "2024-01-02", 300.0),
+            bar("GTCO", "2024-01-02", 40.0),
+        ])
+        .unwrap();
+
+        let (headers, rows) = repo
+            .query_sql("select symbol, close FROM daily_bars ORDER BY symbol;")
+            .unwrap();
+        assert_eq!(headers, ["symbol", "close"]);
+        assert_eq!(rows, [["DANGCEM", "300.0"], ["GTCO", "40.0"]]);
+
+        let (headers, rows) = repo
+            .query_sql("WITH t AS (SELECT COUNT(*) AS n FROM daily_bars) SELECT n FROM t")
+            .unwrap();
+        assert_eq!(headers, ["n"]);
+        assert_eq!(rows, [["2"]]);
+    }
+
+    #[test]
+    fn test_query_sql_refuses_writes() {
+        let repo = repo();
+        repo.upsert_daily_bars(&[bar("DANGCEM", "2024-01-02", 300.0)]).unwrap();
+
+        for sql in [
+            "INSERT INTO tickers (symbol) VALUES ('GTCO')",
+            "DROP TABLE daily_bars",
+            "delete from daily_bars",
+            "SELECT 1) AS q; DROP TABLE daily_bars; SELECT (1",
+            "SELECT 1; DROP TABLE daily_bars",
+        ] {
+            let err = repo.query_sql(sql).unwrap_err();
+            assert!(err.to_string().contains("read-only"), "{}: {}", sql, err);
+        }
+        assert_eq!(repo.bar_count().unwrap(), 1);
+        assert_eq!(repo.ticker_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_export_parquet_round_trips_and_checks_table() {
+        let repo = repo();
+        repo.upsert_daily_bars(&[
+            bar("DANGCEM", "2024-01-02", 300.0),
+            bar("GTCO", "2024-01-02", 40.0),
+        ])
+        .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("bars.parquet");
+        assert_eq!(repo.export_parquet("daily_bars", &out).unwrap(), 2);
+        // Overwrites rather than failing on an existing file.
+        assert_eq!(repo.export_parquet("daily_bars", &out).unwrap(), 2);
+        let sql = format!("SELECT symbol FROM read_parquet('{}') ORDER BY 1", out.display());
+        let (_, rows) = repo.query_sql(&sql).unwrap();
+        assert_eq!(rows, vec![vec!["DANGCEM".to_string()], vec!["GTCO".to_string()]]);
+
+        assert!(repo.export_parquet("audit_log", &out).is_err());
+        assert!(repo.export_parquet("daily_bars; DROP TABLE tickers", &out).is_err());
+    }
+
+    #[test]
+    fn test_find_date_gaps_skips_weekends_only() {
+        let repo = repo();
+        repo.upsert_daily_bars(&[
+            bar("GTCO", "2024-01-04", 40.0),
+            bar("GTCO", "2024-01-05", 40.5),
+            // weekend: 3 days
+            bar("GTCO", "2024-01-08", 41.0),
+            // a missing week
+            bar("GTCO", "2024-01-16", 41.5),
+            bar("DANGCEM", "2024-02-01", 600.0),
+        ])
+        .unwrap();
+
+        let d = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        let all = DateRange::default();
+        assert_eq!(
+            repo.find_date_gaps("GTCO", None, all, 3).unwrap(),
+            vec![(d("2024-01-08"), d("2024-01-16"))]
+        );
+        assert_eq!(repo.find_date_gaps("GTCO", None, all, 2).unwrap().len(), 2);
+        assert!(repo.find_date_gaps("DANGCEM", None, all, 3).unwrap().is_empty());
+
+        // Only dates inside the window count; its edges aren't gaps.
+        let january_first_week = DateRange::new(Some(d("2024-01-01")), Some(d("2024-01-10")));
+        assert!(repo.find_date_gaps("GTCO", None, january_first_week, 3).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_adjusted_close_divides_out_later_splits() {
+        use crate::models::ActionType;
+
+        let repo = repo();
+        repo.upsert_daily_bars(&[
+            bar("MTNN", "2024-02-28", 400.0),
+            bar("MTNN", "2024-02-29", 410.0),
+            bar("MTNN", "2024-03-01", 206.0),
+        ])
+        .unwrap();
+        let action = |ex_date: &str, action_type, ratio_or_amount| CorporateAction {
+            symbol: "MTNN".to_string(),
+            ex_date: NaiveDate::parse_from_str(ex_date, "%Y-%m-%d").unwrap(),
+            action_type,
+            ratio_or_amount,
+        };
+        repo.upsert_corporate_actions(&[
+            action("2024-03-01", ActionType::Split, 2.0),
+            action("2024-02-29", ActionType::Dividend, 5.0),
+        ])
+        .unwrap();
+
+        let closes: Vec<f64> =
+            repo.adjusted_close_series("MTNN", None).unwrap().into_iter().map(|(_, c)| c).collect();
+        assert_eq!(closes, vec![200.0, 205.0, 206.0]);
+    }
+
+    #[test]
+    fn test_readonly_open_needs_existing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing.duckdb");
+        let err = Repository::open_readonly(&path, 0).err().unwrap();
+        assert!(err.to_string().contains("does not exist"));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_bulk_insert_50k_bars() {
+        let repo = repo();
+        let start = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+        let bars: Vec<DailyBar> = (0..50_000)
+            .map(|i| {
+                let mut b = bar(&format!("SYM{}", i % 10), "2000-01-01", 10.0 + (i % 7) as f64);
+                b.date = start + chrono::Duration::days(i / 10);
+                b
+            })
+            .collect();
+
+        let t = std::time::Instant::now();
+        assert_eq!(repo.bulk_insert_bars(&bars).unwrap(), 50_000);
+        debug!("bulk_insert_bars: 50k rows in {:?}", t.elapsed());
+        assert_eq!(repo.bar_count().unwrap(), 50_000);
+
+        // Re-running merges; repeats within a batch keep the last one
+        let mut again = bars[..2].to_vec();
+        again.push(DailyBar { close: 99.0, ..bars[0].clone() });
+        repo.bulk_insert_bars(&again).unwrap();
+        assert_eq!(repo.bar_count().unwrap(), 50_000);
+        assert_eq!(repo.last_bar("SYM0").unwrap().unwrap().date, bars[49_990].date);
+        let first =
+            repo.bars_in_range("SYM0", None, DateRange::new(None, Some(start)), None).unwrap();
+        assert_eq!(first[0].close, 99.0);
+    }
+
+    #[test]
+    fn test_resample_weekly_and_monthly() {
+        let repo = repo();
+        let mut mon = bar("GTCO", "2024-01-29", 40.0);
+        (mon.open, mon.high, mon.low, mon.volume) = (Some(39.0), Some(41.0), Some(38.5), Some(100));
+        let mut wed = bar("GTCO", "2024-01-31", 42.0);
+        wed.volume = Some(50);
+        repo.upsert_daily_bars(&[
+            mon,
+            wed,
+            // Close-only days in the next week, which also starts February
+            bar("GTCO", "2024-02-01", 43.0),
+            bar("GTCO", "2024-02-05", 44.0),
+            bar("GTCO", "2024-02-06", 41.0),
+        ])
+        .unwrap();
+
+        let weekly = repo.resample("GTCO", None, DateRange::default(), Resample::Weekly).unwrap();
+        assert_eq!(weekly.len(), 2);
+        let w = &weekly[0];
+        assert_eq!(w.date, NaiveDate::from_ymd_opt(2024, 1, 29).unwrap());
+        assert_eq!((w.open, w.high, w.low, w.close), (Some(39.0), Some(43.0), Some(38.5), 43.0));
+        assert_eq!(w.volume, Some(150));
+        let w = &weekly[1];
+        assert_eq!((w.open, w.high, w.low, w.close), (Some(44.0), Some(44.0), Some(41.0), 41.0));
+
+        let monthly = repo.resample("GTCO", None, DateRange::default(), Resample::Monthly).unwrap();
+        assert_eq!(monthly.len(), 2);
+        assert_eq!(monthly[1].date, NaiveDate::from_ymd_opt(2024, 2, 1).unwrap());
+        assert_eq!(monthly[1].open, Some(43.0));
+    }
+}
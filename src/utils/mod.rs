@@ -1,3 +1,6 @@
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate, Weekday};
+use std::io::{self, BufRead, IsTerminal, Write};
 use std::time::{Duration, Instant};
 use tracing::info;
 
@@ -48,10 +51,85 @@ pub fn fmt_number(n: i64) -> String {
     result.chars().rev().collect()
 }
 
+/// Weekdays in `[from, to]` that aren't in `holidays` — the sessions an
+/// exchange trading Mon–Fri would be expected to hold.
+pub fn expected_trading_days(from: NaiveDate, to: NaiveDate, holidays: &[NaiveDate]) -> i64 {
+    from.iter_days()
+        .take_while(|d| *d <= to)
+        .filter(|d| !matches!(d.weekday(), Weekday::Sat | Weekday::Sun))
+        .filter(|d| !holidays.contains(d))
+        .count() as i64
+}
+
+/// The latest session on or before `today`: skips back over weekends and
+/// `holidays`.
+pub fn last_trading_day(today: NaiveDate, holidays: &[NaiveDate]) -> NaiveDate {
+    let mut day = today;
+    while matches!(day.weekday(), Weekday::Sat | Weekday::Sun) || holidays.contains(&day) {
+        day = day.pred_opt().expect("date in range");
+    }
+    day
+}
+
+/// Weekdays between the first and last of `trading_dates` (sorted) on which
+/// no trading happened — likely exchange holidays.
+pub fn holiday_candidates(trading_dates: &[NaiveDate]) -> Vec<NaiveDate> {
+    let (Some(&first), Some(&last)) = (trading_dates.first(), trading_dates.last()) else {
+        return Vec::new();
+    };
+    let traded: std::collections::HashSet<&NaiveDate> = trading_dates.iter().collect();
+    first
+        .iter_days()
+        .take_while(|d| *d <= last)
+        .filter(|d| !matches!(d.weekday(), Weekday::Sat | Weekday::Sun))
+        .filter(|d| !traded.contains(d))
+        .collect()
+}
+
+/// Ask a yes/no question on the terminal; anything but "y"/"yes" is a no.
+/// Errors when stdin isn't a TTY, so scripts must confirm explicitly (`--yes`).
+pub fn confirm(prompt: &str) -> Result<bool> {
+    anyhow::ensure!(io::stdin().is_terminal(), "not a terminal; pass --yes to confirm");
+    print!("{} [y/N] ", prompt);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_expected_trading_days() {
+        let d = |s| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        // 2024 has 262 weekdays
+        assert_eq!(expected_trading_days(d("2024-01-01"), d("2024-12-31"), &[]), 262);
+        // Mon 1 Jan – Sun 7 Jan with New Year's Day off
+        assert_eq!(expected_trading_days(d("2024-01-01"), d("2024-01-07"), &[d("2024-01-01")]), 4);
+        // A Saturday holiday doesn't double-count
+        assert_eq!(expected_trading_days(d("2024-01-06"), d("2024-01-06"), &[d("2024-01-06")]), 0);
+    }
+
+    #[test]
+    fn test_holiday_candidates_are_untraded_weekdays() {
+        let d = |s| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        // Fri 29 Dec 2023 … Wed 3 Jan 2024, New Year's Day (Mon) not traded
+        let traded = [d("2023-12-29"), d("2024-01-02"), d("2024-01-03")];
+        assert_eq!(holiday_candidates(&traded), vec![d("2024-01-01")]);
+        assert!(holiday_candidates(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_last_trading_day_skips_weekends_and_holidays() {
+        let d = |s| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        assert_eq!(last_trading_day(d("2024-01-03"), &[]), d("2024-01-03"));
+        assert_eq!(last_trading_day(d("2024-01-07"), &[]), d("2024-01-05"));
+        // Mon 1 Jan is New Year's Day: back to Fri 29 Dec
+        assert_eq!(last_trading_day(d("2024-01-01"), &[d("2024-01-01")]), d("2023-12-29"));
+    }
+
     #[test]
     fn test_fmt_number() {
         assert_eq!(fmt_number(1_234_567), "1,234,567");